@@ -0,0 +1,76 @@
+//! RPC interface for the AMM module.
+
+use std::sync::Arc;
+
+pub use amm_runtime_api::AmmApi as AmmRuntimeApi;
+use amm_runtime_api::Route;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+#[rpc]
+pub trait AmmApi<BlockHash, Hash, Balance> {
+    /// The route through the existing pools that receives the most `asset_out` for
+    /// `amount_in` of `asset_in`.
+    #[rpc(name = "amm_bestRoute")]
+    fn best_route(
+        &self,
+        asset_in: Hash,
+        asset_out: Hash,
+        amount_in: Balance,
+        at: Option<BlockHash>,
+    ) -> Result<Option<Route<Hash, Balance>>>;
+}
+
+/// Error code of a failed runtime API call.
+const RUNTIME_ERROR: i64 = 1;
+
+fn runtime_error<E: std::fmt::Debug>(e: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the AMM module.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+/// Implements the `AmmApi` RPC trait with the runtime API of a client.
+pub struct Amm<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Amm<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Amm {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block, Hash, Balance> AmmApi<<Block as BlockT>::Hash, Hash, Balance> for Amm<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: AmmRuntimeApi<Block, Hash, Balance>,
+    Hash: Codec + Serialize + DeserializeOwned,
+    Balance: Codec + Serialize + DeserializeOwned,
+{
+    fn best_route(
+        &self,
+        asset_in: Hash,
+        asset_out: Hash,
+        amount_in: Balance,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Route<Hash, Balance>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.best_route(&at, asset_in, asset_out, amount_in)
+            .map_err(runtime_error)
+    }
+}
@@ -0,0 +1,28 @@
+//! Runtime API definition for the AMM module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+
+/// Assets a swap goes through and the amount of the last one it receives.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct Route<Hash, Balance> {
+    pub path: Vec<Hash>,
+    pub amount_out: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+    pub trait AmmApi<Hash, Balance> where
+        Hash: Codec,
+        Balance: Codec,
+    {
+        /// The route through the existing pools that receives the most `asset_out` for
+        /// `amount_in` of `asset_in`.
+        fn best_route(asset_in: Hash, asset_out: Hash, amount_in: Balance) -> Option<Route<Hash, Balance>>;
+    }
+}
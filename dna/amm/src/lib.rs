@@ -0,0 +1,202 @@
+//! # AMM module
+//!
+//! Constant product liquidity pools, one per exchange pair, trading next to the pair's order
+//! book. A pool holds its reserves in an account of its own and mints shares of the pool as
+//! an asset to the liquidity providers.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The above line is needed to compile the Wasm binaries.
+
+use codec::{Decode, Encode};
+use core::convert::{TryFrom, TryInto};
+use primitives::U256;
+use rstd::{prelude::*, result};
+use sp_runtime::traits::{AccountIdConversion, CheckedAdd, CheckedSub, Zero};
+use sp_runtime::{ModuleId, Permill, RuntimeDebug};
+use support::{
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
+    traits::Get,
+    StorageMap,
+};
+use system::ensure_signed;
+
+mod liquidity;
+#[cfg(test)]
+mod mock;
+mod route;
+mod swap;
+#[cfg(test)]
+mod tests;
+
+/// Parent account of the pool accounts
+const MODULE_ID: ModuleId = ModuleId(*b"dna/pool");
+
+pub trait Trait: dex::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Part of each swap's input kept by the pool for its liquidity providers
+    type SwapFee: Get<Permill>;
+    /// Most pools a routed swap goes through
+    type MaxRouteHops: Get<u32>;
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Pool<AccountId, Hash, Balance> {
+    /// hash of the exchange pair the pool trades
+    pub hash: Hash,
+    /// account holding the reserves
+    pub account: AccountId,
+    pub base: Hash,
+    pub quote: Hash,
+    pub base_reserve: Balance,
+    pub quote_reserve: Balance,
+    /// asset of the pool shares, its supply is the number of shares
+    pub share_asset: Hash,
+}
+
+pub type PoolOf<T> =
+    Pool<<T as system::Trait>::AccountId, <T as system::Trait>::Hash, <T as balances::Trait>::Balance>;
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// Exchange pair already has a pool
+        PoolExisted,
+        /// No pool for the exchange pair
+        NoMatchingPool,
+        /// Amount must be above zero
+        ZeroAmount,
+        /// Asset is neither the base nor the quote of the pool
+        AssetNotInPool,
+        /// Pool reserves can not cover the trade
+        InsufficientLiquidity,
+        /// Trade would execute worse than the given limit
+        SlippageExceeded,
+        /// Route needs two to `MaxRouteHops + 1` assets
+        InvalidRoute,
+        /// Overflow
+        OverflowError,
+        /// Number conversion failed
+        NumberCastError,
+    }
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+	{
+		// (accountId, exchangePairHash, shareAssetHash)
+		PoolCreated(AccountId, Hash, Hash),
+
+		// (accountId, exchangePairHash, base amount, quote amount, shares minted)
+		LiquidityAdded(AccountId, Hash, Balance, Balance, Balance),
+
+		// (accountId, exchangePairHash, base amount, quote amount, shares burned)
+		LiquidityRemoved(AccountId, Hash, Balance, Balance, Balance),
+
+		// (accountId, exchangePairHash, asset in, amount in, amount out)
+		Swapped(AccountId, Hash, Hash, Balance, Balance),
+
+		// (accountId, asset path, amount in, amount out)
+		RouteSwapped(AccountId, Vec<Hash>, Balance, Balance),
+	}
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as AmmStorage {
+        /// ExchangePairHash => Pool
+        pub Pools get(fn pool): map hasher(blake2_256) T::Hash => Option<PoolOf<T>>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event() = default;
+
+        type Error = Error<T>;
+
+        const SwapFee: Permill = T::SwapFee::get();
+        const MaxRouteHops: u32 = T::MaxRouteHops::get();
+
+        /// # Create the pool of an exchange pair with its first liquidity
+        /// * `origin` - signer
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `base_amount` - base deposited, sets the initial price with `quote_amount`
+        /// * `quote_amount` - quote deposited
+        pub fn create_pool(origin, ep_hash: T::Hash, base_amount: T::Balance, quote_amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_create_pool(sender, ep_hash, base_amount, quote_amount)
+        }
+
+        /// # Add liquidity at the pool's price
+        /// * `origin` - signer
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `base_amount` - base deposited
+        /// * `max_quote_amount` - most quote deposited along with `base_amount`
+        pub fn add_liquidity(origin, ep_hash: T::Hash, base_amount: T::Balance, max_quote_amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_add_liquidity(sender, ep_hash, base_amount, max_quote_amount)
+        }
+
+        /// # Withdraw liquidity by burning pool shares
+        /// * `origin` - signer
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `shares` - pool shares burned
+        /// * `min_base_amount` - least base withdrawn
+        /// * `min_quote_amount` - least quote withdrawn
+        pub fn remove_liquidity(origin, ep_hash: T::Hash, shares: T::Balance, min_base_amount: T::Balance, min_quote_amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_remove_liquidity(sender, ep_hash, shares, min_base_amount, min_quote_amount)
+        }
+
+        /// # Swap an exact amount of one asset of the pool for the other
+        /// * `origin` - signer
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `asset_in` - base or quote of the pair, the asset paid
+        /// * `amount_in` - amount paid
+        /// * `min_amount_out` - least amount of the other asset received
+        pub fn swap(origin, ep_hash: T::Hash, asset_in: T::Hash, amount_in: T::Balance, min_amount_out: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_swap(sender, ep_hash, asset_in, amount_in, min_amount_out)?;
+
+            Ok(())
+        }
+
+        /// # Swap through the pools of several exchange pairs in one go
+        /// * `origin` - signer
+        /// * `path` - assets traded through, from the asset paid to the asset received
+        /// * `amount_in` - amount of the first asset paid
+        /// * `min_amount_out` - least amount of the last asset received
+        pub fn swap_route(origin, path: Vec<T::Hash>, amount_in: T::Balance, min_amount_out: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_swap_route(sender, path, amount_in, min_amount_out)?;
+
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Account holding the reserves of the pool of an exchange pair.
+    pub fn pool_account(ep_hash: T::Hash) -> T::AccountId {
+        MODULE_ID.into_sub_account(ep_hash)
+    }
+
+    fn into_u256(amount: T::Balance) -> result::Result<U256, DispatchError> {
+        let amount: u128 = amount.try_into().map_err(|_| Error::<T>::NumberCastError)?;
+        Ok(U256::from(amount))
+    }
+
+    fn from_u256(amount: U256) -> result::Result<T::Balance, DispatchError> {
+        let amount: u128 = amount.try_into().map_err(|_| Error::<T>::OverflowError)?;
+        T::Balance::try_from(amount).map_err(|_| Error::<T>::NumberCastError.into())
+    }
+}
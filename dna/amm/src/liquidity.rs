@@ -0,0 +1,160 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn do_create_pool(
+        sender: T::AccountId,
+        ep_hash: T::Hash,
+        base_amount: T::Balance,
+        quote_amount: T::Balance,
+    ) -> DispatchResult {
+        <dex::Module<T>>::transactional(|| {
+            ensure!(Self::pool(ep_hash).is_none(), Error::<T>::PoolExisted);
+            let ep = <dex::Module<T>>::exchange_pair(ep_hash)
+                .ok_or(dex::Error::<T>::NoMatchingExchangePair)?;
+
+            let account = Self::pool_account(ep_hash);
+            let share_asset =
+                <assets::Module<T>>::create_asset(account.clone(), b"DNA-LP".to_vec(), Zero::zero())?;
+
+            <Pools<T>>::insert(
+                ep_hash,
+                Pool {
+                    hash: ep_hash,
+                    account,
+                    base: ep.base,
+                    quote: ep.quote,
+                    base_reserve: Zero::zero(),
+                    quote_reserve: Zero::zero(),
+                    share_asset,
+                },
+            );
+
+            Self::deposit_event(RawEvent::PoolCreated(sender.clone(), ep_hash, share_asset));
+
+            Self::do_add_liquidity(sender, ep_hash, base_amount, quote_amount)
+        })
+    }
+
+    /// Deposit `base_amount` and the quote amount worth it at the pool's price. The first
+    /// deposit into an empty pool sets the price and mints the geometric mean of the amounts
+    /// as shares.
+    pub fn do_add_liquidity(
+        sender: T::AccountId,
+        ep_hash: T::Hash,
+        base_amount: T::Balance,
+        max_quote_amount: T::Balance,
+    ) -> DispatchResult {
+        <dex::Module<T>>::transactional(|| {
+            ensure!(
+                !base_amount.is_zero() && !max_quote_amount.is_zero(),
+                Error::<T>::ZeroAmount
+            );
+            let mut pool = Self::pool(ep_hash).ok_or(Error::<T>::NoMatchingPool)?;
+
+            let base_amount_u256 = Self::into_u256(base_amount)?;
+            let total_shares = Self::total_shares(&pool)?;
+
+            let (quote_amount, shares) = if total_shares.is_zero() || pool.base_reserve.is_zero() {
+                let shares = (base_amount_u256 * Self::into_u256(max_quote_amount)?).integer_sqrt();
+                (max_quote_amount, shares)
+            } else {
+                let base_reserve = Self::into_u256(pool.base_reserve)?;
+                let quote_reserve = Self::into_u256(pool.quote_reserve)?;
+
+                // rounded up, in the pool's favor
+                let quote_amount =
+                    (base_amount_u256 * quote_reserve + base_reserve - U256::one()) / base_reserve;
+                let shares = base_amount_u256 * total_shares / base_reserve;
+                (Self::from_u256(quote_amount)?, shares)
+            };
+            ensure!(quote_amount <= max_quote_amount, Error::<T>::SlippageExceeded);
+
+            let shares = Self::from_u256(shares)?;
+            ensure!(!shares.is_zero(), Error::<T>::ZeroAmount);
+
+            <assets::Module<T>>::transfer(sender.clone(), pool.base, pool.account.clone(), base_amount)?;
+            <assets::Module<T>>::transfer(sender.clone(), pool.quote, pool.account.clone(), quote_amount)?;
+            <assets::Module<T>>::mint_to(pool.share_asset, sender.clone(), shares)?;
+
+            pool.base_reserve = pool
+                .base_reserve
+                .checked_add(&base_amount)
+                .ok_or(Error::<T>::OverflowError)?;
+            pool.quote_reserve = pool
+                .quote_reserve
+                .checked_add(&quote_amount)
+                .ok_or(Error::<T>::OverflowError)?;
+            <Pools<T>>::insert(ep_hash, pool);
+
+            Self::deposit_event(RawEvent::LiquidityAdded(
+                sender,
+                ep_hash,
+                base_amount,
+                quote_amount,
+                shares,
+            ));
+
+            Ok(())
+        })
+    }
+
+    /// Burn pool shares for their part of both reserves.
+    pub fn do_remove_liquidity(
+        sender: T::AccountId,
+        ep_hash: T::Hash,
+        shares: T::Balance,
+        min_base_amount: T::Balance,
+        min_quote_amount: T::Balance,
+    ) -> DispatchResult {
+        <dex::Module<T>>::transactional(|| {
+            ensure!(!shares.is_zero(), Error::<T>::ZeroAmount);
+            let mut pool = Self::pool(ep_hash).ok_or(Error::<T>::NoMatchingPool)?;
+
+            let total_shares = Self::total_shares(&pool)?;
+            ensure!(!total_shares.is_zero(), Error::<T>::InsufficientLiquidity);
+
+            // rounded down, in the pool's favor
+            let shares_u256 = Self::into_u256(shares)?;
+            let base_amount = Self::from_u256(
+                shares_u256 * Self::into_u256(pool.base_reserve)? / total_shares,
+            )?;
+            let quote_amount = Self::from_u256(
+                shares_u256 * Self::into_u256(pool.quote_reserve)? / total_shares,
+            )?;
+            ensure!(
+                base_amount >= min_base_amount && quote_amount >= min_quote_amount,
+                Error::<T>::SlippageExceeded
+            );
+
+            <assets::Module<T>>::burn_from(sender.clone(), pool.share_asset, shares)?;
+            <assets::Module<T>>::transfer(pool.account.clone(), pool.base, sender.clone(), base_amount)?;
+            <assets::Module<T>>::transfer(pool.account.clone(), pool.quote, sender.clone(), quote_amount)?;
+
+            pool.base_reserve = pool
+                .base_reserve
+                .checked_sub(&base_amount)
+                .ok_or(Error::<T>::InsufficientLiquidity)?;
+            pool.quote_reserve = pool
+                .quote_reserve
+                .checked_sub(&quote_amount)
+                .ok_or(Error::<T>::InsufficientLiquidity)?;
+            <Pools<T>>::insert(ep_hash, pool);
+
+            Self::deposit_event(RawEvent::LiquidityRemoved(
+                sender,
+                ep_hash,
+                base_amount,
+                quote_amount,
+                shares,
+            ));
+
+            Ok(())
+        })
+    }
+
+    fn total_shares(pool: &PoolOf<T>) -> result::Result<U256, DispatchError> {
+        let share_asset =
+            <assets::Module<T>>::asset(pool.share_asset).ok_or(Error::<T>::NoMatchingPool)?;
+        Self::into_u256(share_asset.total_supply)
+    }
+}
@@ -0,0 +1,178 @@
+//! Test runtime of the AMM module.
+
+use super::*;
+use primitives::H256;
+use sp_runtime::{
+    testing::{Header, TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+use support::{impl_outer_origin, parameter_types, weights::Weight};
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = system {}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 1;
+}
+
+impl balances::Trait for Test {
+    type Balance = u128;
+    type DustRemoval = ();
+    type Event = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = system::Module<Test>;
+}
+
+impl assets::Trait for Test {
+    type Event = ();
+}
+
+parameter_types! {
+    pub const PriceFactor: u128 = 1;
+    pub const BlocksPerDay: u32 = 6 * 60 * 24;
+    pub const OpenedOrdersArrayCap: u8 = 20;
+    pub const CommitRevealWindow: u64 = 10;
+    pub const UnrevealedCommitmentPenalty: Perbill = Perbill::from_percent(10);
+    pub const OrderGroupCap: u8 = 4;
+    pub const OrderDeposit: u128 = 10;
+    pub const StopOrdersCap: u32 = 4;
+    pub const TwapObservationPeriod: u64 = 10;
+    pub const TwapObservationCap: u32 = 10;
+    pub const ListingBond: u128 = 1_000;
+    pub const TradingFee: Permill = Permill::zero();
+}
+
+impl dex::Trait for Test {
+    type Event = ();
+    type Price = u128;
+    type PriceFactor = PriceFactor;
+    type BlocksPerDay = BlocksPerDay;
+    type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
+    type GovernanceOrigin = system::EnsureRoot<u64>;
+    type CommitRevealWindow = CommitRevealWindow;
+    type UnrevealedCommitmentPenalty = UnrevealedCommitmentPenalty;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type OrderGroupCap = OrderGroupCap;
+    type OrderDeposit = OrderDeposit;
+    type StopOrdersCap = StopOrdersCap;
+    type TwapObservationPeriod = TwapObservationPeriod;
+    type TwapObservationCap = TwapObservationCap;
+    type ReferencePrice = ();
+    type ListingBond = ListingBond;
+    type ListingOrigin = system::EnsureRoot<u64>;
+    type ListingSlash = ();
+    type TradingFee = TradingFee;
+    type OnTradingFee = ();
+}
+
+parameter_types! {
+    pub const SwapFee: Permill = Permill::from_percent(1);
+    pub const MaxRouteHops: u32 = 2;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type SwapFee = SwapFee;
+    type MaxRouteHops = MaxRouteHops;
+}
+
+pub type Assets = assets::Module<Test>;
+pub type Exchange = dex::Module<Test>;
+pub type Amm = Module<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+
+pub const SUPPLY: u128 = 1_000_000_000;
+
+pub fn base() -> H256 {
+    Assets::genesis_asset_hash(b"BASE")
+}
+
+pub fn quote() -> H256 {
+    Assets::genesis_asset_hash(b"QUOTE")
+}
+
+pub fn other() -> H256 {
+    Assets::genesis_asset_hash(b"OTHER")
+}
+
+pub fn far() -> H256 {
+    Assets::genesis_asset_hash(b"FAR")
+}
+
+pub fn ep_hash() -> H256 {
+    pair(base(), quote())
+}
+
+pub fn pair(base: H256, quote: H256) -> H256 {
+    Exchange::exchange_pair_hash_by_base_quote(base, quote).expect("genesis exchange pair")
+}
+
+/// Alice owns `BASE`, `QUOTE`, `OTHER` and `FAR` and hands half of each supply to Bob. The
+/// exchange pairs link them as BASE/QUOTE, OTHER/QUOTE, BASE/OTHER and FAR/OTHER.
+pub fn new_test_ext() -> runtime_io::TestExternalities {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+    let holders = vec![(BOB, SUPPLY / 2)];
+    assets::GenesisConfig::<Test> {
+        assets: vec![
+            (ALICE, b"BASE".to_vec(), SUPPLY, holders.clone()),
+            (ALICE, b"QUOTE".to_vec(), SUPPLY, holders.clone()),
+            (ALICE, b"OTHER".to_vec(), SUPPLY, holders.clone()),
+            (ALICE, b"FAR".to_vec(), SUPPLY, holders),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    dex::GenesisConfig::<Test> {
+        exchange_pairs: vec![
+            (ALICE, b"BASE".to_vec(), b"QUOTE".to_vec(), dex::MatchingMode::Continuous, None),
+            (ALICE, b"OTHER".to_vec(), b"QUOTE".to_vec(), dex::MatchingMode::Continuous, None),
+            (ALICE, b"BASE".to_vec(), b"OTHER".to_vec(), dex::MatchingMode::Continuous, None),
+            (ALICE, b"FAR".to_vec(), b"OTHER".to_vec(), dex::MatchingMode::Continuous, None),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext = runtime_io::TestExternalities::new(t);
+    ext.execute_with(|| system::Module::<Test>::set_block_number(1));
+    ext
+}
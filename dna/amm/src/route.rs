@@ -0,0 +1,109 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Swap `amount_in` of the first asset of `path` through the pool of each pair of
+    /// neighboring assets in turn. All hops are reverted when any of them fails or the last
+    /// one receives less than `min_amount_out`.
+    pub fn do_swap_route(
+        sender: T::AccountId,
+        path: Vec<T::Hash>,
+        amount_in: T::Balance,
+        min_amount_out: T::Balance,
+    ) -> result::Result<T::Balance, DispatchError> {
+        ensure!(
+            path.len() >= 2 && path.len() <= T::MaxRouteHops::get() as usize + 1,
+            Error::<T>::InvalidRoute
+        );
+
+        <dex::Module<T>>::transactional(|| {
+            let mut amount = amount_in;
+            for hop in path.windows(2) {
+                let ep_hash = Self::exchange_pair_of(hop[0], hop[1])?;
+                amount = Self::do_swap(sender.clone(), ep_hash, hop[0], amount, Zero::zero())?;
+            }
+            ensure!(amount >= min_amount_out, Error::<T>::SlippageExceeded);
+
+            Self::deposit_event(RawEvent::RouteSwapped(sender, path, amount_in, amount));
+
+            Ok(amount)
+        })
+    }
+
+    /// The path from `asset_in` to `asset_out` through at most `MaxRouteHops` pools that
+    /// receives the most for `amount_in`, and the amount it receives.
+    pub fn best_route(
+        asset_in: T::Hash,
+        asset_out: T::Hash,
+        amount_in: T::Balance,
+    ) -> Option<(Vec<T::Hash>, T::Balance)> {
+        if asset_in == asset_out || amount_in.is_zero() {
+            return None;
+        }
+
+        let pools: Vec<PoolOf<T>> = (0..<dex::Module<T>>::exchange_pair_index())
+            .filter_map(<dex::Module<T>>::exchange_pair_hash_by_index)
+            .filter_map(Self::pool)
+            .collect();
+
+        let mut path = Vec::new();
+        path.push(asset_in);
+        let mut best = None;
+        Self::search_routes(&pools, asset_out, amount_in, &mut path, &mut best);
+
+        best
+    }
+
+    /// Extend `path` by each pool trading its last asset, depth first, keeping the route to
+    /// `asset_out` receiving the most in `best`.
+    fn search_routes(
+        pools: &[PoolOf<T>],
+        asset_out: T::Hash,
+        amount: T::Balance,
+        path: &mut Vec<T::Hash>,
+        best: &mut Option<(Vec<T::Hash>, T::Balance)>,
+    ) {
+        if path.len() > T::MaxRouteHops::get() as usize {
+            return;
+        }
+        let asset = match path.last() {
+            Some(asset) => *asset,
+            None => return,
+        };
+
+        for pool in pools {
+            let next = if pool.base == asset {
+                pool.quote
+            } else if pool.quote == asset {
+                pool.base
+            } else {
+                continue;
+            };
+            // no asset, and so no pool, is visited twice
+            if path.contains(&next) {
+                continue;
+            }
+
+            let amount_out = match Self::swap_amount_out(pool, asset, amount) {
+                Ok(amount_out) if !amount_out.is_zero() => amount_out,
+                _ => continue,
+            };
+
+            path.push(next);
+            if next == asset_out {
+                if best.as_ref().map_or(true, |(_, best_out)| amount_out > *best_out) {
+                    *best = Some((path.clone(), amount_out));
+                }
+            } else {
+                Self::search_routes(pools, asset_out, amount_out, path, best);
+            }
+            path.pop();
+        }
+    }
+
+    /// The exchange pair of two assets, in either orientation.
+    fn exchange_pair_of(a: T::Hash, b: T::Hash) -> result::Result<T::Hash, DispatchError> {
+        <dex::Module<T>>::exchange_pair_hash_by_base_quote(a, b)
+            .or_else(|| <dex::Module<T>>::exchange_pair_hash_by_base_quote(b, a))
+            .ok_or_else(|| dex::Error::<T>::NoMatchingExchangePair.into())
+    }
+}
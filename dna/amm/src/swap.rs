@@ -0,0 +1,72 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Swap `amount_in` of one asset of the pool for the other, returns the amount received.
+    pub fn do_swap(
+        sender: T::AccountId,
+        ep_hash: T::Hash,
+        asset_in: T::Hash,
+        amount_in: T::Balance,
+        min_amount_out: T::Balance,
+    ) -> result::Result<T::Balance, DispatchError> {
+        <dex::Module<T>>::transactional(|| {
+            ensure!(!amount_in.is_zero(), Error::<T>::ZeroAmount);
+            // a halted pair trades neither in its book nor in its pool
+            <dex::Module<T>>::ensure_pair_not_halted(ep_hash)?;
+
+            let mut pool = Self::pool(ep_hash).ok_or(Error::<T>::NoMatchingPool)?;
+            let amount_out = Self::swap_amount_out(&pool, asset_in, amount_in)?;
+            ensure!(!amount_out.is_zero(), Error::<T>::InsufficientLiquidity);
+            ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+            let (reserve_in, reserve_out, asset_out) = if asset_in == pool.base {
+                (&mut pool.base_reserve, &mut pool.quote_reserve, pool.quote)
+            } else {
+                (&mut pool.quote_reserve, &mut pool.base_reserve, pool.base)
+            };
+            *reserve_in = reserve_in
+                .checked_add(&amount_in)
+                .ok_or(Error::<T>::OverflowError)?;
+            *reserve_out = reserve_out
+                .checked_sub(&amount_out)
+                .ok_or(Error::<T>::InsufficientLiquidity)?;
+
+            <assets::Module<T>>::transfer(sender.clone(), asset_in, pool.account.clone(), amount_in)?;
+            <assets::Module<T>>::transfer(pool.account.clone(), asset_out, sender.clone(), amount_out)?;
+            <Pools<T>>::insert(ep_hash, pool);
+
+            Self::deposit_event(RawEvent::Swapped(
+                sender, ep_hash, asset_in, amount_in, amount_out,
+            ));
+
+            Ok(amount_out)
+        })
+    }
+
+    /// Amount of the other asset `amount_in` of `asset_in` buys from the pool. The fee is
+    /// taken off the input and stays in the pool, the rest trades at `x * y = k`.
+    pub fn swap_amount_out(
+        pool: &PoolOf<T>,
+        asset_in: T::Hash,
+        amount_in: T::Balance,
+    ) -> result::Result<T::Balance, DispatchError> {
+        let (reserve_in, reserve_out) = if asset_in == pool.base {
+            (pool.base_reserve, pool.quote_reserve)
+        } else if asset_in == pool.quote {
+            (pool.quote_reserve, pool.base_reserve)
+        } else {
+            return Err(Error::<T>::AssetNotInPool.into());
+        };
+        ensure!(
+            !reserve_in.is_zero() && !reserve_out.is_zero(),
+            Error::<T>::InsufficientLiquidity
+        );
+
+        let fee = T::SwapFee::get() * amount_in;
+        let amount_in = Self::into_u256(amount_in - fee)?;
+        let reserve_in = Self::into_u256(reserve_in)?;
+        let reserve_out = Self::into_u256(reserve_out)?;
+
+        Self::from_u256(reserve_out * amount_in / (reserve_in + amount_in))
+    }
+}
@@ -0,0 +1,288 @@
+//! Tests of the AMM module.
+
+use crate::mock::*;
+use crate::Error;
+use primitives::H256;
+use support::{assert_noop, assert_ok};
+
+fn balance(who: u64, asset: H256) -> u128 {
+    Assets::free_balance_of((who, asset))
+}
+
+fn pool() -> crate::PoolOf<Test> {
+    Amm::pool(ep_hash()).expect("pool created")
+}
+
+fn share_supply() -> u128 {
+    Assets::asset(pool().share_asset).expect("share asset").total_supply
+}
+
+/// Pool shares Alice and Bob hold.
+fn shares_held() -> u128 {
+    balance(ALICE, pool().share_asset) + balance(BOB, pool().share_asset)
+}
+
+#[test]
+fn first_deposit_mints_the_geometric_mean_of_the_amounts() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000, 4_000));
+
+        let pool = pool();
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_000, 4_000));
+        assert_eq!(balance(pool.account, base()), 1_000);
+        assert_eq!(balance(pool.account, quote()), 4_000);
+        assert_eq!(balance(ALICE, pool.share_asset), 2_000);
+        assert_eq!(share_supply(), 2_000);
+
+        assert_noop!(
+            Amm::create_pool(Origin::signed(BOB), ep_hash(), 1_000, 4_000),
+            Error::<Test>::PoolExisted
+        );
+    });
+}
+
+#[test]
+fn later_deposits_mint_shares_pro_rata_and_pay_the_quote_rounded_up() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000, 3_001));
+        assert_eq!(share_supply(), 1_732);
+
+        // 10 base is worth 30.01 quote, rounded up to 31
+        assert_noop!(
+            Amm::add_liquidity(Origin::signed(BOB), ep_hash(), 10, 30),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(Amm::add_liquidity(Origin::signed(BOB), ep_hash(), 10, 40));
+
+        // 17.32 shares, rounded down
+        let pool = pool();
+        assert_eq!(balance(BOB, pool.share_asset), 17);
+        assert_eq!(balance(BOB, base()), SUPPLY / 2 - 10);
+        assert_eq!(balance(BOB, quote()), SUPPLY / 2 - 31);
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_010, 3_032));
+        assert_eq!(share_supply(), 1_749);
+    });
+}
+
+#[test]
+fn removing_liquidity_pays_out_rounded_down() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000, 3_001));
+        assert_ok!(Amm::add_liquidity(Origin::signed(BOB), ep_hash(), 10, 40));
+
+        // 17 of 1749 shares are worth 9.82 base and 29.47 quote
+        assert_noop!(
+            Amm::remove_liquidity(Origin::signed(BOB), ep_hash(), 17, 10, 0),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_noop!(
+            Amm::remove_liquidity(Origin::signed(BOB), ep_hash(), 18, 0, 0),
+            assets::Error::<Test>::BalanceNotEnough
+        );
+        assert_ok!(Amm::remove_liquidity(Origin::signed(BOB), ep_hash(), 17, 9, 29));
+
+        let pool = pool();
+        assert_eq!(balance(BOB, pool.share_asset), 0);
+        assert_eq!(balance(BOB, base()), SUPPLY / 2 - 1);
+        assert_eq!(balance(BOB, quote()), SUPPLY / 2 - 2);
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_001, 3_003));
+        assert_eq!(share_supply(), 1_732);
+    });
+}
+
+#[test]
+fn swap_keeps_the_fee_in_the_pool_and_the_product_of_the_reserves_grows() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+
+        // 1% of 10_000 is kept, 9_900 trades at x * y = k for 9_802.95
+        assert_ok!(Amm::swap(Origin::signed(BOB), ep_hash(), base(), 10_000, 0));
+
+        let pool = pool();
+        assert_eq!(balance(BOB, base()), SUPPLY / 2 - 10_000);
+        assert_eq!(balance(BOB, quote()), SUPPLY / 2 + 9_802);
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_010_000, 990_198));
+        assert_eq!(balance(pool.account, base()), pool.base_reserve);
+        assert_eq!(balance(pool.account, quote()), pool.quote_reserve);
+
+        // without the fee the product holds up to the rounding of the output
+        assert!((pool.base_reserve - 100) * pool.quote_reserve >= 1_000_000 * 1_000_000);
+        assert_eq!(pool.base_reserve * pool.quote_reserve, 1_000_099_980_000);
+
+        // and the other way round
+        assert_ok!(Amm::swap(Origin::signed(BOB), ep_hash(), quote(), 9_802, 0));
+
+        let k = pool.base_reserve * pool.quote_reserve;
+        let pool = self::pool();
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_000_198, 1_000_000));
+        assert!(pool.base_reserve * pool.quote_reserve > k);
+    });
+}
+
+#[test]
+fn swap_fails_below_the_least_amount_out() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+
+        assert_noop!(
+            Amm::swap(Origin::signed(BOB), ep_hash(), base(), 10_000, 9_803),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_noop!(
+            Amm::swap(Origin::signed(BOB), ep_hash(), H256::repeat_byte(9), 10_000, 0),
+            Error::<Test>::AssetNotInPool
+        );
+        assert_noop!(
+            Amm::swap(Origin::signed(BOB), ep_hash(), base(), 0, 0),
+            Error::<Test>::ZeroAmount
+        );
+        // rounds to nothing out
+        assert_noop!(
+            Amm::swap(Origin::signed(BOB), ep_hash(), base(), 1, 0),
+            Error::<Test>::InsufficientLiquidity
+        );
+
+        assert_ok!(Amm::swap(Origin::signed(BOB), ep_hash(), base(), 10_000, 9_802));
+    });
+}
+
+#[test]
+fn share_asset_supply_is_the_pool_shares() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000, 4_000));
+        assert_ok!(Amm::add_liquidity(Origin::signed(BOB), ep_hash(), 500, 2_000));
+        assert_eq!(balance(BOB, pool().share_asset), 1_000);
+        assert_eq!(share_supply(), 3_000);
+        assert_eq!(shares_held(), 3_000);
+
+        // 99 base after the fee buys 371.48 quote
+        assert_ok!(Amm::swap(Origin::signed(BOB), ep_hash(), base(), 100, 0));
+        assert_eq!((pool().base_reserve, pool().quote_reserve), (1_600, 5_629));
+
+        assert_ok!(Amm::remove_liquidity(Origin::signed(BOB), ep_hash(), 1_000, 0, 0));
+        assert_eq!((pool().base_reserve, pool().quote_reserve), (1_067, 3_753));
+        assert_eq!(share_supply(), 2_000);
+        assert_eq!(shares_held(), 2_000);
+
+        // the last shares take what is left
+        assert_ok!(Amm::remove_liquidity(Origin::signed(ALICE), ep_hash(), 2_000, 1_067, 3_753));
+        let pool = pool();
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (0, 0));
+        assert_eq!(balance(pool.account, base()), 0);
+        assert_eq!(balance(pool.account, quote()), 0);
+        assert_eq!(share_supply(), 0);
+
+        // an emptied pool is priced again by its next deposit
+        assert_ok!(Amm::add_liquidity(Origin::signed(BOB), ep_hash(), 100, 900));
+        assert_eq!(share_supply(), 300);
+        assert_eq!(shares_held(), 300);
+    });
+}
+
+#[test]
+fn route_swaps_through_pairs_of_either_orientation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+        let other_quote = pair(other(), quote());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), other_quote, 1_000_000, 1_000_000));
+
+        // 10_000 base buys 9_802 quote, which buy 9_610 other from the quote side of OTHER/QUOTE
+        let path = vec![base(), quote(), other()];
+        assert_noop!(
+            Amm::swap_route(Origin::signed(BOB), path.clone(), 10_000, 9_611),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(Amm::swap_route(Origin::signed(BOB), path, 10_000, 9_610));
+
+        assert_eq!(balance(BOB, base()), SUPPLY / 2 - 10_000);
+        assert_eq!(balance(BOB, quote()), SUPPLY / 2);
+        assert_eq!(balance(BOB, other()), SUPPLY / 2 + 9_610);
+
+        let pool = pool();
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (1_010_000, 990_198));
+        let pool = Amm::pool(other_quote).unwrap();
+        assert_eq!((pool.base_reserve, pool.quote_reserve), (990_390, 1_009_802));
+        assert_eq!(balance(pool.account, other()), 990_390);
+        assert_eq!(balance(pool.account, quote()), 1_009_802);
+    });
+}
+
+#[test]
+fn route_failing_on_its_last_hop_reverts_the_earlier_hops() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+
+        let path = vec![base(), quote(), other()];
+        assert_noop!(
+            Amm::swap_route(Origin::signed(BOB), path.clone(), 10_000, 0),
+            Error::<Test>::NoMatchingPool
+        );
+
+        // 9_802 quote buy nothing from a pool of one
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), pair(other(), quote()), 1, 1));
+        assert_noop!(
+            Amm::swap_route(Origin::signed(BOB), path, 10_000, 0),
+            Error::<Test>::InsufficientLiquidity
+        );
+
+        assert_eq!(balance(BOB, base()), SUPPLY / 2);
+        assert_eq!(balance(BOB, quote()), SUPPLY / 2);
+        assert_eq!((pool().base_reserve, pool().quote_reserve), (1_000_000, 1_000_000));
+    });
+}
+
+#[test]
+fn best_route_receives_the_most() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+        let deep = pair(other(), quote());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), deep, 1_000_000, 1_000_000));
+        let shallow = pair(base(), other());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), shallow, 10_000, 10_000));
+
+        // the deep pools are worth their second fee for a large amount
+        assert_eq!(
+            Amm::best_route(base(), other(), 10_000),
+            Some((vec![base(), quote(), other()], 9_610))
+        );
+        // but not for a small one
+        assert_eq!(Amm::best_route(base(), other(), 10), Some((vec![base(), other()], 9)));
+
+        assert_eq!(Amm::best_route(base(), base(), 10_000), None);
+        assert_eq!(Amm::best_route(base(), other(), 0), None);
+        assert_eq!(Amm::best_route(base(), far(), 10_000), None);
+    });
+}
+
+#[test]
+fn best_route_goes_through_at_most_max_route_hops_pools() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), ep_hash(), 1_000_000, 1_000_000));
+        let deep = pair(other(), quote());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), deep, 1_000_000, 1_000_000));
+        let shallow = pair(base(), other());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), shallow, 10_000, 10_000));
+        let far_other = pair(far(), other());
+        assert_ok!(Amm::create_pool(Origin::signed(ALICE), far_other, 1_000_000, 1_000_000));
+
+        // three hops through the deep pools would receive more
+        let three_hops = Amm::swap_amount_out(&Amm::pool(far_other).unwrap(), other(), 9_610);
+        let (path, amount_out) = Amm::best_route(base(), far(), 10_000).unwrap();
+        assert_eq!(path, vec![base(), other(), far()]);
+        assert!(amount_out < three_hops.unwrap());
+
+        assert_noop!(
+            Amm::swap_route(
+                Origin::signed(BOB),
+                vec![base(), quote(), other(), far()],
+                10_000,
+                0
+            ),
+            Error::<Test>::InvalidRoute
+        );
+        assert_noop!(
+            Amm::swap_route(Origin::signed(BOB), vec![base()], 10_000, 0),
+            Error::<Test>::InvalidRoute
+        );
+    });
+}
@@ -30,4 +30,18 @@ impl<T: Trait> Module<T> {
         // Return Ok.
         Ok(())
     }
+
+    /// Burn assets from a specific address and take them off the asset's supply.
+    pub fn burn_from(who: T::AccountId, hash: T::Hash, amount: T::Balance) -> DispatchResult {
+        let mut asset = Self::asset(hash).ok_or(Error::<T>::NoMatchingAsset)?;
+        asset.total_supply = asset
+            .total_supply
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+
+        Self::burn(who, hash, amount)?;
+        <Assets<T>>::insert(hash, asset);
+
+        Ok(())
+    }
 }
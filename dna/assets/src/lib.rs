@@ -7,10 +7,12 @@
 // Importing crates declared in the cargo.toml file.
 use codec::{Decode, Encode};
 // use core::ops::{Add, AddAssign, Sub, SubAssign};
-use rstd::prelude::*;
-use sp_runtime::traits::{Bounded, Hash};
+use rstd::{prelude::*, result};
+use sp_runtime::traits::{Bounded, CheckedAdd, CheckedSub, Hash};
 use support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
     traits::Randomness, StorageMap, StorageValue,
 };
 use system::ensure_signed;
@@ -19,6 +21,7 @@ use system::ensure_signed;
 pub mod burn;
 pub mod freeze;
 pub mod mint;
+pub mod settle;
 pub mod transfer;
 pub mod unfreeze;
 
@@ -30,6 +33,15 @@ pub struct Asset<Hash, Balance> {
     pub total_supply: Balance,
 }
 
+/// Balance changes of an account in an asset, applied in one write by `settle`
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SettlementDelta<Balance> {
+    pub spent_frozen: Balance, // unfrozen and paid to other accounts
+    pub released: Balance,     // unfrozen and kept
+    pub received: Balance,     // paid by other accounts
+}
+
 pub trait Trait: balances::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -50,6 +62,8 @@ decl_error! {
         SenderHaveNoAsset,
         /// Total supply cannot be negative
         NegativeAmount,
+        /// A genesis asset with the symbol already exists
+        DuplicateGenesisAsset,
     }
 }
 
@@ -65,6 +79,8 @@ decl_event!(
         Burned(AccountId,Hash,Balance),
         Freezed(AccountId, Hash, Balance),
         UnFreezed(AccountId, Hash, Balance),
+        // (accountId, assetHash, spent frozen, released, received)
+        Settled(AccountId, Hash, Balance, Balance, Balance),
 	}
 );
 
@@ -82,6 +98,17 @@ decl_storage! {
 
         Nonce: u64;
     }
+    add_extra_genesis {
+        /// (owner, symbol, total supply, holders and the part of the supply each holds), the
+        /// owner holds the rest of the supply
+        config(assets): Vec<(T::AccountId, Vec<u8>, T::Balance, Vec<(T::AccountId, T::Balance)>)>;
+        build(|config: &GenesisConfig<T>| {
+            for (owner, symbol, total_supply, holders) in &config.assets {
+                Module::<T>::create_genesis_asset(owner.clone(), symbol.clone(), *total_supply, holders)
+                    .expect("genesis assets are valid; qed");
+            }
+        });
+    }
 }
 
 // This module's dispatchable functions.
@@ -10,6 +10,18 @@ impl<T: Trait> Module<T> {
         );
         let sender = ensure_signed(origin)?;
 
+        Self::create_asset(sender, symbol, total_supply)?;
+
+        // Return Ok.
+        Ok(())
+    }
+
+    /// Create an asset whose whole supply is owned by `sender`.
+    pub fn create_asset(
+        sender: T::AccountId,
+        symbol: Vec<u8>,
+        total_supply: T::Balance,
+    ) -> result::Result<T::Hash, DispatchError> {
         let nonce = Nonce::get();
 
         let random_seed = <randomness_collective_flip::Module<T>>::random_seed();
@@ -34,7 +46,93 @@ impl<T: Trait> Module<T> {
 
         Self::deposit_event(RawEvent::Issued(sender, hash.clone(), total_supply));
 
-        // Return Ok.
+        Ok(hash)
+    }
+
+    /// Hash of the genesis asset with the given symbol.
+    pub fn genesis_asset_hash(symbol: &[u8]) -> T::Hash {
+        (b"genesis", symbol).using_encoded(<T as system::Trait>::Hashing::hash)
+    }
+
+    /// Create an asset in the genesis block. Its hash follows from its symbol, so the genesis
+    /// configuration of other modules can refer to it.
+    pub fn create_genesis_asset(
+        owner: T::AccountId,
+        symbol: Vec<u8>,
+        total_supply: T::Balance,
+        holders: &[(T::AccountId, T::Balance)],
+    ) -> DispatchResult {
+        let hash = Self::genesis_asset_hash(&symbol);
+        ensure!(Self::asset(hash).is_none(), Error::<T>::DuplicateGenesisAsset);
+
+        let mut owner_amount = total_supply;
+        for (_, amount) in holders {
+            owner_amount = owner_amount
+                .checked_sub(amount)
+                .ok_or(Error::<T>::BalanceNotEnough)?;
+        }
+        for (holder, amount) in holders {
+            Self::deposit_genesis_balance(holder.clone(), hash, *amount)?;
+        }
+        Self::deposit_genesis_balance(owner.clone(), hash, owner_amount)?;
+
+        <Assets<T>>::insert(
+            hash,
+            Asset::<T::Hash, T::Balance> {
+                hash,
+                total_supply,
+                symbol,
+            },
+        );
+        Owners::<T>::insert(hash, owner.clone());
+
+        let owned_asset_index = OwnedAssetsIndex::<T>::get(owner.clone());
+        OwnedAssets::<T>::insert((owner.clone(), owned_asset_index), hash);
+        OwnedAssetsIndex::<T>::insert(owner, owned_asset_index + 1);
+
+        Ok(())
+    }
+
+    /// Add to the balance of a genesis asset holder, who may be listed more than once.
+    fn deposit_genesis_balance(
+        who: T::AccountId,
+        hash: T::Hash,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let balance = Self::balance_of((who.clone(), hash))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let free_balance = Self::free_balance_of((who.clone(), hash))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        BalanceOf::<T>::insert((who.clone(), hash), balance);
+        FreeBalanceOf::<T>::insert((who, hash), free_balance);
+
+        Ok(())
+    }
+
+    /// Add `amount` to the supply of an asset and deposit it into an address.
+    pub fn mint_to(hash: T::Hash, to: T::AccountId, amount: T::Balance) -> DispatchResult {
+        let mut asset = Self::asset(hash).ok_or(Error::<T>::NoMatchingAsset)?;
+        asset.total_supply = asset
+            .total_supply
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        let new_to_amount = Self::balance_of((to.clone(), hash))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let new_to_free_amount = Self::free_balance_of((to.clone(), hash))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        <Assets<T>>::insert(hash, asset);
+        BalanceOf::<T>::insert((to.clone(), hash), new_to_amount);
+        FreeBalanceOf::<T>::insert((to.clone(), hash), new_to_free_amount);
+
+        Self::deposit_event(RawEvent::Issued(to, hash, amount));
+
         Ok(())
     }
 }
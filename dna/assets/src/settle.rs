@@ -0,0 +1,47 @@
+use super::*;
+
+// This function applies the net balance changes of an address in one write.
+impl<T: Trait> Module<T> {
+    pub fn settle(
+        sender: T::AccountId,
+        hash: T::Hash,
+        delta: SettlementDelta<T::Balance>,
+    ) -> DispatchResult {
+        let asset = Self::asset(hash);
+        ensure!(asset.is_some(), Error::<T>::NoMatchingAsset);
+
+        let unfreezed_amount = delta
+            .spent_frozen
+            .checked_add(&delta.released)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let old_freezed_amount = Self::freezed_balance_of((sender.clone(), hash.clone()));
+        let new_freezed_amount = old_freezed_amount
+            .checked_sub(&unfreezed_amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+
+        let new_free_amount = Self::free_balance_of((sender.clone(), hash.clone()))
+            .checked_add(&delta.released)
+            .and_then(|x| x.checked_add(&delta.received))
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        let new_amount = Self::balance_of((sender.clone(), hash.clone()))
+            .checked_add(&delta.received)
+            .ok_or(Error::<T>::AmountOverflow)?
+            .checked_sub(&delta.spent_frozen)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+
+        BalanceOf::<T>::insert((sender.clone(), hash.clone()), new_amount);
+        FreeBalanceOf::<T>::insert((sender.clone(), hash.clone()), new_free_amount);
+        FreezedBalanceOf::<T>::insert((sender.clone(), hash.clone()), new_freezed_amount);
+
+        Self::deposit_event(RawEvent::Settled(
+            sender,
+            hash,
+            delta.spent_frozen,
+            delta.released,
+            delta.received,
+        ));
+
+        Ok(())
+    }
+}
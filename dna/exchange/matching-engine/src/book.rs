@@ -0,0 +1,99 @@
+use crate::{fill_amounts, is_dust, price_matched, Fill, MatchError, Order, Side};
+
+/// Storage of an order book the engine matches against. Reads take `&mut self` so that
+/// implementations backed by runtime storage can cache or charge for them.
+pub trait OrderBook {
+    type OrderId: Copy + PartialEq;
+    type Error;
+
+    fn price_factor(&self) -> u128;
+
+    /// Convert an engine error into the book's error type.
+    fn match_error(error: MatchError) -> Self::Error;
+
+    /// Best price level of `side`, the highest bid or the lowest ask.
+    fn best_price(&mut self, side: Side) -> Result<Option<u128>, Self::Error>;
+
+    /// Oldest order of a price level.
+    fn first_order(&mut self, side: Side, price: u128)
+        -> Result<Option<Self::OrderId>, Self::Error>;
+
+    /// Order queued after `id` in its price level.
+    fn next_order(
+        &mut self,
+        side: Side,
+        price: u128,
+        id: Self::OrderId,
+    ) -> Result<Option<Self::OrderId>, Self::Error>;
+
+    /// The tradable part of a resting order, `None` when it can no longer trade.
+    fn maker(&mut self, id: Self::OrderId) -> Result<Option<Order<Self::OrderId>>, Self::Error>;
+
+    /// Persist a fill of a resting order, `taker` already has the fill applied. A maker that
+    /// is filled or left as dust has to leave its price level.
+    fn fill(
+        &mut self,
+        fill: &Fill<Self::OrderId>,
+        taker: &Order<Self::OrderId>,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Match `taker` against the other side of the book, best price first and oldest order
+/// first within a level. Each fill is applied to the taker and passed to the book before
+/// the next one is made. Returns whether the taker is done, filled or left as dust.
+pub fn match_order<B: OrderBook>(
+    book: &mut B,
+    taker: &mut Order<B::OrderId>,
+) -> Result<bool, B::Error> {
+    let price_factor = book.price_factor();
+    let side = !taker.side;
+
+    loop {
+        if is_dust(taker, price_factor).map_err(B::match_error)? {
+            return Ok(true);
+        }
+
+        let price = match book.best_price(side)? {
+            Some(price) => price,
+            None => return Ok(false),
+        };
+        if !price_matched(taker.price, taker.side, price) {
+            return Ok(false);
+        }
+
+        let mut traded = false;
+        let mut next = book.first_order(side, price)?;
+        while let Some(id) = next {
+            // read ahead, a filled maker leaves the level
+            next = book.next_order(side, price, id)?;
+
+            let maker = match book.maker(id)? {
+                Some(maker) => maker,
+                None => continue,
+            };
+
+            let (base_amount, quote_amount) =
+                fill_amounts(&maker, taker, price_factor).map_err(B::match_error)?;
+            let fill = Fill {
+                maker: id,
+                taker: taker.id,
+                price: maker.price,
+                base_amount,
+                quote_amount,
+            };
+
+            taker.apply_fill(&fill).map_err(B::match_error)?;
+            book.fill(&fill, taker)?;
+            traded = true;
+
+            if is_dust(taker, price_factor).map_err(B::match_error)? {
+                return Ok(true);
+            }
+        }
+
+        // a level without tradable orders would be visited forever
+        if !traded {
+            return Ok(false);
+        }
+    }
+}
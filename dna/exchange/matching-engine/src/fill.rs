@@ -0,0 +1,134 @@
+use crate::{MatchError, Order, Side};
+use core::convert::TryInto;
+use primitive_types::U256;
+
+/// Rounding direction of a converted amount.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `a * b / c` without intermediate overflow.
+pub fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Result<u128, MatchError> {
+    if c == 0 {
+        return Err(MatchError::Overflow);
+    }
+
+    let product = U256::from(a) * U256::from(b);
+    let c = U256::from(c);
+    let mut result = product / c;
+    if rounding == Rounding::Up && product % c != U256::zero() {
+        result += U256::one();
+    }
+
+    result.try_into().map_err(|_| MatchError::Overflow)
+}
+
+/// Base amount worth `quote_amount` at `price`.
+pub fn quote_to_base(
+    quote_amount: u128,
+    price: u128,
+    price_factor: u128,
+    rounding: Rounding,
+) -> Result<u128, MatchError> {
+    mul_div(quote_amount, price, price_factor, rounding)
+}
+
+/// Quote amount worth `base_amount` at `price`.
+pub fn base_to_quote(
+    base_amount: u128,
+    price: u128,
+    price_factor: u128,
+    rounding: Rounding,
+) -> Result<u128, MatchError> {
+    mul_div(base_amount, price_factor, price, rounding)
+}
+
+/// Whether an order of `side` at `price` trades against a level at `level_price`.
+pub fn price_matched(price: u128, side: Side, level_price: u128) -> bool {
+    match side {
+        Side::Sell => price <= level_price,
+        Side::Buy => price >= level_price,
+    }
+}
+
+/// Amounts of a fill at the maker's price as `(base, quote)`. The order that is used up
+/// sets its side of the fill exactly, the other side is converted at the maker price and
+/// rounded in the maker's favor: the maker gives the amount rounded down and receives the
+/// amount rounded up. Neither side exceeds what the paying order has left, a side cut to
+/// that converts the other side again so the rounding still favors the maker.
+pub fn fill_amounts<Id>(
+    maker: &Order<Id>,
+    taker: &Order<Id>,
+    price_factor: u128,
+) -> Result<(u128, u128), MatchError> {
+    let (buyer, seller) = match taker.side {
+        Side::Buy => (taker, maker),
+        Side::Sell => (maker, taker),
+    };
+
+    let price = maker.price;
+    let maker_is_buyer = maker.side == Side::Buy;
+
+    // both asks valued in base, times the price factor
+    let seller_base = U256::from(seller.remained_buy_amount) * U256::from(price_factor);
+    let buyer_base = U256::from(buyer.remained_buy_amount) * U256::from(price);
+
+    let (base_rounding, quote_rounding) = if maker_is_buyer {
+        (Rounding::Down, Rounding::Up)
+    } else {
+        (Rounding::Up, Rounding::Down)
+    };
+
+    let (mut base_amount, mut quote_amount) = if seller_base <= buyer_base {
+        // the seller is filled
+        let base_amount = seller.remained_buy_amount;
+        (base_amount, base_to_quote(base_amount, price, price_factor, quote_rounding)?)
+    } else {
+        // the buyer is filled
+        let quote_amount = buyer.remained_buy_amount;
+        (quote_to_base(quote_amount, price, price_factor, base_rounding)?, quote_amount)
+    };
+
+    if base_amount > buyer.remained_sell_amount {
+        base_amount = buyer.remained_sell_amount;
+        quote_amount = base_to_quote(base_amount, price, price_factor, quote_rounding)?;
+    }
+    if quote_amount > seller.remained_sell_amount {
+        quote_amount = seller.remained_sell_amount;
+        base_amount = quote_to_base(quote_amount, price, price_factor, base_rounding)?;
+    }
+    // a buying maker's base converted back can round above the cap, less favors the maker
+    let base_amount = base_amount.min(buyer.remained_sell_amount);
+    if base_amount == 0 || quote_amount == 0 {
+        return Err(MatchError::ZeroFill);
+    }
+
+    Ok((base_amount, quote_amount))
+}
+
+/// An order is dust once what it has left can not be traded for a whole unit at its
+/// price, its remaining funds should then be released instead of resting in the book.
+pub fn is_dust<Id>(order: &Order<Id>, price_factor: u128) -> Result<bool, MatchError> {
+    if order.remained_sell_amount == 0 || order.remained_buy_amount == 0 {
+        return Ok(true);
+    }
+
+    let counterparty_amount = match order.side {
+        Side::Buy => quote_to_base(
+            order.remained_buy_amount,
+            order.price,
+            price_factor,
+            Rounding::Down,
+        )?,
+        Side::Sell => base_to_quote(
+            order.remained_buy_amount,
+            order.price,
+            price_factor,
+            Rounding::Down,
+        )?,
+    };
+
+    Ok(counterparty_amount == 0)
+}
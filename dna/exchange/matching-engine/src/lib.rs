@@ -0,0 +1,96 @@
+//! Price-time priority matching of the DEX, free of runtime storage so that the module,
+//! clients and simulators all run the same logic.
+//!
+//! Prices are the amount of base per unit of quote times a price factor. A buy order gives
+//! base and receives quote, a sell order gives quote and receives base.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::ops::Not;
+
+mod book;
+mod fill;
+mod memory;
+#[cfg(test)]
+mod tests;
+
+pub use book::*;
+pub use fill::*;
+pub use memory::MemoryOrderBook;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Not for Side {
+    type Output = Side;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+/// The part of an order the engine matches with.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Order<Id> {
+    pub id: Id,
+    pub side: Side,
+    pub price: u128,
+    /// amount the order still gives, base for a buy and quote for a sell
+    pub remained_sell_amount: u128,
+    /// amount the order still receives
+    pub remained_buy_amount: u128,
+}
+
+impl<Id> Order<Id> {
+    /// Take a fill off the order's remaining amounts.
+    pub fn apply_fill(&mut self, fill: &Fill<Id>) -> Result<(), MatchError> {
+        let (give, have) = fill.amounts(self.side);
+
+        self.remained_sell_amount = self
+            .remained_sell_amount
+            .checked_sub(give)
+            .ok_or(MatchError::Underflow)?;
+        self.remained_buy_amount = self
+            .remained_buy_amount
+            .checked_sub(have)
+            .ok_or(MatchError::Underflow)?;
+
+        Ok(())
+    }
+}
+
+/// A trade between a resting maker order and a taker order, at the maker's price.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Fill<Id> {
+    pub maker: Id,
+    pub taker: Id,
+    pub price: u128,
+    pub base_amount: u128,
+    pub quote_amount: u128,
+}
+
+impl<Id> Fill<Id> {
+    /// What an order of `side` gives and receives in this fill.
+    pub fn amounts(&self, side: Side) -> (u128, u128) {
+        match side {
+            Side::Buy => (self.base_amount, self.quote_amount),
+            Side::Sell => (self.quote_amount, self.base_amount),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchError {
+    /// an amount does not fit into `u128`, or the price is zero
+    Overflow,
+    /// a fill takes more than an order has left
+    Underflow,
+    /// a fill rounds to nothing on one side
+    ZeroFill,
+}
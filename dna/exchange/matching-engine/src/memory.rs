@@ -0,0 +1,156 @@
+use crate::{is_dust, match_order, Fill, MatchError, Order, OrderBook, Side};
+use sp_std::collections::btree_map::BTreeMap;
+use sp_std::prelude::*;
+
+/// An order book held in memory, for clients and simulators.
+pub struct MemoryOrderBook<Id: Ord> {
+    price_factor: u128,
+    /// price => (sequence => order id), the sequence keeps the time priority
+    bids: BTreeMap<u128, BTreeMap<u64, Id>>,
+    asks: BTreeMap<u128, BTreeMap<u64, Id>>,
+    orders: BTreeMap<Id, (Order<Id>, u64)>,
+    sequence: u64,
+    fills: Vec<Fill<Id>>,
+}
+
+impl<Id: Ord + Copy> MemoryOrderBook<Id> {
+    pub fn new(price_factor: u128) -> Self {
+        MemoryOrderBook {
+            price_factor,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            orders: BTreeMap::new(),
+            sequence: 0,
+            fills: Vec::new(),
+        }
+    }
+
+    /// Match an order and rest what is left of it. Returns the fills it made.
+    pub fn place(&mut self, mut order: Order<Id>) -> Result<Vec<Fill<Id>>, MatchError> {
+        let done = match_order(self, &mut order)?;
+        if !done {
+            self.rest(order);
+        }
+
+        Ok(sp_std::mem::take(&mut self.fills))
+    }
+
+    /// Take a resting order off the book.
+    pub fn cancel(&mut self, id: Id) -> Option<Order<Id>> {
+        let (order, sequence) = self.orders.remove(&id)?;
+        let levels = self.levels_mut(order.side);
+
+        if let Some(level) = levels.get_mut(&order.price) {
+            level.remove(&sequence);
+            if level.is_empty() {
+                levels.remove(&order.price);
+            }
+        }
+
+        Some(order)
+    }
+
+    pub fn order(&self, id: Id) -> Option<&Order<Id>> {
+        self.orders.get(&id).map(|(order, _)| order)
+    }
+
+    /// Orders of a price level, oldest first.
+    pub fn level(&self, side: Side, price: u128) -> Vec<Id> {
+        self.levels(side)
+            .get(&price)
+            .map(|level| level.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn rest(&mut self, order: Order<Id>) {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        let id = order.id;
+        let price = order.price;
+        let side = order.side;
+        self.orders.insert(id, (order, sequence));
+        self.levels_mut(side)
+            .entry(price)
+            .or_default()
+            .insert(sequence, id);
+    }
+
+    fn levels(&self, side: Side) -> &BTreeMap<u128, BTreeMap<u64, Id>> {
+        match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        }
+    }
+
+    fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<u128, BTreeMap<u64, Id>> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+}
+
+impl<Id: Ord + Copy> OrderBook for MemoryOrderBook<Id> {
+    type OrderId = Id;
+    type Error = MatchError;
+
+    fn price_factor(&self) -> u128 {
+        self.price_factor
+    }
+
+    fn match_error(error: MatchError) -> MatchError {
+        error
+    }
+
+    fn best_price(&mut self, side: Side) -> Result<Option<u128>, MatchError> {
+        let levels = self.levels(side);
+        Ok(match side {
+            Side::Buy => levels.keys().next_back().cloned(),
+            Side::Sell => levels.keys().next().cloned(),
+        })
+    }
+
+    fn first_order(&mut self, side: Side, price: u128) -> Result<Option<Id>, MatchError> {
+        Ok(self
+            .levels(side)
+            .get(&price)
+            .and_then(|level| level.values().next().cloned()))
+    }
+
+    fn next_order(&mut self, side: Side, price: u128, id: Id) -> Result<Option<Id>, MatchError> {
+        let sequence = match self.orders.get(&id) {
+            Some((_, sequence)) => *sequence,
+            None => return Ok(None),
+        };
+
+        Ok(self.levels(side).get(&price).and_then(|level| {
+            level
+                .range(sequence + 1..)
+                .next()
+                .map(|(_, id)| *id)
+        }))
+    }
+
+    fn maker(&mut self, id: Id) -> Result<Option<Order<Id>>, MatchError> {
+        Ok(self.order(id).cloned())
+    }
+
+    fn fill(&mut self, fill: &Fill<Id>, _taker: &Order<Id>) -> Result<(), MatchError> {
+        let price_factor = self.price_factor;
+        let done = match self.orders.get_mut(&fill.maker) {
+            Some((maker, _)) => {
+                maker.apply_fill(fill)?;
+                is_dust(maker, price_factor)?
+            }
+            None => return Err(MatchError::Underflow),
+        };
+
+        if done {
+            self.cancel(fill.maker);
+        }
+        self.fills.push(fill.clone());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Tests of the matching engine.
+
+use crate::*;
+use primitive_types::U256;
+use sp_std::collections::btree_map::BTreeMap;
+
+/// Deterministic pseudo random numbers for the property tests, xorshift64*.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `1..=max`.
+    fn up_to(&mut self, max: u128) -> u128 {
+        u128::from(self.next()) % max + 1
+    }
+
+    fn side(&mut self) -> Side {
+        if self.next() & 1 == 0 {
+            Side::Buy
+        } else {
+            Side::Sell
+        }
+    }
+}
+
+const PRICE_FACTORS: [u128; 3] = [1, 1_000, 100_000_000];
+
+/// An order giving `sell_amount` at `price`, receiving what that is worth rounded down as
+/// the module does when an order is placed.
+fn order(id: u32, side: Side, price: u128, sell_amount: u128, price_factor: u128) -> Order<u32> {
+    let buy_amount = match side {
+        Side::Buy => base_to_quote(sell_amount, price, price_factor, Rounding::Down),
+        Side::Sell => quote_to_base(sell_amount, price, price_factor, Rounding::Down),
+    }
+    .unwrap();
+
+    Order {
+        id,
+        side,
+        price,
+        remained_sell_amount: sell_amount,
+        remained_buy_amount: buy_amount,
+    }
+}
+
+/// Whether the maker of a fill receives at least what it gives is worth at its price.
+fn favors_maker(maker_side: Side, fill: &Fill<u32>, price_factor: u128) -> bool {
+    let base = U256::from(fill.base_amount) * U256::from(price_factor);
+    let quote = U256::from(fill.quote_amount) * U256::from(fill.price);
+
+    match maker_side {
+        Side::Buy => quote >= base,
+        Side::Sell => base >= quote,
+    }
+}
+
+#[test]
+fn mul_div_rounds_as_asked() {
+    assert_eq!(mul_div(7, 3, 2, Rounding::Down), Ok(10));
+    assert_eq!(mul_div(7, 3, 2, Rounding::Up), Ok(11));
+    assert_eq!(mul_div(6, 3, 2, Rounding::Down), Ok(9));
+    assert_eq!(mul_div(6, 3, 2, Rounding::Up), Ok(9));
+
+    // the product does not overflow, the result does
+    assert_eq!(mul_div(u128::MAX, 3, 3, Rounding::Up), Ok(u128::MAX));
+    assert_eq!(
+        mul_div(u128::MAX, 2, 1, Rounding::Down),
+        Err(MatchError::Overflow)
+    );
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(MatchError::Overflow));
+}
+
+#[test]
+fn fill_rounds_in_the_makers_favor() {
+    // 7 quote are worth 14 / 3 base at 2
+    let ask = order(1, Side::Sell, 2, 10, 3);
+    let bid = order(2, Side::Buy, 2, 5, 3);
+    assert_eq!(bid.remained_buy_amount, 7);
+
+    // a selling maker receives the base rounded up
+    assert_eq!(fill_amounts(&ask, &bid, 3), Ok((5, 7)));
+    // a buying maker gives the base rounded down
+    let ask = order(1, Side::Sell, 2, 10, 3);
+    let bid = order(2, Side::Buy, 2, 5, 3);
+    assert_eq!(fill_amounts(&bid, &ask, 3), Ok((4, 7)));
+}
+
+#[test]
+fn fill_never_takes_more_than_the_payer_has() {
+    // the bid can pay 4 base only, while 7 quote are worth 5 rounded up, so the ask gives
+    // the 6 quote that 4 base buy
+    let ask = order(1, Side::Sell, 2, 10, 3);
+    let mut bid = order(2, Side::Buy, 2, 5, 3);
+    bid.remained_sell_amount = 4;
+
+    assert_eq!(fill_amounts(&ask, &bid, 3), Ok((4, 6)));
+
+    bid.remained_sell_amount = 0;
+    assert_eq!(fill_amounts(&ask, &bid, 3), Err(MatchError::ZeroFill));
+}
+
+#[test]
+fn random_fills_favor_the_maker_and_use_up_an_order() {
+    let mut rng = Rng(0x5eed);
+
+    for _ in 0..10_000 {
+        let price_factor = PRICE_FACTORS[rng.up_to(3) as usize - 1];
+        let maker_side = rng.side();
+        let maker_price = rng.up_to(1_000 * price_factor);
+        let taker_price = match maker_side {
+            Side::Sell => maker_price + rng.up_to(maker_price) - 1,
+            Side::Buy => rng.up_to(maker_price),
+        };
+
+        let maker = order(1, maker_side, maker_price, rng.up_to(1 << 60), price_factor);
+        let taker = order(
+            2,
+            !maker_side,
+            taker_price,
+            rng.up_to(1 << 60),
+            price_factor,
+        );
+        if is_dust(&maker, price_factor).unwrap() || is_dust(&taker, price_factor).unwrap() {
+            continue;
+        }
+
+        let (base_amount, quote_amount) = match fill_amounts(&maker, &taker, price_factor) {
+            Ok(amounts) => amounts,
+            Err(e) => {
+                assert_eq!(e, MatchError::ZeroFill);
+                continue;
+            }
+        };
+        let fill = Fill {
+            maker: 1,
+            taker: 2,
+            price: maker_price,
+            base_amount,
+            quote_amount,
+        };
+        assert!(favors_maker(maker_side, &fill, price_factor), "{:?}", fill);
+
+        // neither order goes below zero
+        let mut maker_after = maker.clone();
+        let mut taker_after = taker.clone();
+        assert_eq!(maker_after.apply_fill(&fill), Ok(()));
+        assert_eq!(taker_after.apply_fill(&fill), Ok(()));
+
+        // and one of them is done, so matching moves on
+        assert!(
+            is_dust(&maker_after, price_factor).unwrap()
+                || is_dust(&taker_after, price_factor).unwrap(),
+            "{:?} {:?}",
+            maker_after,
+            taker_after
+        );
+    }
+}
+
+#[test]
+fn random_books_conserve_supply() {
+    let mut rng = Rng(0xb00c);
+
+    for _ in 0..200 {
+        let price_factor = PRICE_FACTORS[rng.up_to(3) as usize - 1];
+        let mut book = MemoryOrderBook::new(price_factor);
+        let mut orders = BTreeMap::new();
+        // order id => (base, quote) given by the order in its fills
+        let mut given: BTreeMap<u32, (u128, u128)> = BTreeMap::new();
+        let mut received: BTreeMap<u32, (u128, u128)> = BTreeMap::new();
+
+        for id in 0..50 {
+            let price = (90 + rng.up_to(20)) * price_factor;
+            let taker = order(id, rng.side(), price, rng.up_to(1_000_000), price_factor);
+            orders.insert(id, taker.clone());
+
+            for fill in book.place(taker).unwrap() {
+                let maker_side = orders[&fill.maker].side;
+                assert!(favors_maker(maker_side, &fill, price_factor), "{:?}", fill);
+                assert!(price_matched(
+                    orders[&fill.taker].price,
+                    !maker_side,
+                    fill.price
+                ));
+
+                for (id, side) in [(fill.maker, maker_side), (fill.taker, !maker_side)].iter() {
+                    let (give, have) = fill.amounts(*side);
+                    let (give, have) = match side {
+                        Side::Buy => ((give, 0), (0, have)),
+                        Side::Sell => ((0, give), (have, 0)),
+                    };
+                    let g = given.entry(*id).or_default();
+                    *g = (g.0 + give.0, g.1 + give.1);
+                    let r = received.entry(*id).or_default();
+                    *r = (r.0 + have.0, r.1 + have.1);
+                }
+            }
+        }
+
+        // whatever an order gave was received by another one
+        let total = |amounts: &BTreeMap<u32, (u128, u128)>| {
+            amounts
+                .values()
+                .fold((0, 0), |acc, x| (acc.0 + x.0, acc.1 + x.1))
+        };
+        assert_eq!(total(&given), total(&received));
+
+        for (id, placed) in orders.iter() {
+            let (base, quote) = given.get(id).cloned().unwrap_or_default();
+            let spent = match placed.side {
+                Side::Buy => base,
+                Side::Sell => quote,
+            };
+
+            // no order gave more than it had, a resting order holds the rest
+            assert!(spent <= placed.remained_sell_amount);
+            if let Some(resting) = book.order(*id) {
+                assert_eq!(
+                    resting.remained_sell_amount + spent,
+                    placed.remained_sell_amount
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn book_fills_best_price_then_oldest_order() {
+    let mut book = MemoryOrderBook::new(1);
+    book.place(order(1, Side::Sell, 3, 100, 1)).unwrap();
+    book.place(order(2, Side::Sell, 2, 100, 1)).unwrap();
+    book.place(order(3, Side::Sell, 2, 100, 1)).unwrap();
+
+    // wants 150 quote, the cheaper level has them
+    let fills = book.place(order(4, Side::Buy, 3, 450, 1)).unwrap();
+
+    assert_eq!(
+        fills,
+        vec![
+            Fill {
+                maker: 2,
+                taker: 4,
+                price: 2,
+                base_amount: 200,
+                quote_amount: 100
+            },
+            Fill {
+                maker: 3,
+                taker: 4,
+                price: 2,
+                base_amount: 100,
+                quote_amount: 50
+            },
+        ]
+    );
+    assert_eq!(book.level(Side::Sell, 2), vec![3]);
+    assert_eq!(book.level(Side::Sell, 3), vec![1]);
+    assert_eq!(book.order(3).unwrap().remained_sell_amount, 50);
+    assert!(book.order(4).is_none());
+}
+
+#[test]
+fn book_rests_what_is_left_of_a_partially_filled_taker() {
+    let mut book = MemoryOrderBook::new(1);
+    book.place(order(1, Side::Buy, 2, 100, 1)).unwrap();
+
+    let fills = book.place(order(2, Side::Sell, 2, 20, 1)).unwrap();
+    assert_eq!(
+        fills,
+        vec![Fill {
+            maker: 1,
+            taker: 2,
+            price: 2,
+            base_amount: 40,
+            quote_amount: 20
+        }]
+    );
+    let maker = book.order(1).unwrap();
+    assert_eq!(
+        (maker.remained_sell_amount, maker.remained_buy_amount),
+        (60, 30)
+    );
+
+    let fills = book.place(order(3, Side::Sell, 2, 50, 1)).unwrap();
+    assert_eq!(
+        fills,
+        vec![Fill {
+            maker: 1,
+            taker: 3,
+            price: 2,
+            base_amount: 60,
+            quote_amount: 30
+        }]
+    );
+    assert!(book.level(Side::Buy, 2).is_empty());
+    assert_eq!(book.level(Side::Sell, 2), vec![3]);
+    let rest = book.order(3).unwrap();
+    assert_eq!(
+        (rest.remained_sell_amount, rest.remained_buy_amount),
+        (20, 40)
+    );
+}
+
+#[test]
+fn book_drops_orders_left_as_dust() {
+    // 9 quote are worth 22.5 base at 2.5
+    let mut book = MemoryOrderBook::new(10);
+    book.place(order(1, Side::Sell, 25, 10, 10)).unwrap();
+
+    let fills = book.place(order(2, Side::Buy, 25, 24, 10)).unwrap();
+
+    assert_eq!(
+        fills,
+        vec![Fill {
+            maker: 1,
+            taker: 2,
+            price: 25,
+            base_amount: 23,
+            quote_amount: 9
+        }]
+    );
+    // the ask's last quote buys 2 base, which are worth no whole quote
+    assert!(book.order(1).is_none());
+    assert!(book.level(Side::Sell, 25).is_empty());
+    assert!(book.order(2).is_none());
+    assert!(book.level(Side::Buy, 25).is_empty());
+}
+
+/// A book with one ask level whose only order can no longer trade.
+struct StaleBook;
+
+impl OrderBook for StaleBook {
+    type OrderId = u32;
+    type Error = MatchError;
+
+    fn price_factor(&self) -> u128 {
+        1
+    }
+
+    fn match_error(error: MatchError) -> MatchError {
+        error
+    }
+
+    fn best_price(&mut self, side: Side) -> Result<Option<u128>, MatchError> {
+        Ok(if side == Side::Sell { Some(2) } else { None })
+    }
+
+    fn first_order(&mut self, _side: Side, _price: u128) -> Result<Option<u32>, MatchError> {
+        Ok(Some(1))
+    }
+
+    fn next_order(&mut self, _: Side, _: u128, _: u32) -> Result<Option<u32>, MatchError> {
+        Ok(None)
+    }
+
+    fn maker(&mut self, _id: u32) -> Result<Option<Order<u32>>, MatchError> {
+        Ok(None)
+    }
+
+    fn fill(&mut self, fill: &Fill<u32>, _taker: &Order<u32>) -> Result<(), MatchError> {
+        panic!("filled an order that can not trade: {:?}", fill);
+    }
+}
+
+#[test]
+fn match_stops_at_a_level_without_tradable_orders() {
+    let mut taker = order(1, Side::Buy, 2, 100, 1);
+
+    assert_eq!(match_order(&mut StaleBook, &mut taker), Ok(false));
+    assert_eq!(taker, order(1, Side::Buy, 2, 100, 1));
+}
@@ -0,0 +1,141 @@
+//! RPC interface for the DEX module.
+
+use std::sync::Arc;
+
+use codec::Codec;
+pub use dex_runtime_api::ExchangeApi as ExchangeRuntimeApi;
+use dex_runtime_api::OrderPage;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+#[rpc]
+pub trait ExchangeApi<BlockHash, AccountId, Hash, BlockNumber, Price, OrderStatus> {
+    /// Current trigger price of a trailing stop order.
+    #[rpc(name = "dex_stopOrderTriggerPrice")]
+    fn stop_order_trigger_price(&self, order_hash: Hash, at: Option<BlockHash>)
+        -> Result<Option<Price>>;
+
+    /// Time-weighted average price of an exchange pair over at least the last `window` blocks.
+    #[rpc(name = "dex_twap")]
+    fn twap(&self, ep_hash: Hash, window: BlockNumber, at: Option<BlockHash>)
+        -> Result<Option<Price>>;
+
+    /// Open orders of an account in an exchange pair, newest first.
+    #[rpc(name = "dex_openOrders")]
+    fn open_orders(&self, owner: AccountId, ep_hash: Hash, at: Option<BlockHash>)
+        -> Result<Vec<Hash>>;
+
+    /// A page of the orders of an account in an exchange pair, oldest first.
+    #[rpc(name = "dex_orderHistory")]
+    fn order_history(
+        &self,
+        owner: AccountId,
+        ep_hash: Hash,
+        status: Option<OrderStatus>,
+        from_block: Option<BlockNumber>,
+        to_block: Option<BlockNumber>,
+        start: u64,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> Result<OrderPage<Hash>>;
+}
+
+/// Error code of a failed runtime API call.
+const RUNTIME_ERROR: i64 = 1;
+
+fn runtime_error<E: std::fmt::Debug>(e: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the exchange module.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+/// Implements the `ExchangeApi` RPC trait with the runtime API of a client.
+pub struct Exchange<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Exchange<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Exchange {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block, AccountId, Hash, BlockNumber, Price, OrderStatus>
+    ExchangeApi<<Block as BlockT>::Hash, AccountId, Hash, BlockNumber, Price, OrderStatus>
+    for Exchange<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: ExchangeRuntimeApi<Block, AccountId, Hash, BlockNumber, Price, OrderStatus>,
+    AccountId: Codec + DeserializeOwned,
+    Hash: Codec + Serialize + DeserializeOwned,
+    BlockNumber: Codec + DeserializeOwned,
+    Price: Codec + Serialize,
+    OrderStatus: Codec + DeserializeOwned,
+{
+    fn stop_order_trigger_price(
+        &self,
+        order_hash: Hash,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Price>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.stop_order_trigger_price(&at, order_hash)
+            .map_err(runtime_error)
+    }
+
+    fn twap(
+        &self,
+        ep_hash: Hash,
+        window: BlockNumber,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Price>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.twap(&at, ep_hash, window).map_err(runtime_error)
+    }
+
+    fn open_orders(
+        &self,
+        owner: AccountId,
+        ep_hash: Hash,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<Hash>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.open_orders(&at, owner, ep_hash).map_err(runtime_error)
+    }
+
+    fn order_history(
+        &self,
+        owner: AccountId,
+        ep_hash: Hash,
+        status: Option<OrderStatus>,
+        from_block: Option<BlockNumber>,
+        to_block: Option<BlockNumber>,
+        start: u64,
+        limit: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<OrderPage<Hash>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.order_history(
+            &at, owner, ep_hash, status, from_block, to_block, start, limit,
+        )
+        .map_err(runtime_error)
+    }
+}
@@ -0,0 +1,63 @@
+//! Runtime API definition for the DEX module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+
+/// A page of order hashes and the history index the next page starts at.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OrderPage<Hash> {
+    pub orders: Vec<Hash>,
+    pub next: Option<u64>,
+}
+
+sp_api::decl_runtime_apis! {
+    pub trait ExchangeApi<AccountId, Hash, BlockNumber, Price, OrderStatus> where
+        AccountId: Codec,
+        Hash: Codec,
+        BlockNumber: Codec,
+        Price: Codec,
+        OrderStatus: Codec,
+    {
+        /// Current trigger price of a trailing stop order.
+        fn stop_order_trigger_price(order_hash: Hash) -> Option<Price>;
+
+        /// Time-weighted average price of an exchange pair over at least the last `window`
+        /// blocks.
+        fn twap(ep_hash: Hash, window: BlockNumber) -> Option<Price>;
+
+        /// Open orders of an account in an exchange pair, newest first.
+        fn open_orders(owner: AccountId, ep_hash: Hash) -> Vec<Hash>;
+
+        /// Orders of an account in an exchange pair, oldest first, starting at history
+        /// index `start`, optionally filtered by status and by the block they were placed in.
+        fn order_history(
+            owner: AccountId,
+            ep_hash: Hash,
+            status: Option<OrderStatus>,
+            from_block: Option<BlockNumber>,
+            to_block: Option<BlockNumber>,
+            start: u64,
+            limit: u32,
+        ) -> OrderPage<Hash>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_page_tells_where_the_next_page_starts() {
+        let page = OrderPage { orders: vec![1u64, 2], next: Some(2) };
+        assert_eq!(serde_json::to_string(&page).unwrap(), r#"{"orders":[1,2],"next":2}"#);
+
+        let last: OrderPage<u64> = serde_json::from_str(r#"{"orders":[3],"next":null}"#).unwrap();
+        assert_eq!(last, OrderPage { orders: vec![3], next: None });
+    }
+}
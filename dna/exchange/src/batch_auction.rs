@@ -0,0 +1,519 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn queue_batch_auction(ep_hash: T::Hash) {
+        <PendingBatchPairs<T>>::mutate(|pairs| {
+            if !pairs.contains(&ep_hash) {
+                pairs.push(ep_hash);
+            }
+        });
+    }
+
+    /// Switch the matching mode of a pair, the orders waiting for a batch auction are
+    /// cleared first so none are left behind for the continuous matching engine.
+    pub fn do_set_matching_mode(ep_hash: T::Hash, mode: MatchingMode) -> DispatchResult {
+        Self::transactional(|| {
+            let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+            ensure!(ep.matching_mode != mode, Error::<T>::MatchingModeUnchanged);
+
+            if mode == MatchingMode::Continuous && !Self::batch_auction_orders(ep_hash).is_empty() {
+                Self::clear_batch_auction(ep_hash)?;
+                ensure!(
+                    Self::batch_auction_orders(ep_hash).is_empty(),
+                    Error::<T>::BatchAuctionPending
+                );
+                <PendingBatchPairs<T>>::mutate(|pairs| pairs.retain(|&x| x != ep_hash));
+            }
+
+            let mut ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+            ep.matching_mode = mode;
+            ExchangePairs::insert(ep_hash, ep);
+
+            Self::deposit_event(RawEvent::MatchingModeChanged(ep_hash, mode));
+
+            Ok(())
+        })
+    }
+
+    /// Take an order waiting for the next batch auction of a pair off the waiting list,
+    /// returns whether it was waiting.
+    pub fn take_batch_auction_order(ep_hash: T::Hash, order_hash: T::Hash) -> bool {
+        <BatchAuctionOrders<T>>::mutate(ep_hash, |orders| {
+            let len = orders.len();
+            orders.retain(|&x| x != order_hash);
+            orders.len() != len
+        })
+    }
+
+    /// Clear the book and the orders waiting for the auction at the single price that
+    /// maximizes the matched volume. Orders better than the clearing price are filled
+    /// completely, orders at the marginal price level share what is left pro rata. The
+    /// waiting orders which are still open rest in the book afterwards.
+    pub fn clear_batch_auction(ep_hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+
+            // cleared once the halt is over
+            if Self::is_pair_halted(ep_hash) {
+                Self::queue_batch_auction(ep_hash);
+                return Ok(());
+            }
+
+            let mut waiting = Vec::new();
+            for order_hash in Self::batch_auction_orders(ep_hash) {
+                let order = Self::pair_order(ep_hash, order_hash)
+                    .ok_or(Error::<T>::OrderMatchGetOrderError)?;
+                if !order.is_finished() {
+                    waiting.push(order);
+                }
+            }
+
+            let best_bid = Self::batch_best_price(ep_hash, OrderType::Buy, &waiting);
+            let best_ask = Self::batch_best_price(ep_hash, OrderType::Sell, &waiting);
+
+            if let (Some(best_bid), Some(best_ask)) = (best_bid, best_ask) {
+                if best_bid >= best_ask {
+                    Self::run_batch_auction(&ep, &waiting, best_bid, best_ask)?;
+                }
+            }
+
+            Self::rest_batch_auction_orders(ep_hash)
+        })
+    }
+
+    /// Match the crossed part of the book and the waiting orders of a pair.
+    fn run_batch_auction(
+        ep: &ExchangePair<T>,
+        waiting: &Vec<LimitOrder<T>>,
+        best_bid: T::Price,
+        best_ask: T::Price,
+    ) -> DispatchResult {
+        let ep_hash = ep.hash;
+
+        let mut bids = Self::collect_batch_side(ep_hash, OrderType::Buy, best_ask, waiting)?;
+        let mut asks = Self::collect_batch_side(ep_hash, OrderType::Sell, best_bid, waiting)?;
+
+        let (price, volume) = match Self::batch_clearing_price(&bids, &asks) {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        Self::ensure_price_band(ep_hash, Self::band_reference_price(ep), price)?;
+
+        let bid_fills = Self::allocate_batch_side(&bids, OrderType::Buy, price, volume)?;
+        let ask_fills = Self::allocate_batch_side(&asks, OrderType::Sell, price, volume)?;
+
+        let bids_before = bids.clone();
+        let asks_before = asks.clone();
+
+        let mut settlement = Settlement::<T>::default();
+        let mut bid_left = bid_fills.clone();
+        let mut ask_left = ask_fills.clone();
+        let mut i = 0;
+        let mut j = 0;
+
+        while i < bids.len() && j < asks.len() {
+            if bid_left[i] == Zero::zero() {
+                i += 1;
+                continue;
+            }
+            if ask_left[j] == Zero::zero() {
+                j += 1;
+                continue;
+            }
+
+            let quote_qty = rstd::cmp::min(bid_left[i], ask_left[j]);
+            let base_qty = Self::batch_base_amount(quote_qty, price)?;
+
+            Self::settle_batch_trade(
+                ep,
+                &mut settlement,
+                &mut bids[i],
+                &mut asks[j],
+                base_qty,
+                quote_qty,
+            )?;
+
+            let mut dex = Dex::new(ep.base, ep.quote, &asks[j], &bids[i], base_qty, quote_qty);
+            dex.price = price;
+            let dex_hash = dex.hash;
+            Self::record_exchange(ep_hash, asks[j].hash, bids[i].hash, dex);
+
+            let bid_filled = Self::batch_order_filled(&bids[i]);
+            let ask_filled = Self::batch_order_filled(&asks[j]);
+            Self::deposit_fill_event(&bids[i], dex_hash, base_qty, quote_qty, bid_filled);
+            Self::deposit_fill_event(&asks[j], dex_hash, base_qty, quote_qty, ask_filled);
+
+            bid_left[i] = bid_left[i] - quote_qty;
+            ask_left[j] = ask_left[j] - quote_qty;
+        }
+
+        for (k, order) in bids.iter_mut().enumerate() {
+            if bid_fills[k] != Zero::zero() {
+                let in_book = !waiting.iter().any(|o| o.hash == order.hash);
+                Self::finish_batch_order(ep, &mut settlement, order, &bids_before[k], in_book)?;
+            }
+        }
+
+        for (k, order) in asks.iter_mut().enumerate() {
+            if ask_fills[k] != Zero::zero() {
+                let in_book = !waiting.iter().any(|o| o.hash == order.hash);
+                Self::finish_batch_order(ep, &mut settlement, order, &asks_before[k], in_book)?;
+            }
+        }
+
+        settlement.apply()?;
+
+        Self::set_ep_market_data(ep_hash, price, volume)?;
+
+        Self::deposit_event(RawEvent::BatchAuctionCleared(ep_hash, price, volume));
+
+        Ok(())
+    }
+
+    /// The best price of one side of the book and the waiting orders, `None` for an empty
+    /// side.
+    fn batch_best_price(
+        ep_hash: T::Hash,
+        otype: OrderType,
+        waiting: &Vec<LimitOrder<T>>,
+    ) -> Option<T::Price> {
+        let head = <OrderLinkedItemList<T>>::read_head(ep_hash);
+        let book_price = Self::next_match_price(&head, otype)
+            .filter(|&p| p != T::Price::min_value() && p != T::Price::max_value());
+
+        let prices = waiting
+            .iter()
+            .filter(|o| o.otype == otype)
+            .map(|o| o.price)
+            .chain(book_price);
+
+        match otype {
+            OrderType::Buy => prices.max(),
+            OrderType::Sell => prices.min(),
+        }
+    }
+
+    /// Read the open orders of one side of the book and the waiting orders which can trade
+    /// at `limit`, best price first and in time priority inside a price level. The waiting
+    /// orders arrived after the ones in the book.
+    fn collect_batch_side(
+        ep_hash: T::Hash,
+        otype: OrderType,
+        limit: T::Price,
+        waiting: &Vec<LimitOrder<T>>,
+    ) -> result::Result<Vec<LimitOrder<T>>, DispatchError> {
+        let end_item_price;
+        if otype == OrderType::Buy {
+            end_item_price = Some(T::Price::min_value());
+        } else {
+            end_item_price = Some(T::Price::max_value());
+        }
+
+        let mut orders = Vec::new();
+        let head = <OrderLinkedItemList<T>>::read_head(ep_hash);
+        let mut item_price = Self::next_match_price(&head, otype);
+
+        while item_price != end_item_price {
+            let price = item_price.ok_or(Error::<T>::OrderMatchGetPriceError)?;
+            if !Self::price_matched(price, otype, limit) {
+                break;
+            }
+
+            let item = <LinkedItemList<T>>::get(ep_hash, Some(price))
+                .ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
+            for o in <OrderLinkedItemList<T>>::level_orders(ep_hash, price).iter() {
+                let o = Self::pair_order(ep_hash, o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+                if o.otype == otype && !o.is_finished() {
+                    orders.push(o);
+                }
+            }
+
+            item_price = Self::next_match_price(&item, otype);
+        }
+
+        for o in waiting.iter() {
+            if o.otype == otype && Self::price_matched(o.price, otype, limit) {
+                orders.push(o.clone());
+            }
+        }
+
+        // stable, so the time priority inside a price level is kept
+        match otype {
+            OrderType::Buy => orders.sort_by(|a, b| b.price.cmp(&a.price)),
+            OrderType::Sell => orders.sort_by(|a, b| a.price.cmp(&b.price)),
+        }
+
+        // only the best member of a one-cancels-other group takes part
+        let mut groups = Vec::new();
+        orders.retain(|o| match o.group {
+            Some(group_hash) if groups.contains(&group_hash) => false,
+            Some(group_hash) => {
+                groups.push(group_hash);
+                true
+            }
+            None => true,
+        });
+
+        Ok(orders)
+    }
+
+    /// Put the waiting orders which are still open into the book. An order which would
+    /// cross the book keeps waiting for the next auction, so the book is never crossed.
+    fn rest_batch_auction_orders(ep_hash: T::Hash) -> DispatchResult {
+        let mut still_waiting = Vec::new();
+
+        for order_hash in <BatchAuctionOrders<T>>::take(ep_hash) {
+            let mut order = Self::pair_order(ep_hash, order_hash)
+                .ok_or(Error::<T>::OrderMatchGetOrderError)?;
+            if order.is_finished() {
+                continue;
+            }
+
+            let opposite = match order.otype {
+                OrderType::Buy => OrderType::Sell,
+                OrderType::Sell => OrderType::Buy,
+            };
+            let crossed = Self::batch_best_price(ep_hash, opposite, &Vec::new())
+                .map_or(false, |p| Self::price_matched(order.price, order.otype, p));
+
+            if crossed {
+                still_waiting.push(order_hash);
+            } else {
+                Self::rest_order(ep_hash, &mut order)?;
+            }
+        }
+
+        if !still_waiting.is_empty() {
+            <BatchAuctionOrders<T>>::insert(ep_hash, still_waiting);
+            Self::queue_batch_auction(ep_hash);
+        }
+
+        Ok(())
+    }
+
+    /// The amount of quote asset an order can trade in the auction.
+    fn batch_quantity(order: &LimitOrder<T>) -> T::Balance {
+        match order.otype {
+            OrderType::Buy => order.remained_buy_amount,
+            OrderType::Sell => order.remained_sell_amount,
+        }
+    }
+
+    /// Pick the price with the highest matched volume, then the smallest imbalance,
+    /// then the lowest price. Returns the price and the matched quote volume.
+    fn batch_clearing_price(
+        bids: &Vec<LimitOrder<T>>,
+        asks: &Vec<LimitOrder<T>>,
+    ) -> Option<(T::Price, T::Balance)> {
+        let mut candidates: Vec<T::Price> = bids.iter().chain(asks.iter()).map(|o| o.price).collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let mut best: Option<(T::Price, T::Balance, T::Balance)> = None;
+
+        for price in candidates {
+            let demand = bids
+                .iter()
+                .filter(|o| o.price >= price)
+                .fold(T::Balance::zero(), |acc, o| acc.saturating_add(Self::batch_quantity(o)));
+            let supply = asks
+                .iter()
+                .filter(|o| o.price <= price)
+                .fold(T::Balance::zero(), |acc, o| acc.saturating_add(Self::batch_quantity(o)));
+
+            let volume = rstd::cmp::min(demand, supply);
+            let imbalance = rstd::cmp::max(demand, supply) - volume;
+
+            if volume == Zero::zero() {
+                continue;
+            }
+
+            let better = match best {
+                None => true,
+                Some((_, best_volume, best_imbalance)) => {
+                    volume > best_volume || (volume == best_volume && imbalance < best_imbalance)
+                }
+            };
+
+            if better {
+                best = Some((price, volume, imbalance));
+            }
+        }
+
+        best.map(|(price, volume, _)| (price, volume))
+    }
+
+    /// Split `volume` over one side of the book. Whole price levels are filled best first,
+    /// the level where the volume runs out is filled pro rata and its rounding remainder
+    /// goes to the earliest orders of that level.
+    fn allocate_batch_side(
+        orders: &Vec<LimitOrder<T>>,
+        otype: OrderType,
+        price: T::Price,
+        volume: T::Balance,
+    ) -> result::Result<Vec<T::Balance>, DispatchError> {
+        let mut fills: Vec<T::Balance> = orders.iter().map(|_| Zero::zero()).collect();
+        let mut remained = volume;
+        let mut start = 0;
+
+        while start < orders.len() && remained != Zero::zero() {
+            let level_price = orders[start].price;
+            if !Self::price_matched(level_price, otype, price) {
+                break;
+            }
+
+            let mut end = start;
+            let mut level_total = T::Balance::zero();
+            while end < orders.len() && orders[end].price == level_price {
+                level_total = level_total.saturating_add(Self::batch_quantity(&orders[end]));
+                end += 1;
+            }
+
+            if level_total <= remained {
+                for k in start..end {
+                    fills[k] = Self::batch_quantity(&orders[k]);
+                }
+                remained = remained - level_total;
+            } else {
+                let remained_u256 = U256::from(Self::into_128(remained)?);
+                let level_total_u256 = U256::from(Self::into_128(level_total)?);
+                let mut allocated = T::Balance::zero();
+
+                for k in start..end {
+                    let qty_u256 = U256::from(Self::into_128(Self::batch_quantity(&orders[k]))?);
+                    let share: u128 = (qty_u256 * remained_u256 / level_total_u256)
+                        .try_into()
+                        .map_err(|_| Error::<T>::OverflowError)?;
+                    fills[k] = Self::from_128(share)?;
+                    allocated = allocated + fills[k];
+                }
+
+                let mut dust = remained - allocated;
+                let one: T::Balance = Self::from_128(1)?;
+                for k in start..end {
+                    if dust == Zero::zero() {
+                        break;
+                    }
+                    if fills[k] < Self::batch_quantity(&orders[k]) {
+                        fills[k] = fills[k] + one;
+                        dust = dust - one;
+                    }
+                }
+
+                remained = Zero::zero();
+            }
+
+            start = end;
+        }
+
+        Ok(fills)
+    }
+
+    fn batch_base_amount(
+        quote_qty: T::Balance,
+        price: T::Price,
+    ) -> result::Result<T::Balance, DispatchError> {
+        let base_qty: u128 = (U256::from(Self::into_128(quote_qty)?)
+            * U256::from(Self::into_128(price)?)
+            / U256::from(T::PriceFactor::get()))
+        .try_into()
+        .map_err(|_| Error::<T>::OverflowError)?;
+
+        Self::from_128(base_qty)
+    }
+
+    fn settle_batch_trade(
+        ep: &ExchangePair<T>,
+        settlement: &mut Settlement<T>,
+        bid: &mut LimitOrder<T>,
+        ask: &mut LimitOrder<T>,
+        base_qty: T::Balance,
+        quote_qty: T::Balance,
+    ) -> DispatchResult {
+        settlement.pay_frozen(&bid.owner, ep.base, &ask.owner, base_qty)?;
+        settlement.pay_frozen(&ask.owner, ep.quote, &bid.owner, quote_qty)?;
+
+        bid.remained_sell_amount = bid
+            .remained_sell_amount
+            .checked_sub(&base_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+        bid.remained_buy_amount = bid
+            .remained_buy_amount
+            .checked_sub(&quote_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+
+        // the seller may receive more than asked for when the clearing price is above its limit
+        ask.remained_sell_amount = ask
+            .remained_sell_amount
+            .checked_sub(&quote_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+        ask.remained_buy_amount = ask.remained_buy_amount.saturating_sub(base_qty);
+
+        Ok(())
+    }
+
+    /// A buy order is filled once it got all the quote it asked for, a sell order once it
+    /// sold all its quote, as it may receive more base than asked for.
+    fn batch_order_filled(order: &LimitOrder<T>) -> bool {
+        match order.otype {
+            OrderType::Buy => order.remained_buy_amount == Zero::zero(),
+            OrderType::Sell => order.remained_sell_amount == Zero::zero(),
+        }
+    }
+
+    /// Update status, leftover funds, the account order lists and, for an order `in_book`,
+    /// the price level of an order that traded in the auction.
+    fn finish_batch_order(
+        ep: &ExchangePair<T>,
+        settlement: &mut Settlement<T>,
+        order: &mut LimitOrder<T>,
+        before: &LimitOrder<T>,
+        in_book: bool,
+    ) -> DispatchResult {
+        if let Some(group_hash) = order.group {
+            Self::trigger_order_group(group_hash, order)?;
+        }
+
+        let filled = Self::batch_order_filled(order);
+
+        if filled {
+            order.status = OrderStatus::Filled;
+            order.remained_buy_amount = Zero::zero();
+            if order.remained_sell_amount != Zero::zero() {
+                let give = match order.otype {
+                    OrderType::Buy => ep.base,
+                    OrderType::Sell => ep.quote,
+                };
+                settlement.release(&order.owner, give, order.remained_sell_amount)?;
+                order.remained_sell_amount = Zero::zero();
+            }
+
+            Self::close_order(order.owner.clone(), ep.hash, order.hash);
+
+            ensure!(order.is_finished(), Error::<T>::OrderMatchOrderIsNotFinished);
+        } else {
+            order.status = OrderStatus::PartialFilled;
+        }
+
+        if in_book {
+            let sell_amount = before.remained_sell_amount - order.remained_sell_amount;
+            let buy_amount = before.remained_buy_amount - order.remained_buy_amount;
+
+            Self::apply_level_fill(ep.hash, order, sell_amount, buy_amount)?;
+
+            if filled {
+                <OrderLinkedItemList<T>>::remove_order(
+                    ep.hash,
+                    order.price,
+                    order.hash,
+                    Zero::zero(),
+                    Zero::zero(),
+                )?;
+            }
+        }
+
+        <Orders<T>>::insert(ep.hash, order.hash, order.clone());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! Benchmarks of the price level index, with `l` levels resting on the buy side of the book.
+//! Run with the `runtime-benchmarks` feature of the node, e.g.
+//! `dna-node benchmark --chain dev --pallet dex --extrinsic insert_level --steps 10 --repeat 20`.
+
+use super::*;
+use frame_benchmarking::benchmarks;
+use support::traits::Currency;
+use system::RawOrigin;
+
+const MAX_LEVELS: u32 = 10_000;
+
+fn account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+    let entropy = (name, index).using_encoded(<T as system::Trait>::Hashing::hash);
+    T::AccountId::decode(&mut entropy.as_ref()).unwrap_or_default()
+}
+
+/// A trader holding all of `BASE` and `QUOTE`, the exchange pair it listed and `l` buy levels
+/// at the even prices from 2 to `2 * l`. The levels only hold placeholder orders, the
+/// benchmarked orders never match them.
+fn book<T: Trait>(l: u32) -> result::Result<(T::AccountId, T::Hash, T::Hash), &'static str> {
+    let trader = account::<T>("trader", 0);
+    let supply = T::Balance::max_value() / T::Balance::from(4u32);
+    <balances::Module<T> as Currency<_>>::make_free_balance_be(&trader, supply);
+
+    for symbol in vec![b"BASE".to_vec(), b"QUOTE".to_vec()] {
+        <assets::Module<T>>::issue(RawOrigin::Signed(trader.clone()).into(), symbol, supply)?;
+    }
+    let base = <assets::Module<T>>::owned_asset((trader.clone(), 0)).ok_or("BASE issued")?;
+    let quote = <assets::Module<T>>::owned_asset((trader.clone(), 1)).ok_or("QUOTE issued")?;
+
+    <Module<T>>::do_create_exchange_pair(trader.clone(), base, quote, MatchingMode::Continuous)?;
+    let ep_hash = <Module<T>>::ensure_exchange_pair(base, quote)?;
+
+    for i in 1..=l {
+        let placeholder = (b"level", i).using_encoded(<T as system::Trait>::Hashing::hash);
+        <OrderLinkedItemList<T>>::append(
+            ep_hash,
+            T::Price::from(2 * i as u128),
+            placeholder,
+            T::Balance::from(2 * i),
+            T::Balance::from(1u32),
+            OrderType::Buy,
+        );
+    }
+
+    Ok((trader, base, quote))
+}
+
+/// Place a buy order at `price` selling `price` base for `PriceFactor` quote, amounts that are
+/// exact at any price.
+fn place_bid<T: Trait>(
+    trader: &T::AccountId,
+    base: T::Hash,
+    quote: T::Hash,
+    price: u32,
+) -> result::Result<T::Hash, &'static str> {
+    <Module<T>>::do_create_limit_order(
+        trader.clone(),
+        base,
+        quote,
+        OrderType::Buy,
+        T::Price::from(price as u128),
+        T::Balance::from(price),
+    )?;
+
+    let ep_hash = <Module<T>>::ensure_exchange_pair(base, quote)?;
+    let orders = <Module<T>>::owned_ep_opened_orders(trader, ep_hash).unwrap_or_default();
+    orders.last().copied().ok_or("order placed")
+}
+
+benchmarks! {
+    _ {
+        let l in 1 .. MAX_LEVELS => ();
+    }
+
+    // a buy order opening a level in the middle of the book
+    insert_level {
+        let l in ...;
+        let (trader, base, quote) = book::<T>(l)?;
+        let price = l | 1;
+    }: create_order(
+        RawOrigin::Signed(trader),
+        base,
+        quote,
+        OrderType::Buy,
+        T::Price::from(price as u128),
+        T::Balance::from(price)
+    )
+
+    // canceling the only order of a level in the middle of the book
+    remove_level {
+        let l in ...;
+        let (trader, base, quote) = book::<T>(l)?;
+        let order_hash = place_bid::<T>(&trader, base, quote, l | 1)?;
+    }: cancel_order(RawOrigin::Signed(trader), order_hash)
+
+    // a sell order filling the best bid, whose level is then removed
+    match_best_level {
+        let l in ...;
+        let (trader, base, quote) = book::<T>(l)?;
+        let price = 2 * l + 1;
+        place_bid::<T>(&trader, base, quote, price)?;
+
+        let taker = account::<T>("taker", 0);
+        let deposit = T::OrderDeposit::get() * T::Balance::from(10u32);
+        <balances::Module<T> as Currency<_>>::make_free_balance_be(&taker, deposit);
+        let amount = <Module<T>>::from_128::<T::Balance>(T::PriceFactor::get())?;
+        <assets::Module<T>>::transfer(trader, quote, taker.clone(), amount)?;
+    }: create_order(
+        RawOrigin::Signed(taker),
+        base,
+        quote,
+        OrderType::Sell,
+        T::Price::from(price as u128),
+        amount
+    )
+}
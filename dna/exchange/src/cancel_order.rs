@@ -0,0 +1,74 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn do_cancel_limit_order(sender: T::AccountId, order_hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let mut order = Self::order(order_hash).ok_or(Error::<T>::NoMatchingOrder)?;
+
+            ensure!(order.owner == sender, Error::<T>::CanOnlyCancelOwnOrder);
+            ensure!(
+                !order.is_finished(),
+                Error::<T>::CanOnlyCancelNotFinishedOrder
+            );
+
+            let ep_hash = Self::ensure_exchange_pair(order.base, order.quote)?;
+
+            match order.group {
+                Some(group_hash) => {
+                    // the funds are shared with the rest of the group
+                    order.group = None;
+                    Self::leave_order_group(group_hash, order.hash)?;
+                    Self::cancel_order_in_book(ep_hash, &mut order, false)
+                }
+                None => Self::cancel_order_in_book(ep_hash, &mut order, true),
+            }
+        })
+    }
+
+    /// Take an open order off the book and mark it canceled, `unfreeze` releases the
+    /// funds the order still holds.
+    pub fn cancel_order_in_book(
+        ep_hash: T::Hash,
+        order: &mut LimitOrder<T>,
+        unfreeze: bool,
+    ) -> DispatchResult {
+        // an order waiting for a batch auction is not in the book yet
+        if !Self::take_batch_auction_order(ep_hash, order.hash) {
+            let (sell_amount, buy_amount) = Self::level_amounts(order)?;
+            <OrderLinkedItemList<T>>::remove_order(
+                ep_hash,
+                order.price,
+                order.hash,
+                sell_amount,
+                buy_amount,
+            )?;
+        }
+
+        Self::cancel_open_order(ep_hash, order, unfreeze)
+    }
+
+    /// Mark an open order which is not in the book canceled, `unfreeze` releases the funds
+    /// the order still holds.
+    pub fn cancel_open_order(
+        ep_hash: T::Hash,
+        order: &mut LimitOrder<T>,
+        unfreeze: bool,
+    ) -> DispatchResult {
+        if unfreeze && order.remained_sell_amount != Zero::zero() {
+            let give = match order.otype {
+                OrderType::Buy => order.base,
+                OrderType::Sell => order.quote,
+            };
+            <assets::Module<T>>::unfreeze(order.owner.clone(), give, order.remained_sell_amount)?;
+        }
+
+        order.status = OrderStatus::Canceled;
+        <Orders<T>>::insert(ep_hash, order.hash, order.clone());
+
+        Self::close_order(order.owner.clone(), ep_hash, order.hash);
+
+        Self::deposit_event(RawEvent::OrderCanceled(order.owner.clone(), order.hash));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// The circuit breaker of a pair, the global one unless governance set one for the pair.
+    pub fn circuit_breaker(ep_hash: T::Hash) -> CircuitBreaker<T::BlockNumber> {
+        Self::pair_circuit_breaker(ep_hash).unwrap_or_else(Self::global_circuit_breaker)
+    }
+
+    /// The circuit breaker in effect for a pair, or the global one for `None`.
+    pub fn circuit_breaker_of(ep_hash: Option<T::Hash>) -> CircuitBreaker<T::BlockNumber> {
+        match ep_hash {
+            Some(ep_hash) => Self::circuit_breaker(ep_hash),
+            None => Self::global_circuit_breaker(),
+        }
+    }
+
+    pub fn is_pair_halted(ep_hash: T::Hash) -> bool {
+        Self::halted_pairs()
+            .iter()
+            .any(|(hash, until)| *hash == ep_hash && *until > <system::Module<T>>::block_number())
+    }
+
+    pub fn ensure_pair_not_halted(ep_hash: T::Hash) -> DispatchResult {
+        ensure!(!Self::is_pair_halted(ep_hash), Error::<T>::ExchangePairHalted);
+        Ok(())
+    }
+
+    /// The center of the pair's price band, the reference price of the pair or its latest
+    /// matched price.
+    pub fn band_reference_price(ep: &ExchangePair<T>) -> Option<T::Price> {
+        T::ReferencePrice::reference_price(ep.hash).or(ep.latest_matched_price)
+    }
+
+    /// Reject a trade at `price` further than the pair's band from `reference`, a pair
+    /// without a reference price has not traded yet and trades anywhere.
+    pub fn ensure_price_band(
+        ep_hash: T::Hash,
+        reference: Option<T::Price>,
+        price: T::Price,
+    ) -> DispatchResult {
+        let breaker = Self::circuit_breaker(ep_hash);
+
+        if let Some(reference) = reference {
+            if breaker.band_bps != 0 {
+                ensure!(
+                    !Self::price_moved_beyond(reference, price, breaker.band_bps)?,
+                    Error::<T>::PriceOutsideBand
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Halt the pair when a trade at `price` moved the price further than allowed within the
+    /// window, which starts at the first trade after the previous window ended.
+    pub fn check_price_move(ep_hash: T::Hash, price: T::Price) -> DispatchResult {
+        let breaker = Self::circuit_breaker(ep_hash);
+        if breaker.halt_move_bps == 0 {
+            return Ok(());
+        }
+
+        let now = <system::Module<T>>::block_number();
+        let reference = match Self::price_window(ep_hash) {
+            Some((start, reference)) if now < start + breaker.window => reference,
+            _ => {
+                <PriceWindows<T>>::insert(ep_hash, (now, price));
+                return Ok(());
+            }
+        };
+
+        if Self::price_moved_beyond(reference, price, breaker.halt_move_bps)? {
+            let until = now + breaker.cooldown;
+            <HaltedPairs<T>>::mutate(|pairs| {
+                pairs.retain(|(hash, _)| *hash != ep_hash);
+                pairs.push((ep_hash, until));
+            });
+            <PriceWindows<T>>::remove(ep_hash);
+
+            Self::deposit_event(RawEvent::ExchangePairHalted(ep_hash, until, reference, price));
+        }
+
+        Ok(())
+    }
+
+    /// Lift the halts whose cooldown is over.
+    pub fn resume_halted_pairs(n: T::BlockNumber) {
+        let halted = Self::halted_pairs();
+        if halted.iter().all(|(_, until)| *until > n + One::one()) {
+            return;
+        }
+
+        let mut remained = Vec::new();
+        for (ep_hash, until) in halted {
+            if until > n + One::one() {
+                remained.push((ep_hash, until));
+            } else {
+                Self::deposit_event(RawEvent::ExchangePairResumed(ep_hash));
+            }
+        }
+        <HaltedPairs<T>>::put(remained);
+    }
+
+    pub fn do_resume_pair(ep_hash: T::Hash) -> DispatchResult {
+        ensure!(Self::is_pair_halted(ep_hash), Error::<T>::ExchangePairNotHalted);
+
+        <HaltedPairs<T>>::mutate(|pairs| pairs.retain(|(hash, _)| *hash != ep_hash));
+        <PriceWindows<T>>::remove(ep_hash);
+
+        Self::deposit_event(RawEvent::ExchangePairResumed(ep_hash));
+
+        Ok(())
+    }
+
+    fn price_moved_beyond(
+        reference: T::Price,
+        price: T::Price,
+        bps: u32,
+    ) -> result::Result<bool, DispatchError> {
+        let reference = U256::from(Self::into_128(reference)?);
+        let price = U256::from(Self::into_128(price)?);
+
+        let moved = if price > reference {
+            price - reference
+        } else {
+            reference - price
+        };
+
+        Ok(moved * U256::from(10_000u32) > reference * U256::from(bps))
+    }
+}
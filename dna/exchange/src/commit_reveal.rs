@@ -0,0 +1,131 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// The commitment of an order is the hash of the SCALE encoded
+    /// `(owner, base, quote, otype, price, sell_amount, salt)` tuple.
+    pub fn order_commitment_hash(
+        owner: &T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        price: T::Price,
+        sell_amount: T::Balance,
+        salt: T::Hash,
+    ) -> T::Hash {
+        (owner.clone(), base, quote, otype, price, sell_amount, salt)
+            .using_encoded(<T as system::Trait>::Hashing::hash)
+    }
+
+    pub fn do_commit_order(
+        sender: T::AccountId,
+        commitment: T::Hash,
+        asset: T::Hash,
+        collateral: T::Balance,
+    ) -> DispatchResult {
+        ensure!(collateral > Zero::zero(), Error::<T>::BoundsCheckFailed);
+        ensure!(
+            !<OrderCommitments<T>>::contains_key(&sender, commitment),
+            Error::<T>::CommitmentExisted
+        );
+
+        <assets::Module<T>>::ensure_free_balance(sender.clone(), asset, collateral)?;
+        <assets::Module<T>>::freeze(sender.clone(), asset, collateral)?;
+
+        let now = <system::Module<T>>::block_number();
+        let expires_at = now + T::CommitRevealWindow::get();
+
+        let oc = OrderCommitment {
+            owner: sender.clone(),
+            asset,
+            collateral,
+            committed_at: now,
+            expires_at,
+        };
+
+        <OrderCommitments<T>>::insert(&sender, commitment, oc);
+        <CommitmentsByExpiry<T>>::mutate(expires_at, |commitments| {
+            commitments.push((sender.clone(), commitment))
+        });
+
+        Self::deposit_event(RawEvent::OrderCommitted(sender, commitment));
+
+        Ok(())
+    }
+
+    pub fn do_reveal_order(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        price: T::Price,
+        sell_amount: T::Balance,
+        salt: T::Hash,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            let commitment =
+                Self::order_commitment_hash(&sender, base, quote, otype, price, sell_amount, salt);
+            let oc = Self::order_commitment(&sender, commitment)
+                .ok_or(Error::<T>::NoMatchingCommitment)?;
+
+            let now = <system::Module<T>>::block_number();
+            ensure!(
+                now > oc.committed_at && now <= oc.expires_at,
+                Error::<T>::CommitmentNotRevealable
+            );
+
+            let op_asset_hash;
+            match otype {
+                OrderType::Buy => op_asset_hash = base,
+                OrderType::Sell => op_asset_hash = quote,
+            };
+
+            ensure!(oc.asset == op_asset_hash, Error::<T>::CommitmentAssetMismatch);
+            ensure!(
+                sell_amount <= oc.collateral,
+                Error::<T>::CommitmentCollateralNotEnough
+            );
+
+            Self::remove_commitment(&sender, commitment, oc.expires_at);
+            <assets::Module<T>>::unfreeze(sender.clone(), oc.asset, oc.collateral)?;
+
+            Self::deposit_event(RawEvent::OrderRevealed(sender.clone(), commitment));
+
+            Self::do_create_limit_order(sender, base, quote, otype, price, sell_amount)
+        })
+    }
+
+    /// Refund the commitments that were never revealed, burning the configured penalty.
+    pub fn expire_commitments(n: T::BlockNumber) {
+        for (owner, commitment) in <CommitmentsByExpiry<T>>::take(n) {
+            if let Some(oc) = <OrderCommitments<T>>::take(&owner, commitment) {
+                if let Err(e) = Self::refund_commitment(commitment, oc) {
+                    support::debug::warn!("order commitment {:?} refund failed: {:?}", commitment, e);
+                }
+            }
+        }
+    }
+
+    fn refund_commitment(commitment: T::Hash, oc: OrderCommitment<T>) -> DispatchResult {
+        let penalty = T::UnrevealedCommitmentPenalty::get() * oc.collateral;
+        let refund = oc.collateral - penalty;
+
+        <assets::Module<T>>::unfreeze(oc.owner.clone(), oc.asset, oc.collateral)?;
+        if penalty > Zero::zero() {
+            // taken off the supply, nobody profits from a commitment expiring
+            <assets::Module<T>>::burn_from(oc.owner.clone(), oc.asset, penalty)?;
+        }
+
+        Self::deposit_event(RawEvent::CommitmentExpired(
+            oc.owner, commitment, refund, penalty,
+        ));
+
+        Ok(())
+    }
+
+    fn remove_commitment(owner: &T::AccountId, commitment: T::Hash, expires_at: T::BlockNumber) {
+        <OrderCommitments<T>>::remove(owner, commitment);
+        <CommitmentsByExpiry<T>>::mutate(expires_at, |commitments| {
+            commitments.retain(|(who, x)| !(who == owner && *x == commitment))
+        });
+    }
+}
@@ -9,77 +9,188 @@ impl<T: Trait> Module<T> {
         price: T::Price,
         sell_amount: T::Balance,
     ) -> DispatchResult {
-        Self::ensure_bounds(price, sell_amount)?;
-        let buy_amount = Self::ensure_counterparty_amount_bounds(otype, price, sell_amount)?;
-
-        let ep_hash = Self::ensure_exchange_pair(base, quote)?;
-
-        let op_asset_hash;
-        match otype {
-            OrderType::Buy => op_asset_hash = base,
-            OrderType::Sell => op_asset_hash = quote,
-        };
-
-        let mut order = LimitOrder::new(
+        Self::do_create_order(
+            sender,
             base,
             quote,
-            sender.clone(),
+            otype,
             price,
+            OrderSizing::Spend,
             sell_amount,
-            buy_amount,
-            otype,
-        );
-        let hash = order.hash;
+            None,
+            None,
+        )
+    }
+
+    /// Create a limit order, an iceberg order when `display_amount` is given.
+    /// `amount` is spent or received as given by `sizing`.
+    /// Members of an order `group` use the funds frozen for the group.
+    pub fn do_create_order(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        price: T::Price,
+        sizing: OrderSizing,
+        amount: T::Balance,
+        display_amount: Option<T::Balance>,
+        group: Option<T::Hash>,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            let (sell_amount, buy_amount) = Self::order_amounts(otype, price, sizing, amount)?;
 
-        <assets::Module<T>>::ensure_free_balance(sender.clone(), op_asset_hash, sell_amount)?;
-        <assets::Module<T>>::freeze(sender.clone(), op_asset_hash, sell_amount)?;
-        Orders::insert(hash, order.clone());
+            let ep_hash = Self::ensure_exchange_pair(base, quote)?;
+            let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+            Self::ensure_pair_not_halted(ep_hash)?;
 
-        Nonce::mutate(|n| *n += 1);
-        <Orderbook<T>>::add_to_order_book(order.hash, order.clone());
-        Self::deposit_event(RawEvent::OrderCreated(
-            sender.clone(),
-            base,
-            quote,
-            hash,
-            order.clone(),
-        ));
-        <OwnedEPOpenedOrders<T>>::add_order(sender.clone(), ep_hash, order.hash);
-
-        let owned_index = Self::owned_orders_index(sender.clone());
-        OwnedOrders::<T>::insert((sender.clone(), owned_index), hash);
-        OwnedOrdersIndex::<T>::insert(sender.clone(), owned_index + 1);
-
-        let ep_owned_index = Self::exchange_pair_owned_order_index(ep_hash);
-        ExchangePairOwnedOrders::<T>::insert((ep_hash, ep_owned_index), hash);
-        ExchangePairOwnedOrdersIndex::<T>::insert(ep_hash, ep_owned_index + 1);
-
-        // order match
-        let filled = Self::order_match(ep_hash, &mut order)?;
-
-        // add order to the market order list
-        if !filled {
-            <OrderLinkedItemList<T>>::append(
-                ep_hash,
+            let op_asset_hash;
+            match otype {
+                OrderType::Buy => op_asset_hash = base,
+                OrderType::Sell => op_asset_hash = quote,
+            };
+
+            let mut order = LimitOrder::new(
+                base,
+                quote,
+                sender.clone(),
                 price,
-                hash,
-                order.remained_sell_amount,
-                order.remained_buy_amount,
+                sell_amount,
+                buy_amount,
                 otype,
             );
-        } else {
-            <OwnedEPOpenedOrders<T>>::remove_order(sender.clone(), ep_hash, order.hash);
-            <OwnedEPClosedOrders<T>>::add_order(sender.clone(), ep_hash, order.hash);
+            if let Some(display_amount) = display_amount {
+                ensure!(
+                    display_amount > Zero::zero() && display_amount < sell_amount,
+                    Error::<T>::IcebergDisplayAmountInvalid
+                );
+                order.iceberg = Some(Iceberg {
+                    display_amount,
+                    visible_sell_amount: display_amount,
+                });
+            }
+            order.group = group;
+            order.sizing = sizing;
+            let hash = order.hash;
+
+            Self::open_order(sender.clone(), ep_hash, hash)?;
+
+            match group {
+                Some(group_hash) => Self::add_order_group_member(group_hash, hash),
+                None => {
+                    <assets::Module<T>>::ensure_free_balance(sender.clone(), op_asset_hash, sell_amount)?;
+                    <assets::Module<T>>::freeze(sender.clone(), op_asset_hash, sell_amount)?;
+                }
+            }
+            <Orders<T>>::insert(ep_hash, hash, order.clone());
+            <OrderPairs<T>>::insert(hash, ep_hash);
+
+            Nonce::mutate(|n| *n += 1);
+            <Orderbook<T>>::add_to_order_book(order.hash, order.clone());
+            Self::deposit_event(RawEvent::OrderCreated(
+                sender.clone(),
+                base,
+                quote,
+                hash,
+                order.clone(),
+            ));
+
+            let owned_index = Self::owned_orders_index(sender.clone());
+            OwnedOrders::<T>::insert(&sender, owned_index, hash);
+            OwnedOrdersIndex::<T>::insert(sender.clone(), owned_index + 1);
+
+            let ep_owned_index = Self::exchange_pair_owned_order_index(ep_hash);
+            ExchangePairOwnedOrders::<T>::insert(ep_hash, ep_owned_index, hash);
+            ExchangePairOwnedOrdersIndex::<T>::insert(ep_hash, ep_owned_index + 1);
+
+            // batch auction orders wait outside the book until the pair is cleared in
+            // `on_finalize`, so orders of both sides never share a price level
+            if ep.matching_mode == MatchingMode::BatchAuction {
+                <BatchAuctionOrders<T>>::mutate(ep_hash, |orders| orders.push(hash));
+                Self::queue_batch_auction(ep_hash);
+
+                return Ok(());
+            }
+
+            // order match
+            let filled = Self::order_match(ep_hash, &mut order)?;
+
+            // add order to the market order list
+            if filled {
+                Self::close_order(sender.clone(), ep_hash, order.hash);
+            } else if Self::is_pair_halted(ep_hash) {
+                // a trade of the match halted the pair before the order was done with the
+                // book, at its price the rest could cross the levels left on the other side
+                match order.group.take() {
+                    Some(group_hash) => {
+                        Self::leave_order_group(group_hash, order.hash)?;
+                        Self::cancel_open_order(ep_hash, &mut order, false)?;
+                    }
+                    None => Self::cancel_open_order(ep_hash, &mut order, true)?,
+                }
+            } else {
+                Self::rest_order(ep_hash, &mut order)?;
+            }
+            // let i = <OrderBook<T>>::mutate(|r| {
+            //     r.push(Some(order));
+            //     owned_index;
+            // });
+            // Self::deposit_event(RawEvent::RegistrarAdded(i));
+
+            Ok(())
+        })
+    }
+
+    /// Put the unfilled part of an order into its price level, only the visible slice of
+    /// an iceberg order counts towards the level amounts.
+    pub fn rest_order(ep_hash: T::Hash, order: &mut LimitOrder<T>) -> DispatchResult {
+        if let Some(iceberg) = order.iceberg.as_mut() {
+            if iceberg.visible_sell_amount > order.remained_sell_amount {
+                iceberg.visible_sell_amount = order.remained_sell_amount;
+            }
+            <Orders<T>>::insert(ep_hash, order.hash, order.clone());
         }
-        // let i = <OrderBook<T>>::mutate(|r| {
-        //     r.push(Some(order));
-        //     owned_index;
-        // });
-        // Self::deposit_event(RawEvent::RegistrarAdded(i));
+
+        let (sell_amount, buy_amount) = Self::level_amounts(order)?;
+
+        <OrderLinkedItemList<T>>::append(
+            ep_hash,
+            order.price,
+            order.hash,
+            sell_amount,
+            buy_amount,
+            order.otype,
+        );
 
         Ok(())
     }
 
+    /// Amounts an order gives and receives. An order sized by what it receives gives the
+    /// most it can cost at its limit price, rounded up.
+    pub fn order_amounts(
+        otype: OrderType,
+        price: T::Price,
+        sizing: OrderSizing,
+        amount: T::Balance,
+    ) -> result::Result<(T::Balance, T::Balance), DispatchError> {
+        Self::ensure_bounds(price, amount)?;
+
+        match sizing {
+            OrderSizing::Spend => Ok((
+                amount,
+                Self::ensure_counterparty_amount_bounds(otype, price, amount)?,
+            )),
+            OrderSizing::Receive => {
+                let sell_amount = match otype {
+                    OrderType::Buy => Self::quote_to_base(amount, price, Rounding::Up)?,
+                    OrderType::Sell => Self::base_to_quote(amount, price, Rounding::Up)?,
+                };
+                ensure!(sell_amount > Zero::zero(), Error::<T>::BoundsCheckFailed);
+
+                Ok((sell_amount, amount))
+            }
+        }
+    }
+
     pub fn ensure_bounds(price: T::Price, sell_amount: T::Balance) -> DispatchResult {
         ensure!(
             price > Zero::zero() && price <= T::Price::max_value(),
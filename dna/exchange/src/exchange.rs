@@ -1,5 +1,11 @@
 use super::*;
 
+/// Account the trading fees are collected in
+pub(crate) const MODULE_ID: ModuleId = ModuleId(*b"dna/dexf");
+
+/// Domain separator of the payload signed for a `SignedOrder`
+pub const SIGNED_ORDER_DOMAIN: &[u8] = b"dna-signed-order";
+
 pub trait Trait: assets::Trait + system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     type Price: Parameter
@@ -12,8 +18,72 @@ pub trait Trait: assets::Trait + system::Trait {
         + Into<u128>;
     type PriceFactor: Get<u128>;
     type BlocksPerDay: Get<u32>;
+    /// Maximum number of open orders of an account in an exchange pair
     type OpenedOrdersArrayCap: Get<u8>;
-    type ClosedOrdersArrayCap: Get<u8>;
+    /// Origin allowed to change the matching mode and circuit breakers of exchange pairs and
+    /// the listing policy
+    type GovernanceOrigin: EnsureOrigin<Self::Origin>;
+    /// Number of blocks an order commitment can be revealed in
+    type CommitRevealWindow: Get<Self::BlockNumber>;
+    /// Part of the collateral kept when a commitment expires unrevealed
+    type UnrevealedCommitmentPenalty: Get<Perbill>;
+    /// Signature over a SCALE encoded `SignedOrder`
+    type OffchainSignature: Parameter + Verify<Signer = Self::OffchainPublic>;
+    /// Public key of the signature, identifying the order owner
+    type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+    type OrderGroupCap: Get<u8>;
+    /// Native currency reserved for each open order, refunded when it finishes
+    type OrderDeposit: Get<Self::Balance>;
+    /// Maximum number of stop orders waiting in an exchange pair, every trade walks them
+    type StopOrdersCap: Get<u32>;
+    /// Minimum number of blocks between two price observations of an exchange pair
+    type TwapObservationPeriod: Get<Self::BlockNumber>;
+    /// Number of price observations kept per exchange pair
+    type TwapObservationCap: Get<u32>;
+    /// Price the price bands are centered on, the latest matched price for pairs without one
+    type ReferencePrice: ReferencePrice<Self::Hash, Self::Price>;
+    /// Native currency reserved for listing an exchange pair under the bonded and
+    /// governance listing policies
+    type ListingBond: Get<Self::Balance>;
+    /// Origin deciding on listing applications and bonds
+    type ListingOrigin: EnsureOrigin<Self::Origin>;
+    /// Where slashed listing bonds go, they are burned with `()`
+    type ListingSlash: OnUnbalanced<balances::NegativeImbalance<Self>>;
+    /// Part of what each side of a trade receives paid to the fee account
+    type TradingFee: Get<Permill>;
+    /// Where the collected trading fees go, they stay in the fee account with `()`
+    type OnTradingFee: OnTradingFee<Self::AccountId, Self::Hash, Self::Balance>;
+}
+
+/// Handler for the trading fees paid to the fee account, in the manner of `OnUnbalanced`.
+pub trait OnTradingFee<AccountId, Hash, Balance> {
+    /// `amount` of `asset` was paid to `fee_account` by trades.
+    fn on_trading_fee(fee_account: &AccountId, asset: Hash, amount: Balance);
+}
+
+impl<AccountId, Hash, Balance> OnTradingFee<AccountId, Hash, Balance> for () {
+    fn on_trading_fee(_fee_account: &AccountId, _asset: Hash, _amount: Balance) {}
+}
+
+/// Price of an exchange pair from outside the exchange, such as an oracle.
+pub trait ReferencePrice<Hash, Price> {
+    fn reference_price(ep_hash: Hash) -> Option<Price>;
+}
+
+impl<Hash, Price> ReferencePrice<Hash, Price> for () {
+    fn reference_price(_ep_hash: Hash) -> Option<Price> {
+        None
+    }
+}
+
+/// Prices of the exchange pairs, for other modules.
+pub trait PriceOracle<Hash, Price, BlockNumber> {
+    /// Price of the latest trade of an exchange pair.
+    fn latest_price(ep_hash: Hash) -> Option<Price>;
+
+    /// Time-weighted average price of an exchange pair over at least the last `window`
+    /// blocks.
+    fn twap(ep_hash: Hash, window: BlockNumber) -> Option<Price>;
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
@@ -27,6 +97,81 @@ where
     pub quote: T::Hash,
 
     pub latest_matched_price: Option<T::Price>,
+    pub matching_mode: MatchingMode,
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum MatchingMode {
+    /// orders are matched against the book as soon as they are placed
+    Continuous,
+    /// orders are collected during the block and cleared at one uniform price in `on_finalize`
+    BatchAuction,
+}
+
+/// Storage layout versions of the module, for `on_runtime_upgrade`
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug)]
+pub enum Releases {
+    V1_0_0,
+    V1_1_0, // exchange pairs record their matching mode
+    V1_2_0, // orders record their iceberg
+    V1_3_0, // orders record their one-cancels-other group
+    V2_0_0, // complete order history per account and exchange pair
+    V3_0_0, // double maps with `blake2_128_concat` keys instead of tuple keys
+    V4_0_0, // bucketed price level index next to the linked price levels
+    V5_0_0, // orders of a price level in a linked queue instead of a vector
+    V6_0_0, // orders record whether they were sized by the amount spent or received
+}
+
+impl Releases {
+    /// Layout of the current code, built in genesis and reached by `on_runtime_upgrade`
+    pub const LATEST: Releases = Releases::V6_0_0;
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
+impl Default for MatchingMode {
+    fn default() -> Self {
+        MatchingMode::Continuous
+    }
+}
+
+/// Who can list a new exchange pair
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum ListingPolicy {
+    /// the owner of the base or quote asset
+    AssetOwner,
+    /// anyone reserving the listing bond, governance can slash it for a bad listing
+    Bonded,
+    /// anyone applying with the listing bond, the pair is listed once `ListingOrigin` approves
+    Governance,
+}
+
+impl Default for ListingPolicy {
+    fn default() -> Self {
+        ListingPolicy::AssetOwner
+    }
+}
+
+/// Exchange pair waiting for the approval of `ListingOrigin`
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ListingApplication<T>
+where
+    T: Trait,
+{
+    pub hash: T::Hash,
+    pub applicant: T::AccountId,
+    pub base: T::Hash,
+    pub quote: T::Hash,
+    pub matching_mode: MatchingMode,
+    pub bond: T::Balance, // native currency reserved until the application is decided
+    pub applied_at: T::BlockNumber,
 }
 
 #[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,7 +192,7 @@ impl Not for OrderType {
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub enum OrderStatus {
     Pending,
     PartialFilled, // TODO DP consider partially filled status as well
@@ -55,6 +200,48 @@ pub enum OrderStatus {
     Canceled,
 }
 
+/// Limits on how far the price of an exchange pair can move, in basis points. A zero
+/// limit is disabled.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CircuitBreaker<BlockNumber> {
+    /// widest distance from the reference price a trade can execute at
+    pub band_bps: u32,
+    /// largest price move within `window` blocks before the pair halts
+    pub halt_move_bps: u32,
+    pub window: BlockNumber,
+    /// number of blocks a halted pair stays halted
+    pub cooldown: BlockNumber,
+}
+
+/// Sum of the prices of an exchange pair over the blocks they were in effect for, the
+/// average price between two blocks is the change of the sum divided by the blocks between.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceAccumulator<Price, BlockNumber> {
+    pub price_cumulative: U256,
+    /// price in effect since the block after `updated_at`
+    pub latest_price: Price,
+    pub updated_at: BlockNumber,
+}
+
+/// The price sum of an exchange pair at the end of a block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceObservation<BlockNumber> {
+    pub block: BlockNumber,
+    pub price_cumulative: U256,
+}
+
+/// The amount a trader fixed when placing an order, the other amount follows from the
+/// limit price.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum OrderSizing {
+    /// `sell_amount` is what the trader spends
+    Spend,
+    /// `buy_amount` is what the trader receives, `sell_amount` is the most it can cost at
+    /// the limit price and what is not spent is released once the order is filled
+    Receive,
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct LimitOrder<T>
@@ -72,10 +259,97 @@ where
     pub remained_buy_amount: T::Balance,
     pub otype: OrderType,
     pub status: OrderStatus,
+    pub iceberg: Option<Iceberg<T::Balance>>,
+    pub group: Option<T::Hash>, // one-cancels-other group the order belongs to
+    pub sizing: OrderSizing,
+}
+
+/// Only `visible_sell_amount` of an iceberg order is shown in its price level,
+/// the rest of the order stays hidden until the visible slice is traded.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Iceberg<Balance> {
+    pub display_amount: Balance,
+    pub visible_sell_amount: Balance,
 }
 
 pub type Price = u128;
 
+/// Orders of which a fill on one cancels all the others
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OrderGroup<T>
+where
+    T: Trait,
+{
+    pub hash: T::Hash,
+    pub owner: T::AccountId,
+    pub ep_hash: T::Hash,
+    pub asset: T::Hash,            // asset all members sell
+    pub frozen_amount: T::Balance, // largest sell amount of the members
+    pub members: Vec<T::Hash>,     // limit orders and stop orders of the group
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OrderCommitment<T>
+where
+    T: Trait,
+{
+    pub owner: T::AccountId,
+    pub asset: T::Hash,          // asset the collateral is frozen in
+    pub collateral: T::Balance,  // maximum amount the revealed order may sell
+    pub committed_at: T::BlockNumber,
+    pub expires_at: T::BlockNumber,
+}
+
+/// Order signed off-chain by its owner and settled on-chain by any relayer. The owner signs
+/// the SCALE encoded `(SIGNED_ORDER_DOMAIN, genesis hash, order)` tuple, see
+/// `signed_order_payload`, so the signature is not valid for another chain or message.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SignedOrder<T>
+where
+    T: Trait,
+{
+    pub owner: T::AccountId,
+    pub base: T::Hash,
+    pub quote: T::Hash,
+    pub otype: OrderType,
+    pub price: T::Price,
+    pub sell_amount: T::Balance,
+    pub expires_at: T::BlockNumber,
+    pub salt: u64, // lets the owner sign several orders with the same parameters
+}
+
+/// Distance a trailing stop keeps from the most favorable price seen
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TrailDistance<Price> {
+    Absolute(Price),
+    Percent(Permill),
+}
+
+/// Order held off the book until the market crosses its trigger price
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StopOrder<T>
+where
+    T: Trait,
+{
+    pub hash: T::Hash,
+    pub base: T::Hash,
+    pub quote: T::Hash,
+    pub owner: T::AccountId,
+    pub otype: OrderType,
+    pub sell_amount: T::Balance,
+    pub trail: Option<TrailDistance<T::Price>>, // the trigger price stays put when None
+    pub limit_price: Option<T::Price>,          // executed as a market order when None
+    pub reference_price: T::Price,              // highest price seen for sell, lowest for buy
+    pub trigger_price: T::Price,
+    pub group: Option<T::Hash>, // one-cancels-other group the order belongs to
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Dex<T>
@@ -137,12 +411,16 @@ where
             remained_buy_amount: buy_amount,
             remained_sell_amount: sell_amount,
             status: OrderStatus::Pending,
+            iceberg: None,
+            group: None,
+            sizing: OrderSizing::Spend,
         }
     }
 
+    /// A filled order may still have a remaining buy amount, when what was left of it was
+    /// dust that could not be traded.
     pub fn is_finished(&self) -> bool {
-        (self.remained_buy_amount == Zero::zero() && self.status == OrderStatus::Filled)
-            || self.status == OrderStatus::Canceled
+        self.status == OrderStatus::Filled || self.status == OrderStatus::Canceled
     }
 }
 
@@ -239,6 +517,60 @@ decl_error! {
         CanOnlyCancelOwnOrder,
         /// can only cancel not finished order
         CanOnlyCancelNotFinishedOrder,
+        /// Exchange pair already uses the requested matching mode
+        MatchingModeUnchanged,
+        /// Batch auction orders still wait for the next clearing
+        BatchAuctionPending,
+        /// Same order commitment was already exist
+        CommitmentExisted,
+        /// No matching order commitment
+        NoMatchingCommitment,
+        /// Order commitment is outside of its reveal window
+        CommitmentNotRevealable,
+        /// Revealed order sells another asset than the committed collateral
+        CommitmentAssetMismatch,
+        /// Revealed order sells more than the committed collateral
+        CommitmentCollateralNotEnough,
+        /// Signature does not match the signed order
+        InvalidOrderSignature,
+        /// Signed order was canceled
+        SignedOrderCanceled,
+        /// Signed order is expired
+        SignedOrderExpired,
+        /// Fill exceeds the remaining amount of the signed order
+        SignedOrderOverfilled,
+        /// Signed orders are on the same side
+        SignedOrderSameSide,
+        /// Taker order price does not cross the maker order price
+        SignedOrderPriceNotMatched,
+        /// Iceberg display amount must be positive and smaller than the order amount
+        IcebergDisplayAmountInvalid,
+        /// Order group size out of bounds
+        OrderGroupSizeInvalid,
+        /// No matching order group
+        NoMatchingOrderGroup,
+        /// Exchange pair has no trade to trail the price of yet
+        NoMarketPrice,
+        /// No matching stop order
+        NoMatchingStopOrder,
+        /// Exchange pair reached the maximum number of waiting stop orders
+        TooManyStopOrders,
+        /// Account reached the maximum number of open orders in the exchange pair
+        TooManyOpenOrders,
+        /// Trade price too far from the latest matched price
+        PriceOutsideBand,
+        /// Exchange pair halted by its circuit breaker
+        ExchangePairHalted,
+        /// Exchange pair not halted
+        ExchangePairNotHalted,
+        /// Listing application for the same exchange pair was already exist
+        ListingApplicationExisted,
+        /// No matching listing application
+        NoMatchingListingApplication,
+        /// Can only withdraw own listing application
+        CanOnlyWithdrawOwnListing,
+        /// Exchange pair was not listed with a bond
+        NoMatchingListingBond,
     }
 }
 
@@ -247,61 +579,140 @@ decl_storage! {
     trait Store for Module<T: Trait> as ExchangeStorage {
         ///	ExchangePairHash => ExchangePair
         pub ExchangePairs get(fn exchange_pair): map hasher(blake2_256) T::Hash => Option<ExchangePair<T>>;
-        /// (BaseAssetHash/base_asset_id, quoteAssetHash/quote_asset_id) => ExchangePairHash
-        pub ExchangePairsHashByBaseQuote get(fn exchange_pair_hash_by_base_quote): map hasher(blake2_256) (T::Hash, T::Hash) => Option<T::Hash>;
+        /// BaseAssetHash/base_asset_id, quoteAssetHash/quote_asset_id => ExchangePairHash
+        pub ExchangePairsHashByBaseQuote get(fn exchange_pair_hash_by_base_quote): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::Hash => Option<T::Hash>;
         /// Index => ExchangePairHash
         pub ExchangePairsHashByIndex get(fn exchange_pair_hash_by_index): map hasher(blake2_256) u64 => Option<T::Hash>;
         /// Index
         pub ExchangePairsIndex get(fn exchange_pair_index): u64;
-        /// OrderHash => Order
-        pub Orders get(fn order): map hasher(blake2_256) T::Hash => Option<LimitOrder<T>>;
-        /// (AccoundId, Index) => OrderHash
-        pub OwnedOrders get(fn owned_order): map hasher(blake2_256) (T::AccountId, u64) => Option<T::Hash>;
+        /// ExchangePairHash, OrderHash => Order
+        pub Orders get(fn pair_order): double_map hasher(blake2_128_concat) T::Hash, hasher(identity) T::Hash => Option<LimitOrder<T>>;
+        /// OrderHash => ExchangePairHash
+        pub OrderPairs get(fn order_pair): map hasher(identity) T::Hash => Option<T::Hash>;
+        /// AccoundId, Index => OrderHash
+        pub OwnedOrders get(fn owned_order): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) u64 => Option<T::Hash>;
         ///	AccountId => Index
         pub OwnedOrdersIndex get(fn owned_orders_index): map hasher(blake2_256) T::AccountId => u64;
-        /// (OrderHash, u64) => DEXHash
-        pub OrderOwnedExchanges get(fn order_owned_exchanges): map hasher(blake2_256) (T::Hash, u64) => Option<T::Hash>;
+        /// OrderHash, u64 => DEXHash
+        pub OrderOwnedExchanges get(fn order_owned_exchanges): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<T::Hash>;
         /// OrderHash => Index
         pub OrderOwnedExchangesIndex get(fn order_owned_exchanges_index): map hasher(blake2_256) T::Hash => u64;
-        /// (ExchangePairHash, Index) => OrderHash
-        pub ExchangePairOwnedOrders get(fn exchange_pair_owned_order): map hasher(blake2_256) (T::Hash, u64) => Option<T::Hash>;
+        /// ExchangePairHash, Index => OrderHash
+        pub ExchangePairOwnedOrders get(fn exchange_pair_owned_order): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<T::Hash>;
         /// ExchangePairHash => Index
         pub ExchangePairOwnedOrdersIndex get(fn exchange_pair_owned_order_index): map hasher(blake2_256) T::Hash => u64;
 
-        /// (ExchangePairHash, Price) => LinkedItem
-        pub LinkedItemList get(fn linked_item): map hasher(blake2_256) (T::Hash, Option<T::Price>) => Option<OrderLinkedItem<T>>;
+        /// ExchangePairHash, Price => LinkedItem
+        pub LinkedItemList get(fn linked_item): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) Option<T::Price> => Option<OrderLinkedItem<T>>;
+        /// (ExchangePairHash, Price), OrderHash => position of the order in the level's queue
+        pub LevelOrderQueue get(fn level_order_node): double_map hasher(blake2_128_concat) (T::Hash, T::Price), hasher(blake2_128_concat) T::Hash => Option<LevelOrderNode<T::Hash>>;
+        /// ExchangePairHash, (OrderType, None) => lowest prices of the price buckets of one side
+        /// ExchangePairHash, (OrderType, Some(BucketLowestPrice)) => prices of the levels in the bucket, ascending
+        pub PriceLevelIndex get(fn price_level_index): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) (OrderType, Option<T::Price>) => Vec<T::Price>;
 
         /// DEXHash => DEX
         pub Exchanges get(fn exchange): map hasher(blake2_256) T::Hash => Option<Dex<T>>;
 
-        /// (AccountId, u64) => DEXHash
-        pub OwnedExchanges get(fn owned_exchanges): map hasher(blake2_256) (T::AccountId, u64) => Option<T::Hash>;
+        /// AccountId, u64 => DEXHash
+        pub OwnedExchanges get(fn owned_exchanges): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) u64 => Option<T::Hash>;
         /// AccountId => u64
         pub OwnedExchangesIndex get(fn owned_exchanges_index): map hasher(blake2_256) T::AccountId => u64;
 
-        /// (AccountId, ExchangePairHash, u64) => DEXHash
-        pub OwnedEPExchanges get(fn owned_ep_exchanges): map hasher(blake2_256) (T::AccountId, T::Hash, u64) => Option<T::Hash>;
-        /// (AccountId, ExchangePairHash) => u64
-        pub OwnedEPExchangesIndex get(fn owned_ep_exchanges_index): map hasher(blake2_256) (T::AccountId, T::Hash) => u64;
+        /// (AccountId, ExchangePairHash), u64 => DEXHash
+        pub OwnedEPExchanges get(fn owned_ep_exchanges): double_map hasher(blake2_128_concat) (T::AccountId, T::Hash), hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        /// AccountId, ExchangePairHash => u64
+        pub OwnedEPExchangesIndex get(fn owned_ep_exchanges_index): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => u64;
 
-        /// (AccountId, ExchangePairHash) => Vec<OrderHash>
-        pub OwnedEPOpenedOrders get(fn owned_ep_opened_orders): map hasher(blake2_256) (T::AccountId, T::Hash) => Option<Vec<T::Hash>>;
+        /// AccountId, ExchangePairHash => Vec<OrderHash>
+        pub OwnedEPOpenedOrders get(fn owned_ep_opened_orders): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => Option<Vec<T::Hash>>;
 
-        /// (AccountId, ExchangePairHash) => Vec<OrderHash>
-        pub OwnedEPClosedOrders get(fn owned_ep_closed_orders): map hasher(blake2_256) (T::AccountId, T::Hash) => Option<Vec<T::Hash>>;
+        /// (AccountId, ExchangePairHash), u64 => (OrderHash, BlockNumber the order was placed in)
+        pub OwnedEPOrders get(fn owned_ep_order): double_map hasher(blake2_128_concat) (T::AccountId, T::Hash), hasher(blake2_128_concat) u64 => Option<(T::Hash, T::BlockNumber)>;
+        /// AccountId, ExchangePairHash => Index
+        pub OwnedEPOrdersIndex get(fn owned_ep_orders_index): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => u64;
 
-        /// (ExchangePairHash, u64) => DEXHash
-        pub ExchangePairOwnedExchanges get(fn exchange_pair_owned_exchanges): map hasher(blake2_256) (T::Hash, u64) => Option<T::Hash>;
+        /// ExchangePairHash, u64 => DEXHash
+        pub ExchangePairOwnedExchanges get(fn exchange_pair_owned_exchanges): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<T::Hash>;
         /// ExchangePairHash => u64
         pub ExchangePairOwnedExchangesIndex get(fn exchange_pair_owned_exchanges_index): map hasher(blake2_256) T::Hash => u64;
-        /// (ExchangePairHash, BlockNumber) => (Sum_of_Exchange_Volume, Highest_Price, Lowest_Price)
-        pub EPExchangeDataBucket get(fn exchange_pair_exchange_data_bucket): map hasher(blake2_256) (T::Hash, T::BlockNumber) => (T::Balance, Option<T::Price>, Option<T::Price>);
+        /// ExchangePairHash, BlockNumber => (Sum_of_Exchange_Volume, Highest_Price, Lowest_Price)
+        pub EPExchangeDataBucket get(fn exchange_pair_exchange_data_bucket): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::BlockNumber => (T::Balance, Option<T::Price>, Option<T::Price>);
         /// store the exchange pair's H/L price within last day
         /// ExchangePairHash => (Vec<Highest_Price>, Vec<Lowest_Price>)
         pub EPExchangePriceBucket get(fn exchange_pair_exchange_price_bucket): map hasher(blake2_256) T::Hash => (Vec<Option<T::Price>>, Vec<Option<T::Price>>);
         pub Nonce: u64;
+        /// Exchange pairs in batch auction mode that received orders in the current block
+        pub PendingBatchPairs get(fn pending_batch_pairs): Vec<T::Hash>;
+        /// ExchangePairHash => Vec<OrderHash>, orders of a batch auction pair waiting for the next clearing
+        pub BatchAuctionOrders get(fn batch_auction_orders): map hasher(blake2_256) T::Hash => Vec<T::Hash>;
+
+        /// AccountId, CommitmentHash => OrderCommitment
+        pub OrderCommitments get(fn order_commitment): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => Option<OrderCommitment<T>>;
+        /// BlockNumber => Vec<(AccountId, CommitmentHash)>
+        pub CommitmentsByExpiry get(fn commitments_by_expiry): map hasher(blake2_256) T::BlockNumber => Vec<(T::AccountId, T::Hash)>;
+
+        /// OrderGroupHash => OrderGroup
+        pub OrderGroups get(fn order_group): map hasher(blake2_256) T::Hash => Option<OrderGroup<T>>;
+
+        /// SignedOrderHash => filled quote amount
+        pub SignedOrderFilled get(fn signed_order_filled): map hasher(blake2_256) T::Hash => T::Balance;
+        /// SignedOrderHash => canceled
+        pub CanceledSignedOrders get(fn signed_order_canceled): map hasher(blake2_256) T::Hash => bool;
+        /// BlockNumber => Vec<(AccountId, SignedOrderHash, quote capacity)> of the filled signed orders
+        pub SignedOrdersByExpiry get(fn signed_orders_by_expiry): map hasher(blake2_256) T::BlockNumber => Vec<(T::AccountId, T::Hash, T::Balance)>;
+
+        /// StopOrderHash => StopOrder
+        pub StopOrders get(fn stop_order): map hasher(blake2_256) T::Hash => Option<StopOrder<T>>;
+        /// ExchangePairHash => Vec<StopOrderHash>
+        pub EPStopOrders get(fn exchange_pair_stop_orders): map hasher(blake2_256) T::Hash => Vec<T::Hash>;
+        /// Stop orders triggered in the current block, executed in `on_finalize`
+        pub TriggeredStopOrders get(fn triggered_stop_orders): Vec<T::Hash>;
+        /// AccountId, ExchangePairHash => Vec<StopOrderHash>
+        pub OwnedEPStopOrders get(fn owned_ep_stop_orders): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => Vec<T::Hash>;
+
+        /// OrderHash => native currency reserved for the open order
+        pub OrderDeposits get(fn order_deposit): map hasher(blake2_256) T::Hash => T::Balance;
 
         pub Orderbook get (fn order_book): Vec<Option<LimitOrder<T>>>;
+
+        /// Circuit breaker of the exchange pairs without one of their own
+        pub GlobalCircuitBreaker get(fn global_circuit_breaker): CircuitBreaker<T::BlockNumber>;
+        /// ExchangePairHash => CircuitBreaker
+        pub PairCircuitBreakers get(fn pair_circuit_breaker): map hasher(blake2_256) T::Hash => Option<CircuitBreaker<T::BlockNumber>>;
+        /// ExchangePairHash => (BlockNumber the window started in, price at its start)
+        pub PriceWindows get(fn price_window): map hasher(blake2_256) T::Hash => Option<(T::BlockNumber, T::Price)>;
+        /// (ExchangePairHash, BlockNumber the halt ends in) of the halted exchange pairs
+        pub HaltedPairs get(fn halted_pairs): Vec<(T::Hash, T::BlockNumber)>;
+
+        /// ExchangePairHash => PriceAccumulator
+        pub PriceAccumulators get(fn price_accumulator): map hasher(blake2_256) T::Hash => Option<PriceAccumulator<T::Price, T::BlockNumber>>;
+        /// ExchangePairHash, Index % TwapObservationCap => PriceObservation
+        pub PriceObservations get(fn price_observation): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<PriceObservation<T::BlockNumber>>;
+        /// ExchangePairHash => Index
+        pub PriceObservationCount get(fn price_observation_count): map hasher(blake2_256) T::Hash => u64;
+        /// Exchange pairs traded in the current block
+        pub TradedPairs get(fn traded_pairs): Vec<T::Hash>;
+
+        /// Who can list a new exchange pair
+        pub CurrentListingPolicy get(fn listing_policy): ListingPolicy;
+        /// ListingApplicationHash => ListingApplication
+        pub ListingApplications get(fn listing_application): map hasher(blake2_256) T::Hash => Option<ListingApplication<T>>;
+        /// ExchangePairHash => (AccountId, bond) of the pairs listed under the bonded policy
+        pub ListingBonds get(fn listing_bond): map hasher(blake2_256) T::Hash => Option<(T::AccountId, T::Balance)>;
+
+        /// Storage layout version, see `Releases`
+        pub StorageVersion get(fn storage_version) build(|_: &GenesisConfig<T>| Releases::LATEST): Releases;
+    }
+    add_extra_genesis {
+        /// (creator, base asset symbol, quote asset symbol, matching mode, circuit breaker of
+        /// the pair), the assets are genesis assets and the pairs skip the listing policy
+        config(exchange_pairs): Vec<(T::AccountId, Vec<u8>, Vec<u8>, MatchingMode, Option<CircuitBreaker<T::BlockNumber>>)>;
+        build(|config: &GenesisConfig<T>| {
+            for (creator, base, quote, matching_mode, breaker) in &config.exchange_pairs {
+                Module::<T>::create_genesis_exchange_pair(creator.clone(), base, quote, *matching_mode, *breaker)
+                    .expect("genesis exchange pairs are valid; qed");
+            }
+        });
     }
 }
 
@@ -310,6 +721,10 @@ decl_event!(
 	where
 		<T as system::Trait>::AccountId,
 		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+		BlockNumber = <T as system::Trait>::BlockNumber,
+		Price = <T as Trait>::Price,
+		CircuitBreaker = CircuitBreaker<<T as system::Trait>::BlockNumber>,
 		ExchangePair = ExchangePair<T>,
 		LimitOrder = LimitOrder<T>,
 		Dex = Dex<T>,
@@ -324,13 +739,93 @@ decl_event!(
 
 		// (accountId, orderHash)
 		OrderCanceled(AccountId, Hash),
+
+		// (orderHash, DEXHash/exchange_id, filled base amount, filled quote amount, remained sell amount)
+		OrderPartiallyFilled(Hash, Hash, Balance, Balance, Balance),
+
+		// (orderHash, DEXHash/exchange_id, filled base amount, filled quote amount)
+		OrderFilled(Hash, Hash, Balance, Balance),
+
+		// (accountId, orderHash, reason), for orders placed on behalf of the account outside of its extrinsic
+		OrderRejected(AccountId, Hash, DispatchError),
+
+		// (exchangePairHash, MatchingMode)
+		MatchingModeChanged(Hash, MatchingMode),
+
+		// (exchangePairHash, clearing price, matched quote volume)
+		BatchAuctionCleared(Hash, Price, Balance),
+
+		// (accountId, commitmentHash)
+		OrderCommitted(AccountId, Hash),
+
+		// (accountId, commitmentHash)
+		OrderRevealed(AccountId, Hash),
+
+		// (accountId, commitmentHash, refunded, penalty)
+		CommitmentExpired(AccountId, Hash, Balance, Balance),
+
+		// (makerOrderHash, takerOrderHash, base amount, quote amount)
+		SignedOrdersSettled(Hash, Hash, Balance, Balance),
+
+		// (accountId, signedOrderHash)
+		SignedOrderCanceled(AccountId, Hash),
+
+		// (accountId, orderGroupHash)
+		OrderGroupCreated(AccountId, Hash),
+
+		// (orderGroupHash, orderHash of the member that traded)
+		OrderGroupTriggered(Hash, Hash),
+
+		// (accountId, exchangePairHash, stopOrderHash, trigger price)
+		StopOrderCreated(AccountId, Hash, Hash, Price),
+
+		// (stopOrderHash, traded price crossing the trigger)
+		StopOrderTriggered(Hash, Price),
+
+		// (accountId, stopOrderHash)
+		StopOrderCanceled(AccountId, Hash),
+
+		// (accountId, signedOrderHash, filled quote amount, unfilled quote amount)
+		SignedOrderExpired(AccountId, Hash, Balance, Balance),
+
+		// (exchangePairHash or None for the global one, CircuitBreaker)
+		CircuitBreakerSet(Option<Hash>, CircuitBreaker),
+
+		// (exchangePairHash, block the halt ends in, price at the start of the window, traded price)
+		ExchangePairHalted(Hash, BlockNumber, Price, Price),
+
+		// (exchangePairHash)
+		ExchangePairResumed(Hash),
+
+		ListingPolicySet(ListingPolicy),
+
+		// (accountId, listingApplicationHash, baseAssetHash, quoteAssetHash)
+		ListingApplied(AccountId, Hash, Hash, Hash),
+
+		// (listingApplicationHash, exchangePairHash)
+		ListingApproved(Hash, Hash),
+
+		// (listingApplicationHash, slashed bond)
+		ListingRejected(Hash, Balance),
+
+		// (listingApplicationHash)
+		ListingWithdrawn(Hash),
+
+		// (exchangePairHash, accountId, released bond)
+		ListingBondReleased(Hash, AccountId, Balance),
+
+		// (exchangePairHash, accountId, slashed bond)
+		ListingBondSlashed(Hash, AccountId, Balance),
+
+		// (assetHash, fee amount)
+		TradingFeeCollected(Hash, Balance),
 	}
 );
 
 impl<T: Trait> OrderOwnedExchanges<T> {
     pub fn add_exchange(order_hash: T::Hash, exchange_hash: T::Hash) {
         let index = OrderOwnedExchangesIndex::<T>::get(&order_hash);
-        Self::insert((order_hash.clone(), index), exchange_hash);
+        Self::insert(order_hash, index, exchange_hash);
         OrderOwnedExchangesIndex::<T>::insert(order_hash, index + 1);
     }
 }
@@ -338,7 +833,7 @@ impl<T: Trait> OrderOwnedExchanges<T> {
 impl<T: Trait> OwnedExchanges<T> {
     pub fn add_exchange(account_id: T::AccountId, exchange_hash: T::Hash) {
         let index = OwnedExchangesIndex::<T>::get(&account_id);
-        Self::insert((account_id.clone(), index), exchange_hash);
+        Self::insert(&account_id, index, exchange_hash);
         OwnedExchangesIndex::<T>::insert(account_id, index + 1);
     }
 }
@@ -346,23 +841,23 @@ impl<T: Trait> OwnedExchanges<T> {
 impl<T: Trait> ExchangePairOwnedExchanges<T> {
     pub fn add_exchange(ep_hash: T::Hash, exchange_hash: T::Hash) {
         let index = ExchangePairOwnedExchangesIndex::<T>::get(&ep_hash);
-        Self::insert((ep_hash.clone(), index), exchange_hash);
+        Self::insert(ep_hash, index, exchange_hash);
         ExchangePairOwnedExchangesIndex::<T>::insert(ep_hash, index + 1);
     }
 }
 
 impl<T: Trait> OwnedEPExchanges<T> {
     pub fn add_exchange(account_id: T::AccountId, ep_hash: T::Hash, exchange_hash: T::Hash) {
-        let index = OwnedEPExchangesIndex::<T>::get((account_id.clone(), ep_hash));
-        Self::insert((account_id.clone(), ep_hash, index), exchange_hash);
-        OwnedEPExchangesIndex::<T>::insert((account_id.clone(), ep_hash), index + 1);
+        let index = OwnedEPExchangesIndex::<T>::get(&account_id, ep_hash);
+        Self::insert((account_id.clone(), ep_hash), index, exchange_hash);
+        OwnedEPExchangesIndex::<T>::insert(account_id, ep_hash, index + 1);
     }
 }
 
 impl<T: Trait> OwnedEPOpenedOrders<T> {
     pub fn add_order(account_id: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) {
         let mut orders;
-        if let Some(ts) = Self::get((account_id.clone(), ep_hash)) {
+        if let Some(ts) = Self::get(&account_id, ep_hash) {
             orders = ts;
         } else {
             orders = Vec::<T::Hash>::new();
@@ -372,25 +867,21 @@ impl<T: Trait> OwnedEPOpenedOrders<T> {
             Some(_) => return,
             None => {
                 orders.insert(0, order_hash);
-                if orders.len() == T::OpenedOrdersArrayCap::get() as usize {
-                    orders.pop();
-                }
-
-                <OwnedEPOpenedOrders<T>>::insert((account_id, ep_hash), orders);
+                <OwnedEPOpenedOrders<T>>::insert(account_id, ep_hash, orders);
             }
         }
     }
 
     pub fn remove_order(account_id: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) {
         let mut orders;
-        if let Some(ts) = Self::get((account_id.clone(), ep_hash)) {
+        if let Some(ts) = Self::get(&account_id, ep_hash) {
             orders = ts;
         } else {
             orders = Vec::<T::Hash>::new();
         }
 
         orders.retain(|&x| x != order_hash);
-        <OwnedEPOpenedOrders<T>>::insert((account_id, ep_hash), orders);
+        <OwnedEPOpenedOrders<T>>::insert(account_id, ep_hash, orders);
     }
 }
 
@@ -416,29 +907,6 @@ impl<T: Trait> Orderbook<T> {
     }
 }
 
-impl<T: Trait> OwnedEPClosedOrders<T> {
-    pub fn add_order(account_id: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) {
-        let mut orders;
-        if let Some(ts) = Self::get((account_id.clone(), ep_hash)) {
-            orders = ts;
-        } else {
-            orders = Vec::<T::Hash>::new();
-        }
-
-        match orders.iter().position(|&x| x == order_hash) {
-            Some(_) => return,
-            None => {
-                orders.insert(0, order_hash);
-                if orders.len() == T::ClosedOrdersArrayCap::get() as usize {
-                    orders.pop();
-                }
-
-                <OwnedEPClosedOrders<T>>::insert((account_id, ep_hash), orders);
-            }
-        }
-    }
-}
-
 // This module's dispatchable functions.
 decl_module! {
     // The module declaration.
@@ -447,6 +915,53 @@ decl_module! {
         pub fn deposit_event() = default;
         type Error = Error<T>;
 
+        fn on_runtime_upgrade() {
+            let version = Self::storage_version();
+
+            if version < Releases::V1_1_0 {
+                Self::migrate_exchange_pair_mode();
+            }
+            // the other migrations read orders in the current layout
+            if version < Releases::V6_0_0 {
+                Self::migrate_order_layout(version);
+            }
+
+            if version < Releases::V3_0_0 {
+                Self::migrate_to_double_maps();
+            }
+            // the history is rebuilt in the double map layout
+            if version < Releases::V2_0_0 {
+                Self::migrate_order_history();
+            }
+
+            // the price levels are read in the queue layout from here on
+            if version < Releases::V5_0_0 {
+                Self::migrate_level_order_queues();
+            }
+            if version < Releases::V4_0_0 {
+                Self::migrate_price_level_index();
+            }
+
+            StorageVersion::put(Releases::LATEST);
+        }
+
+        fn on_finalize(n: T::BlockNumber) {
+            Self::expire_commitments(n);
+            Self::expire_signed_orders(n);
+
+            for ep_hash in PendingBatchPairs::<T>::take() {
+                if let Err(e) = Self::clear_batch_auction(ep_hash) {
+                    support::debug::warn!("batch auction clearing failed for {:?}: {:?}", ep_hash, e);
+                }
+            }
+
+            Self::execute_triggered_stop_orders();
+
+            Self::resume_halted_pairs(n);
+
+            Self::record_price_observations(n);
+        }
+
         /// # Provide info to create an order limit
         /// * `_origin` - signer
         /// * `base` - hash/asset_id of base asset
@@ -482,6 +997,90 @@ decl_module! {
              Ok(())
         }
 
+        /// # Provide info to create an iceberg order
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `price` - price per unit of the base unit
+        /// * `sell_amount` -  amount kept for exchange
+        /// * `display_amount` - part of `sell_amount` shown in the order book at a time
+        /// * `order_type` - buy or sell
+        pub fn create_iceberg_order(_origin, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price, sell_amount: T::Balance, display_amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_create_order(sender, base, quote, otype, price, OrderSizing::Spend, sell_amount, Some(display_amount), None)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Provide info to create an order limit sized by the amount spent or received
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `price` - price per unit of the base unit
+        /// * `sizing` - whether `amount` is the amount to spend or to receive
+        /// * `amount` - amount to spend or to receive
+        /// * `order_type` - buy or sell
+        pub fn create_sized_order(_origin, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price, sizing: OrderSizing, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_create_order(sender, base, quote, otype, price, sizing, amount, None, None)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Provide info to create one-cancels-other orders
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `order_type` - buy or sell, the same for all orders of the group
+        /// * `orders` - price, sell amount and trigger price of each order, an order with a
+        ///   trigger price waits as a stop order until the market crosses it
+        pub fn create_oco_orders(_origin, base: T::Hash, quote: T::Hash, otype: OrderType, orders: Vec<(T::Price, T::Balance, Option<T::Price>)>) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_create_order_group(sender, base, quote, otype, orders)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Provide info to create a trailing stop order
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `order_type` - buy or sell
+        /// * `sell_amount` -  amount kept for exchange
+        /// * `trail` - absolute or percentage distance of the trigger price from the market
+        /// * `limit_price` - price of the order placed once triggered, a market order if not given
+        pub fn create_trailing_stop_order(_origin, base: T::Hash, quote: T::Hash, otype: OrderType, sell_amount: T::Balance, trail: TrailDistance<T::Price>, limit_price: Option<T::Price>) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_create_stop_order(sender, base, quote, otype, sell_amount, trail, limit_price)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Provide info to cancel a stop order
+        /// * `_origin` - signer
+        /// * `stop_order_hash` - hash of the stop order
+        pub fn cancel_stop_order(_origin, stop_order_hash: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_cancel_stop_order(sender, stop_order_hash)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
         /// # Provide info to create a exchange pair
         /// * `_origin` - signer
         /// * `base` - hash/asset_id of base asset
@@ -490,7 +1089,181 @@ decl_module! {
             let sender = ensure_signed(_origin)?;
 
              // call corresponding internal function
-            Self::do_create_exchange_pair(sender, base, quote)?;
+            Self::do_create_exchange_pair(sender, base, quote, MatchingMode::Continuous)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Provide info to create a exchange pair with a given matching mode
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `mode` - continuous matching or frequent batch auction
+        pub fn create_exchange_pair_with_mode(_origin, base:T::Hash, quote:T::Hash, mode: MatchingMode) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+            Self::do_create_exchange_pair(sender, base, quote, mode)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Switch the matching mode of an exchange pair
+        /// * `origin` - governance origin
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `mode` - continuous matching or frequent batch auction
+        pub fn set_matching_mode(origin, ep_hash: T::Hash, mode: MatchingMode) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            Self::do_set_matching_mode(ep_hash, mode)
+        }
+
+        /// # Set the circuit breaker of an exchange pair or the global one
+        /// * `origin` - governance origin
+        /// * `ep_hash` - hash of the exchange pair, `None` for the global circuit breaker
+        /// * `breaker` - price band and halt limits, `None` makes the pair use the global one
+        pub fn set_circuit_breaker(origin, ep_hash: Option<T::Hash>, breaker: Option<CircuitBreaker<T::BlockNumber>>) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            match ep_hash {
+                Some(ep_hash) => {
+                    ensure!(Self::exchange_pair(ep_hash).is_some(), Error::<T>::NoMatchingExchangePair);
+                    <PairCircuitBreakers<T>>::mutate(ep_hash, |x| *x = breaker);
+                }
+                None => <GlobalCircuitBreaker<T>>::put(breaker.unwrap_or_default()),
+            }
+
+            Self::deposit_event(RawEvent::CircuitBreakerSet(ep_hash, Self::circuit_breaker_of(ep_hash)));
+
+            Ok(())
+        }
+
+        /// # Set who can list new exchange pairs
+        /// * `origin` - governance origin
+        /// * `policy` - asset owners, anyone with a bond or governance approval
+        pub fn set_listing_policy(origin, policy: ListingPolicy) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            CurrentListingPolicy::put(policy);
+
+            Self::deposit_event(RawEvent::ListingPolicySet(policy));
+
+            Ok(())
+        }
+
+        /// # List the exchange pair of a listing application and return its bond
+        /// * `origin` - listing origin
+        /// * `application_hash` - hash of the listing application
+        pub fn approve_listing(origin, application_hash: T::Hash) -> DispatchResult {
+            T::ListingOrigin::ensure_origin(origin)?;
+
+            Self::do_approve_listing(application_hash)
+        }
+
+        /// # Reject a listing application, slashing its bond
+        /// * `origin` - listing origin
+        /// * `application_hash` - hash of the listing application
+        pub fn reject_listing(origin, application_hash: T::Hash) -> DispatchResult {
+            T::ListingOrigin::ensure_origin(origin)?;
+
+            Self::do_reject_listing(application_hash)
+        }
+
+        /// # Withdraw an own listing application and get its bond back
+        /// * `_origin` - signer
+        /// * `application_hash` - hash of the listing application
+        pub fn withdraw_listing(_origin, application_hash: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+            Self::do_withdraw_listing(sender, application_hash)
+        }
+
+        /// # Return or slash the bond of an exchange pair listed under the bonded policy
+        /// * `origin` - listing origin
+        /// * `ep_hash` - hash of the exchange pair
+        /// * `slash` - slash the bond instead of returning it
+        pub fn release_listing_bond(origin, ep_hash: T::Hash, slash: bool) -> DispatchResult {
+            T::ListingOrigin::ensure_origin(origin)?;
+
+            Self::do_release_listing_bond(ep_hash, slash)
+        }
+
+        /// # Lift the halt of an exchange pair before its cooldown is over
+        /// * `origin` - governance origin
+        /// * `ep_hash` - hash of the exchange pair
+        pub fn resume_exchange_pair(origin, ep_hash: T::Hash) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            Self::do_resume_pair(ep_hash)
+        }
+
+        /// # Lock funds for an order whose parameters are revealed later
+        /// * `_origin` - signer
+        /// * `commitment` - hash of the order parameters and a salt, see `order_commitment_hash`
+        /// * `asset` - hash/asset_id of the asset the order sells
+        /// * `collateral` - maximum amount the revealed order may sell
+        pub fn commit_order(_origin, commitment: T::Hash, asset: T::Hash, collateral: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_commit_order(sender, commitment, asset, collateral)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Place an order previously committed with `commit_order`
+        /// * `_origin` - signer
+        /// * `base` - hash/asset_id of base asset
+        /// * `quote` - hash/asset_id of quote asset
+        /// * `order_type` - buy or sell
+        /// * `price` - price per unit of the base unit
+        /// * `sell_amount` -  amount kept for exchange
+        /// * `salt` - salt used in the commitment
+        pub fn reveal_order(_origin, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price, sell_amount: T::Balance, salt: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_reveal_order(sender, base, quote, otype, price, sell_amount, salt)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Settle two orders signed off-chain against each other
+        /// * `_origin` - relayer
+        /// * `maker_order` - signed order setting the price
+        /// * `maker_signature` - maker's signature over the `signed_order_payload` of the order
+        /// * `taker_order` - signed order crossing the maker order
+        /// * `taker_signature` - taker's signature over the `signed_order_payload` of the order
+        /// * `fill_amount` - quote amount to exchange
+        pub fn settle_signed_orders(
+            _origin,
+            maker_order: SignedOrder<T>,
+            maker_signature: T::OffchainSignature,
+            taker_order: SignedOrder<T>,
+            taker_signature: T::OffchainSignature,
+            fill_amount: T::Balance
+        ) -> DispatchResult {
+            let _relayer = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_settle_signed_orders(maker_order, maker_signature, taker_order, taker_signature, fill_amount)?;
+
+             // Return Ok if successful.
+             Ok(())
+        }
+
+        /// # Cancel an order signed off-chain
+        /// * `_origin` - owner of the order
+        /// * `order` - signed order to cancel
+        pub fn cancel_signed_order(_origin, order: SignedOrder<T>) -> DispatchResult {
+            let sender = ensure_signed(_origin)?;
+
+             // call corresponding internal function
+             Self::do_cancel_signed_order(sender, order)?;
 
              // Return Ok if successful.
              Ok(())
@@ -499,10 +1272,11 @@ decl_module! {
         /// # Provide info to cancel an order
         /// * `_origin` - signer
         /// * `order_hash` - hash/order_id of order
-        pub fn cancel_order(origin, _order_hash: T::Hash) -> Result<(), DispatchError> {
-            let _sender = ensure_signed(origin)?;
+        pub fn cancel_order(origin, order_hash: T::Hash) -> Result<(), DispatchError> {
+            let sender = ensure_signed(origin)?;
 
             // call corresponding internal function
+            Self::do_cancel_limit_order(sender, order_hash)?;
 
              // Return Ok if successful.
              Ok(())
@@ -1,13 +1,31 @@
 use super::*;
 
 impl<T: Trait> Module<T> {
+    /// List an exchange pair the way the listing policy allows, see `ListingPolicy`.
     pub fn do_create_exchange_pair(
         sender: T::AccountId,
         base: T::Hash,
         quote: T::Hash,
+        matching_mode: MatchingMode,
     ) -> DispatchResult {
-        ensure!(base != quote, Error::<T>::BaseEqualQuote);
+        match Self::listing_policy() {
+            ListingPolicy::AssetOwner => {
+                Self::ensure_asset_owner(&sender, base, quote)?;
+                Self::insert_exchange_pair(sender, base, quote, matching_mode)?;
+                Ok(())
+            }
+            ListingPolicy::Bonded => Self::do_list_bonded(sender, base, quote, matching_mode),
+            ListingPolicy::Governance => {
+                Self::do_apply_for_listing(sender, base, quote, matching_mode)
+            }
+        }
+    }
 
+    pub fn ensure_asset_owner(
+        sender: &T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+    ) -> DispatchResult {
         let base_owner = <assets::Module<T>>::owner(base);
         let quote_owner = <assets::Module<T>>::owner(quote);
 
@@ -20,18 +38,43 @@ impl<T: Trait> Module<T> {
         let quote_owner = quote_owner.unwrap();
 
         ensure!(
-            sender == base_owner || sender == quote_owner,
+            *sender == base_owner || *sender == quote_owner,
             Error::<T>::SenderNotEqualToBaseOrQuoteOwner
         );
 
-        let bq = Self::exchange_pair_hash_by_base_quote((base, quote));
-        let qb = Self::exchange_pair_hash_by_base_quote((quote, base));
+        Ok(())
+    }
+
+    /// Ensure an exchange pair of the two assets can be created.
+    pub fn ensure_listable(base: T::Hash, quote: T::Hash) -> DispatchResult {
+        ensure!(base != quote, Error::<T>::BaseEqualQuote);
+
+        ensure!(
+            <assets::Module<T>>::owner(base).is_some()
+                && <assets::Module<T>>::owner(quote).is_some(),
+            Error::<T>::AssetOwnerNotFound
+        );
+
+        let bq = Self::exchange_pair_hash_by_base_quote(base, quote);
+        let qb = Self::exchange_pair_hash_by_base_quote(quote, base);
 
         ensure!(
             !bq.is_some() && !qb.is_some(),
             Error::<T>::ExchangePairExisted
         );
 
+        Ok(())
+    }
+
+    /// Create an exchange pair, once the listing policy allowed `sender` to list it.
+    pub fn insert_exchange_pair(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        matching_mode: MatchingMode,
+    ) -> result::Result<T::Hash, DispatchError> {
+        Self::ensure_listable(base, quote)?;
+
         let nonce = Nonce::get();
 
         let random_seed = <randomness_collective_flip::Module<T>>::random_seed();
@@ -50,11 +93,12 @@ impl<T: Trait> Module<T> {
             base,
             quote,
             latest_matched_price: None,
+            matching_mode,
         };
 
         Nonce::mutate(|n| *n += 1);
         ExchangePairs::insert(hash, ep.clone());
-        ExchangePairsHashByBaseQuote::<T>::insert((base, quote), hash);
+        ExchangePairsHashByBaseQuote::<T>::insert(base, quote, hash);
 
         let index = Self::exchange_pair_index();
         ExchangePairsHashByIndex::<T>::insert(index, hash);
@@ -62,6 +106,26 @@ impl<T: Trait> Module<T> {
 
         Self::deposit_event(RawEvent::ExchangePairCreated(sender, hash, ep));
 
+        Ok(hash)
+    }
+
+    /// Create an exchange pair of two genesis assets, given by their symbols.
+    pub fn create_genesis_exchange_pair(
+        creator: T::AccountId,
+        base_symbol: &[u8],
+        quote_symbol: &[u8],
+        matching_mode: MatchingMode,
+        breaker: Option<CircuitBreaker<T::BlockNumber>>,
+    ) -> DispatchResult {
+        let base = <assets::Module<T>>::genesis_asset_hash(base_symbol);
+        let quote = <assets::Module<T>>::genesis_asset_hash(quote_symbol);
+
+        let ep_hash = Self::insert_exchange_pair(creator, base, quote, matching_mode)?;
+
+        if let Some(breaker) = breaker {
+            <PairCircuitBreakers<T>>::insert(ep_hash, breaker);
+        }
+
         Ok(())
     }
 
@@ -69,7 +133,7 @@ impl<T: Trait> Module<T> {
         base: T::Hash,
         quote: T::Hash,
     ) -> result::Result<T::Hash, DispatchError> {
-        let bq = Self::exchange_pair_hash_by_base_quote((base, quote));
+        let bq = Self::exchange_pair_hash_by_base_quote(base, quote);
         ensure!(bq.is_some(), Error::<T>::NoMatchingExchangePair);
 
         match bq {
@@ -0,0 +1,38 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Account the trading fees are paid to before `OnTradingFee` handles them.
+    pub fn fee_account() -> T::AccountId {
+        MODULE_ID.into_account()
+    }
+
+    /// Trading fee of a trade paying `amount`, taken from what the receiver gets.
+    pub fn trading_fee(amount: T::Balance) -> T::Balance {
+        T::TradingFee::get() * amount
+    }
+
+    /// Pay `amount` of the free `asset` of `from` to `to`, less the trading fee.
+    pub fn pay_with_fee(
+        from: T::AccountId,
+        asset: T::Hash,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let fee = Self::trading_fee(amount);
+
+        <assets::Module<T>>::transfer(from.clone(), asset, to, amount - fee)?;
+        if fee != Zero::zero() {
+            <assets::Module<T>>::transfer(from, asset, Self::fee_account(), fee)?;
+            Self::collect_trading_fee(asset, fee);
+        }
+
+        Ok(())
+    }
+
+    /// Hand a fee already paid to the fee account over to `OnTradingFee`.
+    pub fn collect_trading_fee(asset: T::Hash, amount: T::Balance) {
+        Self::deposit_event(RawEvent::TradingFeeCollected(asset, amount));
+
+        T::OnTradingFee::on_trading_fee(&Self::fee_account(), asset, amount);
+    }
+}
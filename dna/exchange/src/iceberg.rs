@@ -0,0 +1,129 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Amount of the counter asset `amount` buys at `price`, rounded down.
+    pub fn iceberg_counterparty_amount(
+        otype: OrderType,
+        price: T::Price,
+        amount: T::Balance,
+    ) -> result::Result<T::Balance, DispatchError> {
+        let price_u256 = U256::from(Self::into_128(price)?);
+        let amount_u256 = U256::from(Self::into_128(amount)?);
+        let price_factor_u256 = U256::from(T::PriceFactor::get());
+
+        let counterparty_amount = match otype {
+            OrderType::Buy => amount_u256 * price_factor_u256 / price_u256,
+            OrderType::Sell => amount_u256 * price_u256 / price_factor_u256,
+        };
+
+        let result: u128 = counterparty_amount
+            .try_into()
+            .map_err(|_| Error::<T>::OverflowError)?;
+
+        Self::from_128(result)
+    }
+
+    /// Amounts a resting order adds to its price level.
+    pub fn level_amounts(
+        order: &LimitOrder<T>,
+    ) -> result::Result<(T::Balance, T::Balance), DispatchError> {
+        let visible = Self::visible_order(order)?;
+        Ok((visible.remained_sell_amount, visible.remained_buy_amount))
+    }
+
+    /// The part of a resting order other orders can trade against, which is the
+    /// visible slice for an iceberg order and the whole order otherwise.
+    pub fn visible_order(
+        order: &LimitOrder<T>,
+    ) -> result::Result<LimitOrder<T>, DispatchError> {
+        let mut visible = order.clone();
+
+        if let Some(iceberg) = &order.iceberg {
+            if iceberg.visible_sell_amount < order.remained_sell_amount {
+                visible.remained_sell_amount = iceberg.visible_sell_amount;
+                visible.remained_buy_amount = rstd::cmp::min(
+                    Self::iceberg_counterparty_amount(
+                        order.otype,
+                        order.price,
+                        iceberg.visible_sell_amount,
+                    )?,
+                    order.remained_buy_amount,
+                );
+            }
+        }
+
+        Ok(visible)
+    }
+
+    /// Take a fill of a resting order off its price level. An iceberg order only has its
+    /// visible slice in the level, once the slice is used up it is refilled from the hidden
+    /// reserve and goes to the back of the level queue.
+    pub fn apply_level_fill(
+        ep_hash: T::Hash,
+        order: &mut LimitOrder<T>,
+        sell_amount: T::Balance,
+        buy_amount: T::Balance,
+    ) -> DispatchResult {
+        let (level_sell_amount, level_buy_amount) = match order.iceberg.as_mut() {
+            None => (sell_amount, buy_amount),
+            Some(iceberg) => {
+                if sell_amount < iceberg.visible_sell_amount {
+                    iceberg.visible_sell_amount = iceberg.visible_sell_amount - sell_amount;
+                    (sell_amount, buy_amount)
+                } else {
+                    let visible_sell_amount = iceberg.visible_sell_amount;
+                    iceberg.visible_sell_amount = Zero::zero();
+                    (
+                        visible_sell_amount,
+                        Self::iceberg_counterparty_amount(
+                            order.otype,
+                            order.price,
+                            visible_sell_amount,
+                        )?,
+                    )
+                }
+            }
+        };
+
+        <OrderLinkedItemList<T>>::update_amount(
+            ep_hash,
+            order.price,
+            level_sell_amount,
+            level_buy_amount,
+        );
+
+        Self::replenish_iceberg(ep_hash, order)
+    }
+
+    fn replenish_iceberg(ep_hash: T::Hash, order: &mut LimitOrder<T>) -> DispatchResult {
+        if order.is_finished() {
+            return Ok(());
+        }
+
+        let remained_sell_amount = order.remained_sell_amount;
+        let remained_buy_amount = order.remained_buy_amount;
+
+        if let Some(iceberg) = order.iceberg.as_mut() {
+            if iceberg.visible_sell_amount != Zero::zero() {
+                return Ok(());
+            }
+
+            let sell_amount = rstd::cmp::min(iceberg.display_amount, remained_sell_amount);
+            let buy_amount = rstd::cmp::min(
+                Self::iceberg_counterparty_amount(order.otype, order.price, sell_amount)?,
+                remained_buy_amount,
+            );
+            iceberg.visible_sell_amount = sell_amount;
+
+            <OrderLinkedItemList<T>>::requeue(
+                ep_hash,
+                order.price,
+                order.hash,
+                sell_amount,
+                buy_amount,
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -9,27 +9,57 @@ use core::convert::{TryFrom, TryInto};
 use primitives::U256;
 use rstd::if_std;
 use rstd::{ops::Not, prelude::*, result};
-use sp_runtime::traits::{Bounded, CheckedSub, Hash, Member, AtLeast32Bit, Zero};
-use sp_runtime::RuntimeDebug;
+use sp_runtime::traits::{
+    AccountIdConversion, AtLeast32Bit, Bounded, CheckedAdd, CheckedSub, Hash, IdentifyAccount,
+    Member, One, Saturating, Verify, Zero,
+};
+use sp_runtime::{ModuleId, Perbill, Permill, RuntimeDebug};
 use support::{
     decl_error, decl_event, decl_module, decl_storage,
     dispatch::{DispatchError, DispatchResult},
     ensure,
-    traits::{Get, Randomness},
-    Parameter, StorageMap, StorageValue,
+    traits::{EnsureOrigin, Get, OnUnbalanced, Randomness, ReservableCurrency},
+    Blake2_256, Parameter, StorageDoubleMap, StorageHasher, StorageMap, StorageValue, Twox128,
 };
 
 use byteorder::{ByteOrder, LittleEndian};
 use codec::{Decode, Encode, EncodeLike};
 use system::ensure_signed;
 
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+mod batch_auction;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+mod cancel_order;
+mod circuit_breaker;
+mod commit_reveal;
 mod create_order;
 mod exchange;
 mod exchange_pair;
+mod fee;
+mod iceberg;
+mod listing;
 mod market;
 mod match_order;
+mod migration;
+#[cfg(test)]
+mod mock;
+mod open_order;
+mod order_history;
+mod order_group;
 mod price;
+mod settlement;
+mod signed_order;
+mod stop_order;
+#[cfg(test)]
+mod tests;
+mod transactional;
+mod twap;
 mod types;
 
 pub use exchange::*;
+use engine::Rounding;
+use settlement::Settlement;
 pub use types::*;
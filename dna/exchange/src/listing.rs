@@ -0,0 +1,146 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// List an exchange pair right away, reserving the listing bond of the sender until
+    /// governance releases or slashes it.
+    pub fn do_list_bonded(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        matching_mode: MatchingMode,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            let bond = T::ListingBond::get();
+            <balances::Module<T>>::reserve(&sender, bond)?;
+
+            let ep_hash = Self::insert_exchange_pair(sender.clone(), base, quote, matching_mode)?;
+            <ListingBonds<T>>::insert(ep_hash, (sender, bond));
+
+            Ok(())
+        })
+    }
+
+    /// Apply for the listing of an exchange pair, reserving the listing bond of the sender
+    /// until governance decides on the application.
+    pub fn do_apply_for_listing(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        matching_mode: MatchingMode,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            Self::ensure_listable(base, quote)?;
+
+            let hash = Self::listing_application_hash(base, quote);
+            let reversed = Self::listing_application_hash(quote, base);
+            ensure!(
+                Self::listing_application(hash).is_none()
+                    && Self::listing_application(reversed).is_none(),
+                Error::<T>::ListingApplicationExisted
+            );
+
+            let bond = T::ListingBond::get();
+            <balances::Module<T>>::reserve(&sender, bond)?;
+
+            <ListingApplications<T>>::insert(
+                hash,
+                ListingApplication {
+                    hash,
+                    applicant: sender.clone(),
+                    base,
+                    quote,
+                    matching_mode,
+                    bond,
+                    applied_at: <system::Module<T>>::block_number(),
+                },
+            );
+
+            Self::deposit_event(RawEvent::ListingApplied(sender, hash, base, quote));
+
+            Ok(())
+        })
+    }
+
+    /// List the exchange pair of an application and return the bond of the applicant.
+    pub fn do_approve_listing(application_hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let application = <ListingApplications<T>>::take(application_hash)
+                .ok_or(Error::<T>::NoMatchingListingApplication)?;
+
+            <balances::Module<T>>::unreserve(&application.applicant, application.bond);
+            let ep_hash = Self::insert_exchange_pair(
+                application.applicant,
+                application.base,
+                application.quote,
+                application.matching_mode,
+            )?;
+
+            Self::deposit_event(RawEvent::ListingApproved(application_hash, ep_hash));
+
+            Ok(())
+        })
+    }
+
+    /// Drop an application, slashing the bond of the applicant.
+    pub fn do_reject_listing(application_hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let application = <ListingApplications<T>>::take(application_hash)
+                .ok_or(Error::<T>::NoMatchingListingApplication)?;
+
+            let slashed = Self::slash_listing_bond(&application.applicant, application.bond);
+            Self::deposit_event(RawEvent::ListingRejected(application_hash, slashed));
+
+            Ok(())
+        })
+    }
+
+    /// Withdraw an application of the sender, returning its bond.
+    pub fn do_withdraw_listing(sender: T::AccountId, application_hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let application = Self::listing_application(application_hash)
+                .ok_or(Error::<T>::NoMatchingListingApplication)?;
+            ensure!(
+                application.applicant == sender,
+                Error::<T>::CanOnlyWithdrawOwnListing
+            );
+
+            <ListingApplications<T>>::remove(application_hash);
+            <balances::Module<T>>::unreserve(&sender, application.bond);
+
+            Self::deposit_event(RawEvent::ListingWithdrawn(application_hash));
+
+            Ok(())
+        })
+    }
+
+    /// Return the bond of a pair listed under the bonded policy, or slash it.
+    pub fn do_release_listing_bond(ep_hash: T::Hash, slash: bool) -> DispatchResult {
+        Self::transactional(|| {
+            let (owner, bond) =
+                <ListingBonds<T>>::take(ep_hash).ok_or(Error::<T>::NoMatchingListingBond)?;
+
+            if slash {
+                let slashed = Self::slash_listing_bond(&owner, bond);
+                Self::deposit_event(RawEvent::ListingBondSlashed(ep_hash, owner, slashed));
+            } else {
+                <balances::Module<T>>::unreserve(&owner, bond);
+                Self::deposit_event(RawEvent::ListingBondReleased(ep_hash, owner, bond));
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Hash of the listing application for an exchange pair in the given orientation.
+    pub fn listing_application_hash(base: T::Hash, quote: T::Hash) -> T::Hash {
+        (b"listing", base, quote).using_encoded(<T as system::Trait>::Hashing::hash)
+    }
+
+    /// Slash a reserved listing bond into `ListingSlash` and return the slashed amount.
+    fn slash_listing_bond(who: &T::AccountId, bond: T::Balance) -> T::Balance {
+        let (imbalance, not_slashed) = <balances::Module<T>>::slash_reserved(who, bond);
+        T::ListingSlash::on_unbalanced(imbalance);
+
+        bond - not_slashed
+    }
+}
@@ -11,7 +11,7 @@ impl<T: Trait> Module<T> {
         ep.latest_matched_price = Some(price);
 
         let mut bucket =
-            <EPExchangeDataBucket<T>>::get((ep_hash, <system::Module<T>>::block_number()));
+            <EPExchangeDataBucket<T>>::get(ep_hash, <system::Module<T>>::block_number());
         bucket.0 = bucket.0 + amount;
 
         match bucket.1 {
@@ -36,9 +36,13 @@ impl<T: Trait> Module<T> {
             }
         }
 
-        <EPExchangeDataBucket<T>>::insert((ep_hash, <system::Module<T>>::block_number()), bucket);
+        <EPExchangeDataBucket<T>>::insert(ep_hash, <system::Module<T>>::block_number(), bucket);
         <ExchangePairs<T>>::insert(ep_hash, ep);
 
+        Self::accumulate_price(ep_hash, price)?;
+        Self::trail_stop_orders(ep_hash, price);
+        Self::check_price_move(ep_hash, price)?;
+
         Ok(())
     }
 }
@@ -1,204 +1,322 @@
 use super::*;
+use engine::{Fill, OrderBook, Side};
+
+/// An exchange pair's book as the matching engine sees it. Fills are written to storage as
+/// they are made, balance changes are collected and applied once the match is done.
+struct PairBook<'a, T: Trait> {
+    ep: ExchangePair<T>,
+    /// the center of the price band before the match
+    reference_price: Option<T::Price>,
+    taker: &'a mut LimitOrder<T>,
+    settlement: Settlement<T>,
+}
+
+impl<'a, T: Trait> OrderBook for PairBook<'a, T> {
+    type OrderId = T::Hash;
+    type Error = DispatchError;
+
+    fn price_factor(&self) -> u128 {
+        T::PriceFactor::get()
+    }
+
+    fn match_error(error: engine::MatchError) -> DispatchError {
+        <Module<T>>::engine_error(error)
+    }
+
+    fn best_price(&mut self, side: Side) -> result::Result<Option<u128>, DispatchError> {
+        // a trade of this match may have halted the pair
+        if <Module<T>>::is_pair_halted(self.ep.hash) {
+            return Ok(None);
+        }
+
+        let head = <OrderLinkedItemList<T>>::read_head(self.ep.hash);
+
+        let (price, end_item_price) = match side {
+            Side::Buy => (head.prev, T::Price::min_value()),
+            Side::Sell => (head.next, T::Price::max_value()),
+        };
+
+        match price.ok_or(Error::<T>::OrderMatchGetPriceError)? {
+            price if price == end_item_price => Ok(None),
+            price => Ok(Some(<Module<T>>::into_128(price)?)),
+        }
+    }
+
+    fn first_order(
+        &mut self,
+        _side: Side,
+        price: u128,
+    ) -> result::Result<Option<T::Hash>, DispatchError> {
+        let item = <LinkedItemList<T>>::get(self.ep.hash, Some(<Module<T>>::from_128(price)?))
+            .ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
+        Ok(item.first_order)
+    }
+
+    fn next_order(
+        &mut self,
+        _side: Side,
+        price: u128,
+        id: T::Hash,
+    ) -> result::Result<Option<T::Hash>, DispatchError> {
+        Ok(<OrderLinkedItemList<T>>::next_order(
+            self.ep.hash,
+            <Module<T>>::from_128(price)?,
+            id,
+        ))
+    }
+
+    fn maker(
+        &mut self,
+        id: T::Hash,
+    ) -> result::Result<Option<engine::Order<T::Hash>>, DispatchError> {
+        let o = <Module<T>>::pair_order(self.ep.hash, id)
+            .ok_or(Error::<T>::OrderMatchGetOrderError)?;
+
+        // canceled in this match by a one-cancels-other group
+        if o.is_finished() {
+            return Ok(None);
+        }
+
+        Ok(Some(<Module<T>>::engine_order(&<Module<T>>::visible_order(&o)?)?))
+    }
+
+    fn fill(
+        &mut self,
+        fill: &Fill<T::Hash>,
+        _taker: &engine::Order<T::Hash>,
+    ) -> DispatchResult {
+        <Module<T>>::apply_book_fill(self, fill)
+    }
+}
 
 impl<T: Trait> Module<T> {
     pub fn order_match(
         ep_hash: T::Hash,
         order: &mut LimitOrder<T>,
     ) -> result::Result<bool, DispatchError> {
-        let mut head = <OrderLinkedItemList<T>>::read_head(ep_hash);
+        let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+        let mut taker = Self::engine_order(order)?;
+
+        // balance changes are written once per account and asset after the match
+        let mut book = PairBook {
+            reference_price: Self::band_reference_price(&ep),
+            ep,
+            taker: &mut *order,
+            settlement: Settlement::<T>::default(),
+        };
+        engine::match_order(&mut book, &mut taker)?;
 
-        let end_item_price;
-        let otype = order.otype;
-        let oprice = order.price;
+        let PairBook { settlement, .. } = book;
+        settlement.apply()?;
 
-        if otype == OrderType::Buy {
-            end_item_price = Some(T::Price::min_value());
-        } else {
-            end_item_price = Some(T::Price::max_value());
+        Ok(order.status == OrderStatus::Filled)
+    }
+
+    pub fn engine_order(
+        order: &LimitOrder<T>,
+    ) -> result::Result<engine::Order<T::Hash>, DispatchError> {
+        Ok(engine::Order {
+            id: order.hash,
+            side: match order.otype {
+                OrderType::Buy => Side::Buy,
+                OrderType::Sell => Side::Sell,
+            },
+            price: Self::into_128(order.price)?,
+            remained_sell_amount: Self::into_128(order.remained_sell_amount)?,
+            remained_buy_amount: Self::into_128(order.remained_buy_amount)?,
+        })
+    }
+
+    pub fn engine_error(error: engine::MatchError) -> DispatchError {
+        match error {
+            engine::MatchError::Overflow => Error::<T>::OverflowError.into(),
+            engine::MatchError::Underflow => Error::<T>::OrderMatchSubstractError.into(),
+            engine::MatchError::ZeroFill => Error::<T>::BoundsCheckFailed.into(),
         }
+    }
 
-        let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
-        let give: T::Hash;
-        let have: T::Hash;
+    /// Write a fill the engine made between the taker and a resting order.
+    fn apply_book_fill(book: &mut PairBook<T>, fill: &Fill<T::Hash>) -> DispatchResult {
+        let ep_hash = book.ep.hash;
+        let order = &mut *book.taker;
+        let settlement = &mut book.settlement;
+        let otype = order.otype;
+
+        let (give, have) = match otype {
+            OrderType::Buy => (book.ep.base, book.ep.quote),
+            OrderType::Sell => (book.ep.quote, book.ep.base),
+        };
 
+        let mut o =
+            Self::pair_order(ep_hash, fill.maker).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+        Self::ensure_price_band(ep_hash, book.reference_price, o.price)?;
+
+        let base_qty: T::Balance = Self::from_128(fill.base_amount)?;
+        let quote_qty: T::Balance = Self::from_128(fill.quote_amount)?;
+
+        let give_qty: T::Balance;
+        let have_qty: T::Balance;
         match otype {
             OrderType::Buy => {
-                give = ep.base;
-                have = ep.quote;
+                give_qty = base_qty;
+                have_qty = quote_qty;
             }
             OrderType::Sell => {
-                give = ep.quote;
-                have = ep.base;
+                give_qty = quote_qty;
+                have_qty = base_qty;
             }
         };
 
-        loop {
-            if order.status == OrderStatus::Filled {
-                break;
-            }
+        if order.remained_sell_amount == order.sell_amount {
+            order.status = OrderStatus::PartialFilled;
+        }
 
-            let item_price = Self::next_match_price(&head, !otype);
+        if o.remained_sell_amount == o.sell_amount {
+            o.status = OrderStatus::PartialFilled;
+        }
 
-            if item_price == end_item_price {
-                break;
+        settlement.pay_frozen(&order.owner, give, &o.owner, give_qty)?;
+        settlement.pay_frozen(&o.owner, have, &order.owner, have_qty)?;
+
+        order.remained_sell_amount = order
+            .remained_sell_amount
+            .checked_sub(&give_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+        order.remained_buy_amount = order
+            .remained_buy_amount
+            .checked_sub(&have_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+
+        o.remained_sell_amount = o
+            .remained_sell_amount
+            .checked_sub(&have_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+        o.remained_buy_amount = o
+            .remained_buy_amount
+            .checked_sub(&give_qty)
+            .ok_or(Error::<T>::OrderMatchSubstractError)?;
+
+        // the rest of a dust order can not be traded and is released
+        if Self::is_dust(&order)? {
+            order.status = OrderStatus::Filled;
+            if order.remained_sell_amount != Zero::zero() {
+                settlement.release(&order.owner, give, order.remained_sell_amount)?;
+                order.remained_sell_amount = Zero::zero();
             }
 
-            let item_price = item_price.ok_or(Error::<T>::OrderMatchGetPriceError)?;
+            Self::close_order(order.owner.clone(), ep_hash, order.hash);
 
-            if !Self::price_matched(oprice, otype, item_price) {
-                break;
-            }
+            ensure!(
+                order.is_finished(),
+                Error::<T>::OrderMatchOrderIsNotFinished
+            );
+        }
 
-            let item = <LinkedItemList<T>>::get((ep_hash, Some(item_price)))
-                .ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
-            for o in item.orders.iter() {
-                let mut o = Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
-
-                let (base_qty, quote_qty) = Self::calculate_ex_amount(&o, &order)?;
-
-                let give_qty: T::Balance;
-                let have_qty: T::Balance;
-                match otype {
-                    OrderType::Buy => {
-                        give_qty = base_qty;
-                        have_qty = quote_qty;
-                    }
-                    OrderType::Sell => {
-                        give_qty = quote_qty;
-                        have_qty = base_qty;
-                    }
-                };
-
-                if order.remained_sell_amount == order.sell_amount {
-                    order.status = OrderStatus::PartialFilled;
-                }
-
-                if o.remained_sell_amount == o.sell_amount {
-                    o.status = OrderStatus::PartialFilled;
-                }
-
-                <assets::Module<T>>::unfreeze(order.owner.clone(), give, give_qty)?;
-                <assets::Module<T>>::unfreeze(o.owner.clone(), have, have_qty)?;
-
-                <assets::Module<T>>::transfer(
-                    order.owner.clone(),
-                    give,
-                    o.owner.clone(),
-                    give_qty,
-                )?;
-                <assets::Module<T>>::transfer(
-                    o.owner.clone(),
-                    have,
-                    order.owner.clone(),
-                    have_qty,
-                )?;
-
-                order.remained_sell_amount = order
-                    .remained_sell_amount
-                    .checked_sub(&give_qty)
-                    .ok_or(Error::<T>::OrderMatchSubstractError)?;
-                order.remained_buy_amount = order
-                    .remained_buy_amount
-                    .checked_sub(&have_qty)
-                    .ok_or(Error::<T>::OrderMatchSubstractError)?;
-
-                o.remained_sell_amount = o
-                    .remained_sell_amount
-                    .checked_sub(&have_qty)
-                    .ok_or(Error::<T>::OrderMatchSubstractError)?;
-                o.remained_buy_amount = o
-                    .remained_buy_amount
-                    .checked_sub(&give_qty)
-                    .ok_or(Error::<T>::OrderMatchSubstractError)?;
-
-                if order.remained_buy_amount == Zero::zero() {
-                    order.status = OrderStatus::Filled;
-                    if order.remained_sell_amount != Zero::zero() {
-                        <assets::Module<T>>::unfreeze(
-                            order.owner.clone(),
-                            give,
-                            order.remained_sell_amount,
-                        )?;
-                        order.remained_sell_amount = Zero::zero();
-                    }
-
-                    <OwnedEPOpenedOrders<T>>::remove_order(
-                        order.owner.clone(),
-                        ep_hash,
-                        order.hash,
-                    );
-                    <OwnedEPClosedOrders<T>>::add_order(order.owner.clone(), ep_hash, order.hash);
-
-                    ensure!(
-                        order.is_finished(),
-                        Error::<T>::OrderMatchOrderIsNotFinished
-                    );
-                }
-
-                if o.remained_buy_amount == Zero::zero() {
-                    o.status = OrderStatus::Filled;
-                    if o.remained_sell_amount != Zero::zero() {
-                        <assets::Module<T>>::unfreeze(
-                            o.owner.clone(),
-                            have,
-                            o.remained_sell_amount,
-                        )?;
-                        o.remained_sell_amount = Zero::zero();
-                    }
-
-                    <OwnedEPOpenedOrders<T>>::remove_order(o.owner.clone(), ep_hash, o.hash);
-                    <OwnedEPClosedOrders<T>>::add_order(o.owner.clone(), ep_hash, o.hash);
-
-                    ensure!(o.is_finished(), Error::<T>::OrderMatchOrderIsNotFinished);
-                }
-
-                Orders::insert(order.hash.clone(), order.clone());
-                Orders::insert(o.hash.clone(), o.clone());
-
-                // save the exchange pair market data
-                Self::set_ep_market_data(ep_hash, o.price, quote_qty)?;
-
-                // update maker order's amount in market
-                <OrderLinkedItemList<T>>::update_amount(ep_hash, o.price, have_qty, give_qty);
-
-                // remove the matched order
-                <OrderLinkedItemList<T>>::remove_all(ep_hash, !otype);
-
-                // save the exchange data
-                let dex = Dex::new(ep.base, ep.quote, &o, &order, base_qty, quote_qty);
-                Exchanges::insert(dex.hash, dex.clone());
-
-                Self::deposit_event(RawEvent::ExchangeCreated(
-                    order.owner.clone(),
-                    ep.base,
-                    ep.quote,
-                    dex.hash,
-                    dex.clone(),
-                ));
-
-                // save exchange reference data to store
-                <OrderOwnedExchanges<T>>::add_exchange(order.hash, dex.hash);
-                <OrderOwnedExchanges<T>>::add_exchange(o.hash, dex.hash);
-
-                <OwnedExchanges<T>>::add_exchange(order.owner.clone(), dex.hash);
-                <OwnedExchanges<T>>::add_exchange(o.owner.clone(), dex.hash);
-
-                <OwnedEPExchanges<T>>::add_exchange(order.owner.clone(), ep_hash, dex.hash);
-                <OwnedEPExchanges<T>>::add_exchange(o.owner.clone(), ep_hash, dex.hash);
-                <ExchangePairOwnedExchanges<T>>::add_exchange(ep_hash, dex.hash);
-
-                if order.status == OrderStatus::Filled {
-                    break;
-                }
+        // the rest of a dust order can not be traded and is released
+        if Self::is_dust(&o)? {
+            o.status = OrderStatus::Filled;
+            if o.remained_sell_amount != Zero::zero() {
+                settlement.release(&o.owner, have, o.remained_sell_amount)?;
+                o.remained_sell_amount = Zero::zero();
             }
 
-            head = <OrderLinkedItemList<T>>::read_head(ep_hash);
+            Self::close_order(o.owner.clone(), ep_hash, o.hash);
+
+            ensure!(o.is_finished(), Error::<T>::OrderMatchOrderIsNotFinished);
         }
 
-        if order.status == OrderStatus::Filled {
-            Ok(true)
+        // a fill cancels the other orders of a one-cancels-other group
+        if let Some(group_hash) = order.group {
+            Self::trigger_order_group(group_hash, order)?;
+        }
+        if let Some(group_hash) = o.group {
+            Self::trigger_order_group(group_hash, &mut o)?;
+        }
+
+        // update maker order's amount in market
+        Self::apply_level_fill(ep_hash, &mut o, have_qty, give_qty)?;
+
+        <Orders<T>>::insert(ep_hash, order.hash, order.clone());
+        <Orders<T>>::insert(ep_hash, o.hash, o.clone());
+
+        // save the exchange pair market data
+        Self::set_ep_market_data(ep_hash, o.price, quote_qty)?;
+
+        // take the filled maker off its level, an emptied level is removed with it
+        if o.is_finished() {
+            <OrderLinkedItemList<T>>::remove_order(
+                ep_hash,
+                o.price,
+                o.hash,
+                Zero::zero(),
+                Zero::zero(),
+            )?;
+        }
+
+        // save the exchange data
+        let dex = Dex::new(book.ep.base, book.ep.quote, &o, &order, base_qty, quote_qty);
+        let dex_hash = dex.hash;
+        Self::record_exchange(ep_hash, o.hash, order.hash, dex);
+
+        let order_filled = order.status == OrderStatus::Filled;
+        let o_filled = o.status == OrderStatus::Filled;
+        Self::deposit_fill_event(&order, dex_hash, base_qty, quote_qty, order_filled);
+        Self::deposit_fill_event(&o, dex_hash, base_qty, quote_qty, o_filled);
+
+        Ok(())
+    }
+
+    pub fn record_exchange(
+        ep_hash: T::Hash,
+        maker_order_hash: T::Hash,
+        taker_order_hash: T::Hash,
+        dex: Dex<T>,
+    ) {
+        Exchanges::insert(dex.hash, dex.clone());
+
+        Self::deposit_event(RawEvent::ExchangeCreated(
+            dex.taker.clone(),
+            dex.base,
+            dex.quote,
+            dex.hash,
+            dex.clone(),
+        ));
+
+        // save exchange reference data to store
+        <OrderOwnedExchanges<T>>::add_exchange(taker_order_hash, dex.hash);
+        <OrderOwnedExchanges<T>>::add_exchange(maker_order_hash, dex.hash);
+
+        <OwnedExchanges<T>>::add_exchange(dex.taker.clone(), dex.hash);
+        <OwnedExchanges<T>>::add_exchange(dex.maker.clone(), dex.hash);
+
+        <OwnedEPExchanges<T>>::add_exchange(dex.taker.clone(), ep_hash, dex.hash);
+        <OwnedEPExchanges<T>>::add_exchange(dex.maker.clone(), ep_hash, dex.hash);
+        <ExchangePairOwnedExchanges<T>>::add_exchange(ep_hash, dex.hash);
+    }
+
+    /// Report a trade of an order, `filled` when the trade completed the order.
+    pub fn deposit_fill_event(
+        order: &LimitOrder<T>,
+        dex_hash: T::Hash,
+        base_amount: T::Balance,
+        quote_amount: T::Balance,
+        filled: bool,
+    ) {
+        if filled {
+            Self::deposit_event(RawEvent::OrderFilled(
+                order.hash,
+                dex_hash,
+                base_amount,
+                quote_amount,
+            ));
         } else {
-            Ok(false)
+            Self::deposit_event(RawEvent::OrderPartiallyFilled(
+                order.hash,
+                dex_hash,
+                base_amount,
+                quote_amount,
+                order.remained_sell_amount,
+            ));
         }
     }
 }
@@ -0,0 +1,511 @@
+use super::*;
+use rstd::collections::btree_set::BTreeSet;
+use support::storage::migration::{get_storage_value, put_storage_value, take_storage_value};
+use support::storage::unhashed;
+
+/// Exchange pair layout before `V1_1_0`, without the matching mode.
+#[derive(Encode, Decode)]
+struct LegacyExchangePair<T: Trait> {
+    hash: T::Hash,
+    base: T::Hash,
+    quote: T::Hash,
+    latest_matched_price: Option<T::Price>,
+}
+
+/// An order layout of an earlier release, upgraded to the current `LimitOrder`.
+trait LegacyOrder<T: Trait>: Decode {
+    fn upgrade(self) -> LimitOrder<T>;
+}
+
+/// Order layout before `V1_2_0`, without the iceberg.
+#[derive(Encode, Decode)]
+struct LimitOrderV1_0_0<T: Trait> {
+    hash: T::Hash,
+    base: T::Hash,
+    quote: T::Hash,
+    owner: T::AccountId,
+    price: T::Price,
+    sell_amount: T::Balance,
+    buy_amount: T::Balance,
+    remained_sell_amount: T::Balance,
+    remained_buy_amount: T::Balance,
+    otype: OrderType,
+    status: OrderStatus,
+}
+
+impl<T: Trait> LegacyOrder<T> for LimitOrderV1_0_0<T> {
+    fn upgrade(self) -> LimitOrder<T> {
+        LimitOrder::<T> {
+            hash: self.hash,
+            base: self.base,
+            quote: self.quote,
+            owner: self.owner,
+            price: self.price,
+            sell_amount: self.sell_amount,
+            buy_amount: self.buy_amount,
+            remained_sell_amount: self.remained_sell_amount,
+            remained_buy_amount: self.remained_buy_amount,
+            otype: self.otype,
+            status: self.status,
+            iceberg: None,
+            group: None,
+            sizing: OrderSizing::Spend,
+        }
+    }
+}
+
+/// Order layout before `V1_3_0`, without the group.
+#[derive(Encode, Decode)]
+struct LimitOrderV1_2_0<T: Trait> {
+    hash: T::Hash,
+    base: T::Hash,
+    quote: T::Hash,
+    owner: T::AccountId,
+    price: T::Price,
+    sell_amount: T::Balance,
+    buy_amount: T::Balance,
+    remained_sell_amount: T::Balance,
+    remained_buy_amount: T::Balance,
+    otype: OrderType,
+    status: OrderStatus,
+    iceberg: Option<Iceberg<T::Balance>>,
+}
+
+impl<T: Trait> LegacyOrder<T> for LimitOrderV1_2_0<T> {
+    fn upgrade(self) -> LimitOrder<T> {
+        LimitOrder::<T> {
+            hash: self.hash,
+            base: self.base,
+            quote: self.quote,
+            owner: self.owner,
+            price: self.price,
+            sell_amount: self.sell_amount,
+            buy_amount: self.buy_amount,
+            remained_sell_amount: self.remained_sell_amount,
+            remained_buy_amount: self.remained_buy_amount,
+            otype: self.otype,
+            status: self.status,
+            iceberg: self.iceberg,
+            group: None,
+            sizing: OrderSizing::Spend,
+        }
+    }
+}
+
+/// Order layout before `V6_0_0`, without the sizing.
+#[derive(Encode, Decode)]
+struct LimitOrderV1_3_0<T: Trait> {
+    hash: T::Hash,
+    base: T::Hash,
+    quote: T::Hash,
+    owner: T::AccountId,
+    price: T::Price,
+    sell_amount: T::Balance,
+    buy_amount: T::Balance,
+    remained_sell_amount: T::Balance,
+    remained_buy_amount: T::Balance,
+    otype: OrderType,
+    status: OrderStatus,
+    iceberg: Option<Iceberg<T::Balance>>,
+    group: Option<T::Hash>,
+}
+
+impl<T: Trait> LegacyOrder<T> for LimitOrderV1_3_0<T> {
+    fn upgrade(self) -> LimitOrder<T> {
+        LimitOrder::<T> {
+            hash: self.hash,
+            base: self.base,
+            quote: self.quote,
+            owner: self.owner,
+            price: self.price,
+            sell_amount: self.sell_amount,
+            buy_amount: self.buy_amount,
+            remained_sell_amount: self.remained_sell_amount,
+            remained_buy_amount: self.remained_buy_amount,
+            otype: self.otype,
+            status: self.status,
+            iceberg: self.iceberg,
+            group: self.group,
+            sizing: OrderSizing::Spend,
+        }
+    }
+}
+
+/// Price level layout before `V5_0_0`, with the orders of the level in a vector.
+#[derive(Encode, Decode)]
+struct LegacyLinkedItem<K1, K2, K3> {
+    prev: Option<K2>,
+    next: Option<K2>,
+    price: Option<K2>,
+    buy_amount: K3,
+    sell_amount: K3,
+    orders: Vec<K1>,
+}
+
+type LegacyOrderLinkedItem<T> =
+    LegacyLinkedItem<<T as system::Trait>::Hash, <T as Trait>::Price, <T as balances::Trait>::Balance>;
+
+impl<T: Trait> Module<T> {
+    /// Record every existing exchange pair as continuously matched, the only mode there was.
+    pub fn migrate_exchange_pair_mode() {
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep_hash = match Self::exchange_pair_hash_by_index(ep_index) {
+                Some(ep_hash) => ep_hash,
+                None => continue,
+            };
+
+            let key = <ExchangePairs<T>>::hashed_key_for(ep_hash);
+            let legacy = match unhashed::get::<LegacyExchangePair<T>>(&key) {
+                Some(legacy) => legacy,
+                None => continue,
+            };
+
+            ExchangePairs::insert(
+                ep_hash,
+                ExchangePair::<T> {
+                    hash: legacy.hash,
+                    base: legacy.base,
+                    quote: legacy.quote,
+                    latest_matched_price: legacy.latest_matched_price,
+                    matching_mode: MatchingMode::Continuous,
+                },
+            );
+        }
+    }
+
+    /// Rewrite the stored orders from the layout they had at `version` to the current one.
+    pub fn migrate_order_layout(version: Releases) {
+        if version < Releases::V1_2_0 {
+            Self::migrate_orders::<LimitOrderV1_0_0<T>>(version);
+        } else if version < Releases::V1_3_0 {
+            Self::migrate_orders::<LimitOrderV1_2_0<T>>(version);
+        } else if version < Releases::V6_0_0 {
+            Self::migrate_orders::<LimitOrderV1_3_0<T>>(version);
+        }
+    }
+
+    /// Upgrade the orders of every exchange pair and the `Orderbook` from the legacy layout
+    /// `L`. The orders of a pair are read from the layouts of `ExchangePairOwnedOrders` and
+    /// `Orders` at `version`.
+    fn migrate_orders<L: LegacyOrder<T>>(version: Releases) {
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep_hash = match Self::exchange_pair_hash_by_index(ep_index) {
+                Some(ep_hash) => ep_hash,
+                None => continue,
+            };
+
+            for index in 0..Self::exchange_pair_owned_order_index(ep_hash) {
+                let order_hash = if version < Releases::V3_0_0 {
+                    get_storage_value::<T::Hash>(
+                        b"ExchangeStorage",
+                        b"ExchangePairOwnedOrders",
+                        &(ep_hash, index).using_encoded(Blake2_256::hash),
+                    )
+                } else {
+                    Self::exchange_pair_owned_order(ep_hash, index)
+                };
+                let order_hash = match order_hash {
+                    Some(order_hash) => order_hash,
+                    None => continue,
+                };
+
+                if version < Releases::V3_0_0 {
+                    let key = order_hash.using_encoded(Blake2_256::hash);
+                    let legacy = get_storage_value::<L>(b"ExchangeStorage", b"Orders", &key);
+                    if let Some(legacy) = legacy {
+                        put_storage_value(b"ExchangeStorage", b"Orders", &key, legacy.upgrade());
+                    }
+                } else {
+                    let key = <Orders<T>>::hashed_key_for(ep_hash, order_hash);
+                    if let Some(legacy) = unhashed::get::<L>(&key) {
+                        <Orders<T>>::insert(ep_hash, order_hash, legacy.upgrade());
+                    }
+                }
+            }
+        }
+
+        let book = get_storage_value::<Vec<Option<L>>>(b"ExchangeStorage", b"Orderbook", &[]);
+        if let Some(book) = book {
+            let book: Vec<Option<LimitOrder<T>>> =
+                book.into_iter().map(|o| o.map(L::upgrade)).collect();
+            put_storage_value(b"ExchangeStorage", b"Orderbook", &[], book);
+        }
+    }
+
+    /// Take a value from a map of the `hasher(blake2_256)` key layout used before `V3_0_0`.
+    fn take_blake2_256_value<K: Encode, V: Decode>(item: &[u8], key: K) -> Option<V> {
+        take_storage_value(b"ExchangeStorage", item, &key.using_encoded(Blake2_256::hash))
+    }
+
+    /// Move the tuple key maps into the `blake2_128_concat` double maps and key the orders by
+    /// their exchange pair. The old keys are opaque, so they are rebuilt from the exchange
+    /// pairs, their orders and exchanges.
+    /// Exchanges of signed orders are only moved for the accounts and pairs, signed orders
+    /// are not stored so their `OrderOwnedExchanges` entries stay in the old layout.
+    pub fn migrate_to_double_maps() {
+        let mut accounts: BTreeSet<T::AccountId> = BTreeSet::new();
+        let mut ep_accounts: BTreeSet<(T::AccountId, T::Hash)> = BTreeSet::new();
+
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep = match Self::exchange_pair_hash_by_index(ep_index)
+                .and_then(|ep_hash| Self::exchange_pair(ep_hash))
+            {
+                Some(ep) => ep,
+                None => continue,
+            };
+            let ep_hash = ep.hash;
+
+            if let Some(hash) = Self::take_blake2_256_value::<_, T::Hash>(
+                b"ExchangePairsHashByBaseQuote",
+                (ep.base, ep.quote),
+            ) {
+                <ExchangePairsHashByBaseQuote<T>>::insert(ep.base, ep.quote, hash);
+            }
+
+            for index in 0..Self::exchange_pair_owned_order_index(ep_hash) {
+                let order_hash = match Self::take_blake2_256_value::<_, T::Hash>(
+                    b"ExchangePairOwnedOrders",
+                    (ep_hash, index),
+                ) {
+                    Some(order_hash) => order_hash,
+                    None => continue,
+                };
+                <ExchangePairOwnedOrders<T>>::insert(ep_hash, index, order_hash);
+
+                for ex_index in 0..Self::order_owned_exchanges_index(order_hash) {
+                    if let Some(dex_hash) = Self::take_blake2_256_value::<_, T::Hash>(
+                        b"OrderOwnedExchanges",
+                        (order_hash, ex_index),
+                    ) {
+                        <OrderOwnedExchanges<T>>::insert(order_hash, ex_index, dex_hash);
+                    }
+                }
+
+                if let Some(order) =
+                    Self::take_blake2_256_value::<_, LimitOrder<T>>(b"Orders", order_hash)
+                {
+                    accounts.insert(order.owner.clone());
+                    ep_accounts.insert((order.owner.clone(), ep_hash));
+                    <Orders<T>>::insert(ep_hash, order_hash, order);
+                    <OrderPairs<T>>::insert(order_hash, ep_hash);
+                }
+            }
+
+            for index in 0..Self::exchange_pair_owned_exchanges_index(ep_hash) {
+                let dex_hash = match Self::take_blake2_256_value::<_, T::Hash>(
+                    b"ExchangePairOwnedExchanges",
+                    (ep_hash, index),
+                ) {
+                    Some(dex_hash) => dex_hash,
+                    None => continue,
+                };
+                <ExchangePairOwnedExchanges<T>>::insert(ep_hash, index, dex_hash);
+
+                if let Some(dex) = Self::exchange(dex_hash) {
+                    accounts.insert(dex.maker.clone());
+                    accounts.insert(dex.taker.clone());
+                    ep_accounts.insert((dex.maker, ep_hash));
+                    ep_accounts.insert((dex.taker, ep_hash));
+                }
+            }
+
+            Self::migrate_linked_items(ep_hash);
+
+            // only the buckets of the last day are of interest
+            let now = <system::Module<T>>::block_number();
+            let mut n = now.saturating_sub(T::BlocksPerDay::get().into());
+            while n <= now {
+                if let Some(bucket) = Self::take_blake2_256_value::<
+                    _,
+                    (T::Balance, Option<T::Price>, Option<T::Price>),
+                >(b"EPExchangeDataBucket", (ep_hash, n))
+                {
+                    <EPExchangeDataBucket<T>>::insert(ep_hash, n, bucket);
+                }
+                n = n + One::one();
+            }
+        }
+
+        for account in accounts {
+            for index in 0..Self::owned_orders_index(&account) {
+                if let Some(order_hash) = Self::take_blake2_256_value::<_, T::Hash>(
+                    b"OwnedOrders",
+                    (account.clone(), index),
+                ) {
+                    <OwnedOrders<T>>::insert(&account, index, order_hash);
+                }
+            }
+
+            for index in 0..Self::owned_exchanges_index(&account) {
+                if let Some(dex_hash) = Self::take_blake2_256_value::<_, T::Hash>(
+                    b"OwnedExchanges",
+                    (account.clone(), index),
+                ) {
+                    <OwnedExchanges<T>>::insert(&account, index, dex_hash);
+                }
+            }
+        }
+
+        for (account, ep_hash) in ep_accounts {
+            if let Some(count) = Self::take_blake2_256_value::<_, u64>(
+                b"OwnedEPExchangesIndex",
+                (account.clone(), ep_hash),
+            ) {
+                <OwnedEPExchangesIndex<T>>::insert(&account, ep_hash, count);
+
+                for index in 0..count {
+                    if let Some(dex_hash) = Self::take_blake2_256_value::<_, T::Hash>(
+                        b"OwnedEPExchanges",
+                        (account.clone(), ep_hash, index),
+                    ) {
+                        <OwnedEPExchanges<T>>::insert((account.clone(), ep_hash), index, dex_hash);
+                    }
+                }
+            }
+
+            if let Some(orders) = Self::take_blake2_256_value::<_, Vec<T::Hash>>(
+                b"OwnedEPOpenedOrders",
+                (account.clone(), ep_hash),
+            ) {
+                <OwnedEPOpenedOrders<T>>::insert(&account, ep_hash, orders);
+            }
+
+            if let Some(count) = Self::take_blake2_256_value::<_, u64>(
+                b"OwnedEPOrdersIndex",
+                (account.clone(), ep_hash),
+            ) {
+                <OwnedEPOrdersIndex<T>>::insert(&account, ep_hash, count);
+
+                for index in 0..count {
+                    if let Some(entry) = Self::take_blake2_256_value::<_, (T::Hash, T::BlockNumber)>(
+                        b"OwnedEPOrders",
+                        (account.clone(), ep_hash, index),
+                    ) {
+                        <OwnedEPOrders<T>>::insert((account.clone(), ep_hash), index, entry);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Walk the price levels of an exchange pair from the bottom to the top sentinel.
+    fn migrate_linked_items(ep_hash: T::Hash) {
+        let mut price = Some(T::Price::min_value());
+
+        loop {
+            let item = match Self::take_blake2_256_value::<_, LegacyOrderLinkedItem<T>>(
+                b"LinkedItemList",
+                (ep_hash, price),
+            ) {
+                Some(item) => item,
+                None => break,
+            };
+
+            // still in the vector layout, converted by `migrate_level_order_queues`
+            let next = item.next;
+            unhashed::put(&<LinkedItemList<T>>::hashed_key_for(ep_hash, price), &item);
+
+            if price == Some(T::Price::max_value()) {
+                break;
+            }
+            price = next;
+        }
+    }
+
+    /// Move the orders of every price level from the level's vector into its queue, keeping
+    /// their order.
+    pub fn migrate_level_order_queues() {
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep_hash = match Self::exchange_pair_hash_by_index(ep_index) {
+                Some(ep_hash) => ep_hash,
+                None => continue,
+            };
+
+            let mut price = Some(T::Price::min_value());
+
+            loop {
+                let key = <LinkedItemList<T>>::hashed_key_for(ep_hash, price);
+                let legacy = match unhashed::get::<LegacyOrderLinkedItem<T>>(&key) {
+                    Some(legacy) => legacy,
+                    None => break,
+                };
+
+                let mut item = OrderLinkedItem::<T> {
+                    prev: legacy.prev,
+                    next: legacy.next,
+                    price: legacy.price,
+                    buy_amount: legacy.buy_amount,
+                    sell_amount: legacy.sell_amount,
+                    first_order: None,
+                    last_order: None,
+                    order_count: 0,
+                };
+
+                // the head and the sentinels hold no orders
+                if let Some(level_price) = price {
+                    for order_hash in legacy.orders {
+                        <OrderLinkedItemList<T>>::push_order(
+                            ep_hash,
+                            level_price,
+                            &mut item,
+                            order_hash,
+                        );
+                    }
+                }
+                <LinkedItemList<T>>::insert(ep_hash, price, item);
+
+                if price == Some(T::Price::max_value()) {
+                    break;
+                }
+                price = legacy.next;
+            }
+        }
+    }
+
+    /// Index the existing price levels of every exchange pair. The bids are the levels from
+    /// the bottom sentinel to the head, the asks the ones from the head to the top sentinel.
+    pub fn migrate_price_level_index() {
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep_hash = match Self::exchange_pair_hash_by_index(ep_index) {
+                Some(ep_hash) => ep_hash,
+                None => continue,
+            };
+
+            let mut otype = OrderType::Buy;
+            let mut price = Self::linked_item(ep_hash, Some(T::Price::min_value()))
+                .and_then(|item| item.next);
+
+            loop {
+                match price {
+                    None => otype = OrderType::Sell,
+                    Some(p) if p == T::Price::max_value() => break,
+                    Some(p) => {
+                        let mut bounds = Self::price_level_index(ep_hash, (otype, None));
+                        if bounds.is_empty() {
+                            bounds.push(T::Price::min_value());
+                        }
+                        let bound = bounds[bounds.len() - 1];
+
+                        // the levels come in ascending order, so only the last bucket grows
+                        let mut bucket = Self::price_level_index(ep_hash, (otype, Some(bound)));
+                        if bucket.len() < PRICE_BUCKET_CAPACITY / 2 {
+                            bucket.push(p);
+                            <PriceLevelIndex<T>>::insert(ep_hash, (otype, Some(bound)), bucket);
+                        } else {
+                            let mut bucket = Vec::new();
+                            bucket.push(p);
+                            <PriceLevelIndex<T>>::insert(ep_hash, (otype, Some(p)), bucket);
+                            bounds.push(p);
+                            <PriceLevelIndex<T>>::insert(ep_hash, (otype, None), bounds);
+                        }
+                    }
+                }
+
+                price = match Self::linked_item(ep_hash, price) {
+                    Some(item) => item.next,
+                    None => break,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Test runtime of the DEX module.
+
+use super::*;
+use primitives::H256;
+use sp_runtime::{
+    testing::{Header, TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+};
+use support::{
+    impl_outer_event, impl_outer_origin, parameter_types,
+    traits::{Currency, OnUnbalanced},
+    weights::Weight,
+};
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = system {}
+}
+
+mod dex {
+    pub use crate::Event;
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        system<T>,
+        balances<T>,
+        assets<T>,
+        dex<T>,
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = TestEvent;
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 1;
+}
+
+impl balances::Trait for Test {
+    type Balance = u128;
+    type DustRemoval = ();
+    type Event = TestEvent;
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = system::Module<Test>;
+}
+
+impl assets::Trait for Test {
+    type Event = TestEvent;
+}
+
+parameter_types! {
+    pub const PriceFactor: u128 = 1;
+    pub const BlocksPerDay: u32 = 6 * 60 * 24;
+    pub const OpenedOrdersArrayCap: u8 = 20;
+    pub const CommitRevealWindow: u64 = 10;
+    pub const UnrevealedCommitmentPenalty: Perbill = Perbill::from_percent(10);
+    pub const OrderGroupCap: u8 = 4;
+    pub const OrderDeposit: u128 = 10;
+    pub const StopOrdersCap: u32 = 4;
+    pub const TwapObservationPeriod: u64 = 10;
+    pub const TwapObservationCap: u32 = 10;
+    pub const ListingBond: u128 = 1_000;
+    pub const TradingFee: Permill = Permill::zero();
+}
+
+impl Trait for Test {
+    type Event = TestEvent;
+    type Price = u128;
+    type PriceFactor = PriceFactor;
+    type BlocksPerDay = BlocksPerDay;
+    type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
+    type GovernanceOrigin = system::EnsureRoot<u64>;
+    type CommitRevealWindow = CommitRevealWindow;
+    type UnrevealedCommitmentPenalty = UnrevealedCommitmentPenalty;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type OrderGroupCap = OrderGroupCap;
+    type OrderDeposit = OrderDeposit;
+    type StopOrdersCap = StopOrdersCap;
+    type TwapObservationPeriod = TwapObservationPeriod;
+    type TwapObservationCap = TwapObservationCap;
+    type ReferencePrice = ();
+    type ListingBond = ListingBond;
+    type ListingOrigin = system::EnsureRoot<u64>;
+    type ListingSlash = ToTreasury;
+    type TradingFee = TradingFee;
+    type OnTradingFee = ();
+}
+
+/// Pays slashed listing bonds to `TREASURY`.
+pub struct ToTreasury;
+
+impl OnUnbalanced<balances::NegativeImbalance<Test>> for ToTreasury {
+    fn on_nonzero_unbalanced(amount: balances::NegativeImbalance<Test>) {
+        Balances::resolve_creating(&TREASURY, amount);
+    }
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type Assets = assets::Module<Test>;
+pub type Exchange = Module<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+pub const TREASURY: u64 = 99;
+
+pub const ENDOWMENT: u128 = 1_000_000;
+pub const SUPPLY: u128 = 1_000_000_000;
+
+/// Hashes of the genesis assets `BASE` and `QUOTE` and of their exchange pair.
+pub fn base() -> H256 {
+    Assets::genesis_asset_hash(b"BASE")
+}
+
+pub fn quote() -> H256 {
+    Assets::genesis_asset_hash(b"QUOTE")
+}
+
+pub fn ep_hash() -> H256 {
+    Exchange::exchange_pair_hash_by_base_quote(base(), quote()).expect("genesis exchange pair")
+}
+
+/// Alice owns `BASE` and `QUOTE` and hands a third of each supply to Bob and Charlie, the
+/// pair is matched continuously.
+pub fn new_test_ext() -> runtime_io::TestExternalities {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+    balances::GenesisConfig::<Test> {
+        balances: vec![(ALICE, ENDOWMENT), (BOB, ENDOWMENT), (CHARLIE, ENDOWMENT)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let holders = vec![(BOB, SUPPLY / 3), (CHARLIE, SUPPLY / 3)];
+    assets::GenesisConfig::<Test> {
+        assets: vec![
+            (ALICE, b"BASE".to_vec(), SUPPLY, holders.clone()),
+            (ALICE, b"QUOTE".to_vec(), SUPPLY, holders),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    GenesisConfig::<Test> {
+        exchange_pairs: vec![(
+            ALICE,
+            b"BASE".to_vec(),
+            b"QUOTE".to_vec(),
+            MatchingMode::Continuous,
+            None,
+        )],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext = runtime_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Events of the DEX module, oldest first.
+pub fn dex_events() -> Vec<Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            TestEvent::dex(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Hash of the `index`th order an account placed.
+pub fn owned_order(owner: u64, index: u64) -> H256 {
+    Exchange::owned_order(owner, index).expect("order placed")
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// The order with the given hash, in whichever exchange pair it was placed.
+    pub fn order(order_hash: T::Hash) -> Option<LimitOrder<T>> {
+        Self::order_pair(order_hash).and_then(|ep_hash| Self::pair_order(ep_hash, order_hash))
+    }
+
+    /// Ensure the account can have `count` more open orders in the exchange pair, its
+    /// waiting stop orders count as open orders.
+    pub fn ensure_open_orders_limit(
+        owner: &T::AccountId,
+        ep_hash: T::Hash,
+        count: usize,
+    ) -> DispatchResult {
+        let opened = Self::owned_ep_opened_orders(owner, ep_hash)
+            .map_or(0, |orders| orders.len())
+            + Self::owned_ep_stop_orders(owner, ep_hash).len();
+
+        ensure!(
+            opened + count <= T::OpenedOrdersArrayCap::get() as usize,
+            Error::<T>::TooManyOpenOrders
+        );
+
+        Ok(())
+    }
+
+    /// Add an order to the open orders of its owner, reserving the order deposit.
+    pub fn open_order(owner: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) -> DispatchResult {
+        Self::ensure_open_orders_limit(&owner, ep_hash, 1)?;
+
+        let deposit = T::OrderDeposit::get();
+        <balances::Module<T>>::reserve(&owner, deposit)?;
+        <OrderDeposits<T>>::insert(order_hash, deposit);
+
+        <OwnedEPOpenedOrders<T>>::add_order(owner.clone(), ep_hash, order_hash);
+        Self::add_order_history(owner, ep_hash, order_hash);
+
+        Ok(())
+    }
+
+    /// Remove a finished order from the open orders of its owner and refund its deposit.
+    pub fn close_order(owner: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) {
+        <OwnedEPOpenedOrders<T>>::remove_order(owner.clone(), ep_hash, order_hash);
+
+        let deposit = <OrderDeposits<T>>::take(order_hash);
+        if deposit != Zero::zero() {
+            <balances::Module<T>>::unreserve(&owner, deposit);
+        }
+    }
+}
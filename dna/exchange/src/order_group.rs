@@ -0,0 +1,222 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Place one-cancels-other orders selling the same asset. Only the largest
+    /// `sell_amount` of the group is frozen since at most one of the orders trades.
+    /// An order with a trigger price is held as a stop order and placed at its price once
+    /// the market crosses the trigger, as the stop-loss next to a take-profit order.
+    pub fn do_create_order_group(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        orders: Vec<(T::Price, T::Balance, Option<T::Price>)>,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            ensure!(
+                orders.len() >= 2 && orders.len() <= T::OrderGroupCap::get() as usize,
+                Error::<T>::OrderGroupSizeInvalid
+            );
+
+            let ep_hash = Self::ensure_exchange_pair(base, quote)?;
+            Self::ensure_open_orders_limit(&sender, ep_hash, orders.len())?;
+
+            let stops = orders.iter().filter(|(_, _, trigger_price)| trigger_price.is_some());
+            Self::ensure_stop_orders_cap(ep_hash, stops.count())?;
+
+            let mut frozen_amount = T::Balance::zero();
+            for (price, sell_amount, trigger_price) in orders.iter() {
+                Self::ensure_bounds(*price, *sell_amount)?;
+                Self::ensure_counterparty_amount_bounds(otype, *price, *sell_amount)?;
+                if let Some(trigger_price) = trigger_price {
+                    Self::ensure_bounds(*trigger_price, *sell_amount)?;
+                }
+                frozen_amount = rstd::cmp::max(frozen_amount, *sell_amount);
+            }
+
+            let asset;
+            match otype {
+                OrderType::Buy => asset = base,
+                OrderType::Sell => asset = quote,
+            };
+
+            <assets::Module<T>>::ensure_free_balance(sender.clone(), asset, frozen_amount)?;
+            <assets::Module<T>>::freeze(sender.clone(), asset, frozen_amount)?;
+
+            let nonce = Nonce::get();
+            let hash = (
+                <system::Module<T>>::block_number(),
+                sender.clone(),
+                ep_hash,
+                otype,
+                nonce,
+            )
+                .using_encoded(<T as system::Trait>::Hashing::hash);
+            Nonce::mutate(|n| *n += 1);
+
+            let group = OrderGroup {
+                hash,
+                owner: sender.clone(),
+                ep_hash,
+                asset,
+                frozen_amount,
+                members: Vec::new(),
+            };
+            <OrderGroups<T>>::insert(hash, group);
+
+            Self::deposit_event(RawEvent::OrderGroupCreated(sender.clone(), hash));
+
+            for (price, sell_amount, trigger_price) in orders {
+                // an earlier member traded on placement and canceled the group
+                if !<OrderGroups<T>>::contains_key(hash) {
+                    break;
+                }
+
+                match trigger_price {
+                    Some(trigger_price) => {
+                        let so_hash =
+                            Self::new_stop_order_hash(&sender, base, quote, otype, sell_amount);
+                        let so = StopOrder {
+                            hash: so_hash,
+                            base,
+                            quote,
+                            owner: sender.clone(),
+                            otype,
+                            sell_amount,
+                            trail: None,
+                            limit_price: Some(price),
+                            reference_price: trigger_price,
+                            trigger_price,
+                            group: Some(hash),
+                        };
+                        Self::add_order_group_member(hash, so.hash);
+                        Self::add_stop_order(ep_hash, so)?;
+                    }
+                    None => Self::do_create_order(
+                        sender.clone(),
+                        base,
+                        quote,
+                        otype,
+                        price,
+                        OrderSizing::Spend,
+                        sell_amount,
+                        None,
+                        Some(hash),
+                    )?,
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn add_order_group_member(group_hash: T::Hash, order_hash: T::Hash) {
+        <OrderGroups<T>>::mutate(group_hash, |group| {
+            if let Some(group) = group {
+                group.members.push(order_hash);
+            }
+        });
+    }
+
+    pub fn remove_order_group_member(group_hash: T::Hash, order_hash: T::Hash) {
+        <OrderGroups<T>>::mutate(group_hash, |group| {
+            if let Some(group) = group {
+                group.members.retain(|&x| x != order_hash);
+            }
+        });
+    }
+
+    /// Called when a member of a group trades: the other members are canceled and
+    /// the funds only they needed are released.
+    pub fn trigger_order_group(group_hash: T::Hash, member: &mut LimitOrder<T>) -> DispatchResult {
+        let group = match <OrderGroups<T>>::take(group_hash) {
+            Some(group) => group,
+            None => return Ok(()),
+        };
+
+        member.group = None;
+
+        for order_hash in group.members.iter() {
+            if *order_hash == member.hash {
+                continue;
+            }
+
+            // a stop order of the group was never placed
+            if let Some(so) = Self::stop_order(order_hash) {
+                Self::remove_stop_order(&so)?;
+                Self::deposit_event(RawEvent::StopOrderCanceled(so.owner, so.hash));
+                continue;
+            }
+
+            let mut sibling = Self::pair_order(group.ep_hash, order_hash)
+                .ok_or(Error::<T>::NoMatchingOrder)?;
+            if sibling.is_finished() {
+                continue;
+            }
+
+            sibling.group = None;
+            Self::cancel_order_in_book(group.ep_hash, &mut sibling, false)?;
+        }
+
+        let released = group.frozen_amount.saturating_sub(member.sell_amount);
+        if released != Zero::zero() {
+            <assets::Module<T>>::unfreeze(group.owner.clone(), group.asset, released)?;
+        }
+
+        Self::deposit_event(RawEvent::OrderGroupTriggered(group_hash, member.hash));
+
+        Ok(())
+    }
+
+    /// Called when a member of a group is canceled: the group keeps the funds its
+    /// largest remaining member needs and is dissolved once one member is left.
+    pub fn leave_order_group(group_hash: T::Hash, member_hash: T::Hash) -> DispatchResult {
+        let mut group = Self::order_group(group_hash).ok_or(Error::<T>::NoMatchingOrderGroup)?;
+
+        group.members.retain(|&x| x != member_hash);
+
+        let mut remaining = Vec::new();
+        let mut remaining_stops = Vec::new();
+        for order_hash in group.members.iter() {
+            if let Some(so) = Self::stop_order(order_hash) {
+                remaining_stops.push(so);
+                continue;
+            }
+
+            let order = Self::pair_order(group.ep_hash, order_hash)
+                .ok_or(Error::<T>::NoMatchingOrder)?;
+            if !order.is_finished() {
+                remaining.push(order);
+            }
+        }
+
+        let frozen_amount = remaining
+            .iter()
+            .map(|o| o.sell_amount)
+            .chain(remaining_stops.iter().map(|so| so.sell_amount))
+            .fold(T::Balance::zero(), rstd::cmp::max);
+
+        let released = group.frozen_amount.saturating_sub(frozen_amount);
+        if released != Zero::zero() {
+            <assets::Module<T>>::unfreeze(group.owner.clone(), group.asset, released)?;
+        }
+
+        // the last member keeps the funds frozen for it on its own
+        if remaining.len() + remaining_stops.len() <= 1 {
+            for mut order in remaining {
+                order.group = None;
+                <Orders<T>>::insert(group.ep_hash, order.hash, order);
+            }
+            for mut so in remaining_stops {
+                so.group = None;
+                <StopOrders<T>>::insert(so.hash, so);
+            }
+            <OrderGroups<T>>::remove(group_hash);
+        } else {
+            group.frozen_amount = frozen_amount;
+            <OrderGroups<T>>::insert(group_hash, group);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use super::*;
+use rstd::collections::btree_map::BTreeMap;
+
+impl<T: Trait> Module<T> {
+    pub fn add_order_history(owner: T::AccountId, ep_hash: T::Hash, order_hash: T::Hash) {
+        let index = Self::owned_ep_orders_index(&owner, ep_hash);
+        <OwnedEPOrders<T>>::insert(
+            (owner.clone(), ep_hash),
+            index,
+            (order_hash, <system::Module<T>>::block_number()),
+        );
+        <OwnedEPOrdersIndex<T>>::insert(owner, ep_hash, index + 1);
+    }
+
+    /// Open orders of an account in an exchange pair, newest first.
+    pub fn account_open_orders(owner: T::AccountId, ep_hash: T::Hash) -> Vec<T::Hash> {
+        Self::owned_ep_opened_orders(owner, ep_hash).unwrap_or_default()
+    }
+
+    /// Up to `limit` orders of an account in an exchange pair, oldest first, starting at
+    /// history index `start`. Only orders with the given `status` placed within
+    /// `from_block..=to_block` are returned, along with the index to continue from.
+    pub fn account_order_history(
+        owner: T::AccountId,
+        ep_hash: T::Hash,
+        status: Option<OrderStatus>,
+        from_block: Option<T::BlockNumber>,
+        to_block: Option<T::BlockNumber>,
+        start: u64,
+        limit: u32,
+    ) -> (Vec<T::Hash>, Option<u64>) {
+        let count = Self::owned_ep_orders_index(&owner, ep_hash);
+        let mut orders = Vec::new();
+
+        // orders are indexed in the order they were placed, so by block number
+        let first = match from_block {
+            Some(from_block) => Self::first_order_history_index(&owner, ep_hash, count, from_block),
+            None => 0,
+        };
+
+        let mut index = rstd::cmp::max(start, first);
+        while index < count {
+            if orders.len() >= limit as usize {
+                return (orders, Some(index));
+            }
+
+            if let Some((order_hash, block_number)) =
+                Self::owned_ep_order((owner.clone(), ep_hash), index)
+            {
+                if to_block.map_or(false, |to_block| block_number > to_block) {
+                    break;
+                }
+
+                let status_matched = match &status {
+                    Some(status) => Self::pair_order(ep_hash, order_hash)
+                        .map_or(false, |o| o.status == *status),
+                    None => true,
+                };
+                if status_matched {
+                    orders.push(order_hash);
+                }
+            }
+
+            index += 1;
+        }
+
+        (orders, None)
+    }
+
+    fn first_order_history_index(
+        owner: &T::AccountId,
+        ep_hash: T::Hash,
+        count: u64,
+        from_block: T::BlockNumber,
+    ) -> u64 {
+        let mut low = 0;
+        let mut high = count;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let placed_before = Self::owned_ep_order((owner.clone(), ep_hash), mid)
+                .map_or(true, |(_, block_number)| block_number < from_block);
+
+            if placed_before {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        low
+    }
+
+    /// Build the complete order history and open orders of every account from the orders of
+    /// each exchange pair, replacing the capped open and closed order vectors. The block an
+    /// order was placed in was not recorded before, so migrated entries use block zero.
+    pub fn migrate_order_history() {
+        // (AccountId, ExchangePairHash) => whether the history is rebuilt
+        let mut accounts: BTreeMap<(T::AccountId, T::Hash), bool> = BTreeMap::new();
+
+        for ep_index in 0..Self::exchange_pair_index() {
+            let ep_hash = match Self::exchange_pair_hash_by_index(ep_index) {
+                Some(ep_hash) => ep_hash,
+                None => continue,
+            };
+
+            for index in 0..Self::exchange_pair_owned_order_index(ep_hash) {
+                let order = match Self::exchange_pair_owned_order(ep_hash, index)
+                    .and_then(|order_hash| Self::pair_order(ep_hash, order_hash))
+                {
+                    Some(order) => order,
+                    None => continue,
+                };
+
+                let key = (order.owner.clone(), ep_hash);
+                let rebuild = *accounts.entry(key.clone()).or_insert_with(|| {
+                    // accounts with history were already recorded in the new layout
+                    let rebuild = Self::owned_ep_orders_index(&order.owner, ep_hash) == 0;
+                    if rebuild {
+                        <OwnedEPOpenedOrders<T>>::remove(&order.owner, ep_hash);
+                    }
+                    rebuild
+                });
+
+                if !rebuild {
+                    continue;
+                }
+
+                let history_index = Self::owned_ep_orders_index(&order.owner, ep_hash);
+                <OwnedEPOrders<T>>::insert(
+                    key,
+                    history_index,
+                    (order.hash, T::BlockNumber::zero()),
+                );
+                <OwnedEPOrdersIndex<T>>::insert(&order.owner, ep_hash, history_index + 1);
+
+                if !order.is_finished() {
+                    <OwnedEPOpenedOrders<T>>::add_order(order.owner, ep_hash, order.hash);
+                }
+            }
+        }
+
+        // `OwnedEPClosedOrders` was removed, its entries are cleared by prefix
+        let closed_orders_prefix = [
+            Twox128::hash(b"ExchangeStorage"),
+            Twox128::hash(b"OwnedEPClosedOrders"),
+        ]
+        .concat();
+        support::storage::unhashed::kill_prefix(&closed_orders_prefix);
+    }
+}
@@ -60,69 +60,45 @@ impl<T: Trait> Module<T> {
         TryFrom::<u128>::try_from(i).map_err(|_| Error::<T>::NumberCastError.into())
     }
 
-    pub fn calculate_ex_amount(
-        maker_order: &LimitOrder<T>,
-        taker_order: &LimitOrder<T>,
-    ) -> result::Result<(T::Balance, T::Balance), DispatchError> {
-        let buyer_order;
-        let seller_order;
-        if taker_order.otype == OrderType::Buy {
-            buyer_order = taker_order;
-            seller_order = maker_order;
-        } else {
-            buyer_order = maker_order;
-            seller_order = taker_order;
-        }
-
-        // TODO DP: overflow checked need
-        let mut seller_order_filled = true;
-        if seller_order.remained_buy_amount <= buyer_order.remained_sell_amount {
-            // seller_order is Filled
-            let quote_qty: u128 = Self::into_128(seller_order.remained_buy_amount)?
-                * T::PriceFactor::get()
-                / maker_order.price.into();
-            if Self::into_128(buyer_order.remained_buy_amount)? < quote_qty {
-                seller_order_filled = false;
-            }
-        } else {
-            let base_qty: u128 = Self::into_128(buyer_order.remained_buy_amount)?
-                * maker_order.price.into()
-                / T::PriceFactor::get();
-            if Self::into_128(seller_order.remained_buy_amount)? >= base_qty {
-                seller_order_filled = false;
-            }
-        }
-
-        // if seller_order.remained_buy_amount <= buyer_order.remained_sell_amount { // seller_order is Filled
-        if seller_order_filled {
-            let mut quote_qty: u128 = Self::into_128(seller_order.remained_buy_amount)?
-                * T::PriceFactor::get()
-                / maker_order.price.into();
-            let buy_amount_v2 =
-                quote_qty * Self::into_128(maker_order.price)? / T::PriceFactor::get();
-            if buy_amount_v2 != Self::into_128(seller_order.remained_buy_amount)?
-                && Self::into_128(buyer_order.remained_buy_amount)? > quote_qty
-            // have fraction, seller(Filled) give more to align
-            {
-                quote_qty = quote_qty + 1;
-            }
+    /// Base amount worth `quote_amount` at `price`.
+    pub fn quote_to_base(
+        quote_amount: T::Balance,
+        price: T::Price,
+        rounding: Rounding,
+    ) -> result::Result<T::Balance, DispatchError> {
+        let base_amount = engine::quote_to_base(
+            Self::into_128(quote_amount)?,
+            Self::into_128(price)?,
+            T::PriceFactor::get(),
+            rounding,
+        )
+        .map_err(Self::engine_error)?;
+
+        Self::from_128(base_amount)
+    }
 
-            return Ok((seller_order.remained_buy_amount, Self::from_128(quote_qty)?));
-        } else {
-            // buyer_order is Filled
-            let mut base_qty: u128 = Self::into_128(buyer_order.remained_buy_amount)?
-                * maker_order.price.into()
-                / T::PriceFactor::get();
-            let buy_amount_v2 = base_qty * T::PriceFactor::get() / maker_order.price.into();
-            if buy_amount_v2 != Self::into_128(buyer_order.remained_buy_amount)?
-                && Self::into_128(seller_order.remained_buy_amount)? > base_qty
-            // have fraction, buyer(Filled) give more to align
-            {
-                base_qty = base_qty + 1;
-            }
+    /// Quote amount worth `base_amount` at `price`.
+    pub fn base_to_quote(
+        base_amount: T::Balance,
+        price: T::Price,
+        rounding: Rounding,
+    ) -> result::Result<T::Balance, DispatchError> {
+        let quote_amount = engine::base_to_quote(
+            Self::into_128(base_amount)?,
+            Self::into_128(price)?,
+            T::PriceFactor::get(),
+            rounding,
+        )
+        .map_err(Self::engine_error)?;
+
+        Self::from_128(quote_amount)
+    }
 
-            return Ok((Self::from_128(base_qty)?, buyer_order.remained_buy_amount));
-        }
+    /// An order is dust once what it has left can not be traded for a whole unit at its
+    /// price, its remaining funds are then released instead of resting in the book.
+    pub fn is_dust(order: &LimitOrder<T>) -> result::Result<bool, DispatchError> {
+        engine::is_dust(&Self::engine_order(order)?, T::PriceFactor::get())
+            .map_err(Self::engine_error)
     }
 
     pub fn next_match_price(item: &OrderLinkedItem<T>, otype: OrderType) -> Option<T::Price> {
@@ -0,0 +1,89 @@
+use super::*;
+use assets::SettlementDelta;
+use rstd::collections::btree_map::BTreeMap;
+
+/// Balance changes of a match, kept in memory and written once per account and asset.
+pub struct Settlement<T: Trait> {
+    deltas: BTreeMap<(T::AccountId, T::Hash), SettlementDelta<T::Balance>>,
+    /// AssetHash => trading fees paid to the fee account
+    fees: BTreeMap<T::Hash, T::Balance>,
+}
+
+impl<T: Trait> Default for Settlement<T> {
+    fn default() -> Self {
+        Settlement {
+            deltas: BTreeMap::new(),
+            fees: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Trait> Settlement<T> {
+    /// Pay `amount` of the frozen `asset` of `from` to `to`, the same as an unfreeze
+    /// followed by a transfer. The trading fee is taken from what `to` receives.
+    pub fn pay_frozen(
+        &mut self,
+        from: &T::AccountId,
+        asset: T::Hash,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let delta = self.delta(from, asset);
+        delta.spent_frozen = delta
+            .spent_frozen
+            .checked_add(&amount)
+            .ok_or(Error::<T>::OverflowError)?;
+
+        let fee = <Module<T>>::trading_fee(amount);
+
+        let delta = self.delta(to, asset);
+        delta.received = delta
+            .received
+            .checked_add(&(amount - fee))
+            .ok_or(Error::<T>::OverflowError)?;
+
+        if fee != Zero::zero() {
+            let delta = self.delta(&<Module<T>>::fee_account(), asset);
+            delta.received = delta
+                .received
+                .checked_add(&fee)
+                .ok_or(Error::<T>::OverflowError)?;
+
+            let collected = self.fees.entry(asset).or_insert_with(Zero::zero);
+            *collected = collected
+                .checked_add(&fee)
+                .ok_or(Error::<T>::OverflowError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Unfreeze `amount` of the `asset` of `who`.
+    pub fn release(&mut self, who: &T::AccountId, asset: T::Hash, amount: T::Balance) -> DispatchResult {
+        let delta = self.delta(who, asset);
+        delta.released = delta
+            .released
+            .checked_add(&amount)
+            .ok_or(Error::<T>::OverflowError)?;
+
+        Ok(())
+    }
+
+    /// Write the net balance change of every account and asset, then hand the trading
+    /// fees over to `OnTradingFee`.
+    pub fn apply(self) -> DispatchResult {
+        for ((who, asset), delta) in self.deltas {
+            <assets::Module<T>>::settle(who, asset, delta)?;
+        }
+
+        for (asset, fee) in self.fees {
+            <Module<T>>::collect_trading_fee(asset, fee);
+        }
+
+        Ok(())
+    }
+
+    fn delta(&mut self, who: &T::AccountId, asset: T::Hash) -> &mut SettlementDelta<T::Balance> {
+        self.deltas.entry((who.clone(), asset)).or_insert_with(Default::default)
+    }
+}
@@ -0,0 +1,207 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn signed_order_hash(order: &SignedOrder<T>) -> T::Hash {
+        order.using_encoded(<T as system::Trait>::Hashing::hash)
+    }
+
+    /// Message the owner of a signed order signs, the SCALE encoded
+    /// `(SIGNED_ORDER_DOMAIN, genesis hash, order)` tuple.
+    pub fn signed_order_payload(order: &SignedOrder<T>) -> Vec<u8> {
+        let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+        (SIGNED_ORDER_DOMAIN, genesis_hash, order).encode()
+    }
+
+    /// Amount of quote asset a signed order can trade in total.
+    pub fn signed_order_capacity(
+        order: &SignedOrder<T>,
+    ) -> result::Result<T::Balance, DispatchError> {
+        match order.otype {
+            OrderType::Sell => Ok(order.sell_amount),
+            OrderType::Buy => {
+                let capacity: u128 = (U256::from(Self::into_128(order.sell_amount)?)
+                    * U256::from(T::PriceFactor::get())
+                    / U256::from(Self::into_128(order.price)?))
+                .try_into()
+                .map_err(|_| Error::<T>::OverflowError)?;
+
+                Self::from_128(capacity)
+            }
+        }
+    }
+
+    fn ensure_signed_order(
+        order: &SignedOrder<T>,
+        signature: &T::OffchainSignature,
+    ) -> result::Result<T::Hash, DispatchError> {
+        ensure!(
+            signature.verify(Self::signed_order_payload(order).as_slice(), &order.owner),
+            Error::<T>::InvalidOrderSignature
+        );
+
+        let hash = Self::signed_order_hash(order);
+
+        ensure!(
+            !Self::signed_order_canceled(hash),
+            Error::<T>::SignedOrderCanceled
+        );
+        ensure!(
+            <system::Module<T>>::block_number() <= order.expires_at,
+            Error::<T>::SignedOrderExpired
+        );
+
+        Ok(hash)
+    }
+
+    /// Add a fill to a signed order, the first fill schedules it for expiry.
+    fn fill_signed_order(
+        order: &SignedOrder<T>,
+        hash: T::Hash,
+        fill_amount: T::Balance,
+    ) -> DispatchResult {
+        let capacity = Self::signed_order_capacity(order)?;
+        let previously_filled = Self::signed_order_filled(hash);
+        let filled = previously_filled
+            .checked_add(&fill_amount)
+            .ok_or(Error::<T>::OverflowError)?;
+        ensure!(filled <= capacity, Error::<T>::SignedOrderOverfilled);
+
+        if previously_filled.is_zero() {
+            <SignedOrdersByExpiry<T>>::mutate(order.expires_at, |orders| {
+                orders.push((order.owner.clone(), hash, capacity))
+            });
+        }
+        <SignedOrderFilled<T>>::insert(hash, filled);
+
+        Ok(())
+    }
+
+    /// Report the signed orders expiring in block `n` with quote amount left to fill, and drop
+    /// their fills. An expired signed order is rejected before its fills are read again. Signed
+    /// orders that were never filled are unknown to the chain and expire silently.
+    pub fn expire_signed_orders(n: T::BlockNumber) {
+        for (owner, hash, capacity) in <SignedOrdersByExpiry<T>>::take(n) {
+            let filled = <SignedOrderFilled<T>>::take(hash);
+            if filled < capacity && !Self::signed_order_canceled(hash) {
+                Self::deposit_event(RawEvent::SignedOrderExpired(
+                    owner,
+                    hash,
+                    filled,
+                    capacity - filled,
+                ));
+            }
+        }
+    }
+
+    /// Settle `fill_amount` of quote asset between two signed orders at the maker's price.
+    pub fn do_settle_signed_orders(
+        maker_order: SignedOrder<T>,
+        maker_signature: T::OffchainSignature,
+        taker_order: SignedOrder<T>,
+        taker_signature: T::OffchainSignature,
+        fill_amount: T::Balance,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            ensure!(fill_amount > Zero::zero(), Error::<T>::BoundsCheckFailed);
+            ensure!(
+                maker_order.base == taker_order.base && maker_order.quote == taker_order.quote,
+                Error::<T>::NoMatchingExchangePair
+            );
+            ensure!(
+                maker_order.otype != taker_order.otype,
+                Error::<T>::SignedOrderSameSide
+            );
+            ensure!(
+                Self::price_matched(taker_order.price, taker_order.otype, maker_order.price),
+                Error::<T>::SignedOrderPriceNotMatched
+            );
+
+            let ep_hash = Self::ensure_exchange_pair(maker_order.base, maker_order.quote)?;
+            let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+            Self::ensure_pair_not_halted(ep_hash)?;
+            Self::ensure_price_band(ep_hash, Self::band_reference_price(&ep), maker_order.price)?;
+
+            let maker_hash = Self::ensure_signed_order(&maker_order, &maker_signature)?;
+            let taker_hash = Self::ensure_signed_order(&taker_order, &taker_signature)?;
+
+            Self::fill_signed_order(&maker_order, maker_hash, fill_amount)?;
+            Self::fill_signed_order(&taker_order, taker_hash, fill_amount)?;
+
+            // rounded in the maker's favor, as for book orders
+            let rounding = match maker_order.otype {
+                OrderType::Buy => Rounding::Down,
+                OrderType::Sell => Rounding::Up,
+            };
+            let base_qty = Self::quote_to_base(fill_amount, maker_order.price, rounding)?;
+            ensure!(base_qty > Zero::zero(), Error::<T>::BoundsCheckFailed);
+
+            let buyer;
+            let seller;
+            if taker_order.otype == OrderType::Buy {
+                buyer = taker_order.owner.clone();
+                seller = maker_order.owner.clone();
+            } else {
+                buyer = maker_order.owner.clone();
+                seller = taker_order.owner.clone();
+            }
+
+            Self::pay_with_fee(buyer.clone(), maker_order.base, seller.clone(), base_qty)?;
+            Self::pay_with_fee(seller.clone(), maker_order.quote, buyer.clone(), fill_amount)?;
+
+            Self::set_ep_market_data(ep_hash, maker_order.price, fill_amount)?;
+
+            let nonce = Nonce::get();
+            let hash = (
+                <system::Module<T>>::block_number(),
+                nonce,
+                maker_hash,
+                taker_hash,
+                fill_amount,
+            )
+                .using_encoded(<T as system::Trait>::Hashing::hash);
+            Nonce::mutate(|x| *x += 1);
+
+            let dex = Dex {
+                hash,
+                base: maker_order.base,
+                quote: maker_order.quote,
+                buyer,
+                seller,
+                maker: maker_order.owner.clone(),
+                taker: taker_order.owner.clone(),
+                otype: taker_order.otype,
+                price: maker_order.price,
+                base_amount: base_qty,
+                quote_amount: fill_amount,
+            };
+            Self::record_exchange(ep_hash, maker_hash, taker_hash, dex);
+
+            Self::deposit_event(RawEvent::SignedOrdersSettled(
+                maker_hash,
+                taker_hash,
+                base_qty,
+                fill_amount,
+            ));
+
+            Ok(())
+        })
+    }
+
+    pub fn do_cancel_signed_order(sender: T::AccountId, order: SignedOrder<T>) -> DispatchResult {
+        Self::transactional(|| {
+            ensure!(order.owner == sender, Error::<T>::CanOnlyCancelOwnOrder);
+
+            let hash = Self::signed_order_hash(&order);
+            ensure!(
+                !Self::signed_order_canceled(hash),
+                Error::<T>::SignedOrderCanceled
+            );
+
+            <CanceledSignedOrders<T>>::insert(hash, true);
+
+            Self::deposit_event(RawEvent::SignedOrderCanceled(sender, hash));
+
+            Ok(())
+        })
+    }
+}
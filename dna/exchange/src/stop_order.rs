@@ -0,0 +1,337 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn do_create_stop_order(
+        sender: T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        sell_amount: T::Balance,
+        trail: TrailDistance<T::Price>,
+        limit_price: Option<T::Price>,
+    ) -> DispatchResult {
+        Self::transactional(|| {
+            if let Some(price) = limit_price {
+                Self::ensure_bounds(price, sell_amount)?;
+            }
+            ensure!(sell_amount > Zero::zero(), Error::<T>::BoundsCheckFailed);
+
+            let ep_hash = Self::ensure_exchange_pair(base, quote)?;
+            let ep = Self::exchange_pair(ep_hash).ok_or(Error::<T>::NoMatchingExchangePair)?;
+            let reference_price = ep.latest_matched_price.ok_or(Error::<T>::NoMarketPrice)?;
+            Self::ensure_stop_orders_cap(ep_hash, 1)?;
+
+            // the order is placed at its limit price, or near the trigger price without one
+            let trigger_price = Self::stop_trigger_price(otype, trail, reference_price);
+            Self::ensure_bounds(trigger_price, sell_amount)?;
+            Self::ensure_counterparty_amount_bounds(
+                otype,
+                limit_price.unwrap_or(trigger_price),
+                sell_amount,
+            )?;
+
+            let op_asset_hash;
+            match otype {
+                OrderType::Buy => op_asset_hash = base,
+                OrderType::Sell => op_asset_hash = quote,
+            };
+
+            <assets::Module<T>>::ensure_free_balance(sender.clone(), op_asset_hash, sell_amount)?;
+
+            let so = StopOrder {
+                hash: Self::new_stop_order_hash(&sender, base, quote, otype, sell_amount),
+                base,
+                quote,
+                owner: sender.clone(),
+                otype,
+                sell_amount,
+                trail: Some(trail),
+                limit_price,
+                reference_price,
+                trigger_price,
+                group: None,
+            };
+            Self::add_stop_order(ep_hash, so)?;
+
+            <assets::Module<T>>::freeze(sender, op_asset_hash, sell_amount)
+        })
+    }
+
+    pub fn new_stop_order_hash(
+        owner: &T::AccountId,
+        base: T::Hash,
+        quote: T::Hash,
+        otype: OrderType,
+        sell_amount: T::Balance,
+    ) -> T::Hash {
+        let nonce = Nonce::get();
+        let random_seed = <randomness_collective_flip::Module<T>>::random_seed();
+        let hash = (
+            random_seed,
+            <system::Module<T>>::block_number(),
+            base,
+            quote,
+            owner.clone(),
+            otype,
+            sell_amount,
+            nonce,
+        )
+            .using_encoded(<T as system::Trait>::Hashing::hash);
+        Nonce::mutate(|n| *n += 1);
+
+        hash
+    }
+
+    /// Every trade walks the stop orders of its pair, so a pair holds at most
+    /// `StopOrdersCap` of them.
+    pub fn ensure_stop_orders_cap(ep_hash: T::Hash, count: usize) -> DispatchResult {
+        ensure!(
+            (Self::exchange_pair_stop_orders(ep_hash).len() + count) as u32
+                <= T::StopOrdersCap::get(),
+            Error::<T>::TooManyStopOrders
+        );
+
+        Ok(())
+    }
+
+    /// Hold a stop order off the book until the market crosses its trigger price. It
+    /// counts as an open order of its owner and reserves the order deposit.
+    pub fn add_stop_order(ep_hash: T::Hash, so: StopOrder<T>) -> DispatchResult {
+        Self::ensure_open_orders_limit(&so.owner, ep_hash, 1)?;
+
+        let deposit = T::OrderDeposit::get();
+        <balances::Module<T>>::reserve(&so.owner, deposit)?;
+        <OrderDeposits<T>>::insert(so.hash, deposit);
+        <OwnedEPStopOrders<T>>::mutate(&so.owner, ep_hash, |hashes| hashes.push(so.hash));
+
+        <StopOrders<T>>::insert(so.hash, so.clone());
+        <EPStopOrders<T>>::mutate(ep_hash, |hashes| hashes.push(so.hash));
+
+        Self::deposit_event(RawEvent::StopOrderCreated(
+            so.owner,
+            ep_hash,
+            so.hash,
+            so.trigger_price,
+        ));
+
+        Ok(())
+    }
+
+    /// Remove a stop order from the open orders of its owner and refund its deposit.
+    fn close_stop_order(ep_hash: T::Hash, so: &StopOrder<T>) {
+        <OwnedEPStopOrders<T>>::mutate(&so.owner, ep_hash, |hashes| {
+            hashes.retain(|&x| x != so.hash)
+        });
+
+        let deposit = <OrderDeposits<T>>::take(so.hash);
+        if deposit != Zero::zero() {
+            <balances::Module<T>>::unreserve(&so.owner, deposit);
+        }
+    }
+
+    pub fn do_cancel_stop_order(sender: T::AccountId, hash: T::Hash) -> DispatchResult {
+        Self::transactional(|| {
+            let so = Self::stop_order(hash).ok_or(Error::<T>::NoMatchingStopOrder)?;
+            ensure!(so.owner == sender, Error::<T>::CanOnlyCancelOwnOrder);
+
+            Self::discard_stop_order(&so)?;
+
+            Self::deposit_event(RawEvent::StopOrderCanceled(sender, hash));
+
+            Ok(())
+        })
+    }
+
+    /// Remove a stop order that will not be placed and release its funds.
+    fn discard_stop_order(so: &StopOrder<T>) -> DispatchResult {
+        Self::remove_stop_order(so)?;
+
+        match so.group {
+            // the funds are shared with the rest of the group
+            Some(group_hash) => Self::leave_order_group(group_hash, so.hash),
+            None => <assets::Module<T>>::unfreeze(
+                so.owner.clone(),
+                Self::stop_order_asset(so),
+                so.sell_amount,
+            ),
+        }
+    }
+
+    /// Current trigger price of a stop order, exposed through the runtime API.
+    pub fn stop_order_trigger_price(hash: T::Hash) -> Option<T::Price> {
+        Self::stop_order(hash).map(|so| so.trigger_price)
+    }
+
+    /// A sell stop triggers `trail` below the highest price seen, a buy stop `trail`
+    /// above the lowest one.
+    fn stop_trigger_price(
+        otype: OrderType,
+        trail: TrailDistance<T::Price>,
+        reference_price: T::Price,
+    ) -> T::Price {
+        let distance = match trail {
+            TrailDistance::Absolute(distance) => distance,
+            TrailDistance::Percent(percent) => percent * reference_price,
+        };
+
+        match otype {
+            OrderType::Sell => reference_price.saturating_sub(distance),
+            OrderType::Buy => reference_price.saturating_add(distance),
+        }
+    }
+
+    /// Move the trigger prices of the pair's trailing stop orders after a trade at `price`,
+    /// the orders whose trigger is crossed are executed in `on_finalize`.
+    pub fn trail_stop_orders(ep_hash: T::Hash, price: T::Price) {
+        let mut triggered = Vec::new();
+
+        for hash in Self::exchange_pair_stop_orders(ep_hash) {
+            let mut so = match Self::stop_order(hash) {
+                Some(so) => so,
+                None => continue,
+            };
+
+            if let Some(trail) = so.trail {
+                let favorable = match so.otype {
+                    OrderType::Sell => price > so.reference_price,
+                    OrderType::Buy => price < so.reference_price,
+                };
+                if favorable {
+                    so.reference_price = price;
+                    so.trigger_price = Self::stop_trigger_price(so.otype, trail, price);
+                    <StopOrders<T>>::insert(hash, so.clone());
+                }
+            }
+
+            let crossed = match so.otype {
+                OrderType::Sell => price <= so.trigger_price,
+                OrderType::Buy => price >= so.trigger_price,
+            };
+            if crossed {
+                triggered.push(hash);
+                Self::deposit_event(RawEvent::StopOrderTriggered(hash, price));
+            }
+        }
+
+        if !triggered.is_empty() {
+            <EPStopOrders<T>>::mutate(ep_hash, |hashes| {
+                hashes.retain(|x| !triggered.contains(x))
+            });
+            <TriggeredStopOrders<T>>::mutate(|hashes| hashes.extend(triggered));
+        }
+    }
+
+    /// Place the orders of triggered stops. Trades made by these orders can trigger
+    /// further stops, so this runs until no stop is left. A stop whose order cannot be
+    /// placed is discarded with its funds released and an `OrderRejected` event.
+    pub fn execute_triggered_stop_orders() {
+        loop {
+            let triggered = <TriggeredStopOrders<T>>::take();
+            if triggered.is_empty() {
+                break;
+            }
+
+            for hash in triggered {
+                let so = match Self::stop_order(hash) {
+                    Some(so) => so,
+                    None => continue,
+                };
+
+                // the placement is rolled back as a whole, the stop order is still there
+                if let Err(e) = Self::execute_stop_order(&so) {
+                    support::debug::warn!("stop order {:?} execution failed: {:?}", hash, e);
+
+                    let discarded = Self::transactional(|| Self::discard_stop_order(&so));
+                    if let Err(discard_error) = discarded {
+                        support::debug::warn!(
+                            "stop order {:?} discard failed: {:?}",
+                            hash,
+                            discard_error
+                        );
+                    }
+                    Self::deposit_event(RawEvent::OrderRejected(so.owner, hash, e));
+                }
+            }
+        }
+    }
+
+    fn execute_stop_order(so: &StopOrder<T>) -> DispatchResult {
+        Self::transactional(|| {
+            // the order replaces the stop order and its deposit
+            Self::remove_stop_order(so)?;
+
+            let price = match so.limit_price {
+                Some(price) => price,
+                None => Self::stop_market_price(so)?,
+            };
+
+            match so.group {
+                // placed with the funds of the group, in place of the stop order
+                Some(group_hash) => {
+                    Self::remove_order_group_member(group_hash, so.hash);
+                    Self::do_create_order(
+                        so.owner.clone(),
+                        so.base,
+                        so.quote,
+                        so.otype,
+                        price,
+                        OrderSizing::Spend,
+                        so.sell_amount,
+                        None,
+                        Some(group_hash),
+                    )
+                }
+                None => {
+                    <assets::Module<T>>::unfreeze(
+                        so.owner.clone(),
+                        Self::stop_order_asset(so),
+                        so.sell_amount,
+                    )?;
+                    Self::do_create_limit_order(
+                        so.owner.clone(),
+                        so.base,
+                        so.quote,
+                        so.otype,
+                        price,
+                        so.sell_amount,
+                    )
+                }
+            }
+        })
+    }
+
+    /// A market stop is placed at the best price on the other side of the book, or at
+    /// its trigger price when that side is empty.
+    fn stop_market_price(so: &StopOrder<T>) -> result::Result<T::Price, DispatchError> {
+        let ep_hash = Self::ensure_exchange_pair(so.base, so.quote)?;
+        let head = <OrderLinkedItemList<T>>::read_head(ep_hash);
+
+        let sentinel = match so.otype {
+            OrderType::Buy => T::Price::max_value(),
+            OrderType::Sell => T::Price::min_value(),
+        };
+
+        match Self::next_match_price(&head, !so.otype) {
+            Some(price) if price != sentinel => Ok(price),
+            _ => Ok(so.trigger_price),
+        }
+    }
+
+    fn stop_order_asset(so: &StopOrder<T>) -> T::Hash {
+        match so.otype {
+            OrderType::Buy => so.base,
+            OrderType::Sell => so.quote,
+        }
+    }
+
+    pub fn remove_stop_order(so: &StopOrder<T>) -> DispatchResult {
+        let ep_hash = Self::ensure_exchange_pair(so.base, so.quote)?;
+
+        <StopOrders<T>>::remove(so.hash);
+        <EPStopOrders<T>>::mutate(ep_hash, |hashes| hashes.retain(|&x| x != so.hash));
+        <TriggeredStopOrders<T>>::mutate(|hashes| hashes.retain(|&x| x != so.hash));
+        Self::close_stop_order(ep_hash, so);
+
+        Ok(())
+    }
+}
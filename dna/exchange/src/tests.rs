@@ -0,0 +1,1646 @@
+//! Tests of the DEX module.
+
+use super::*;
+use crate::mock::*;
+use sp_runtime::testing::TestSignature;
+use support::{assert_noop, assert_ok, traits::Currency};
+
+fn order(hash: primitives::H256) -> LimitOrder<Test> {
+    Exchange::order(hash).expect("order exists")
+}
+
+fn batch_auction_pair() -> primitives::H256 {
+    let ep_hash = ep_hash();
+    assert_ok!(Exchange::set_matching_mode(
+        Origin::ROOT,
+        ep_hash,
+        MatchingMode::BatchAuction
+    ));
+    ep_hash
+}
+
+#[test]
+fn batch_auction_orders_wait_outside_the_book() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 2, 50));
+        let bid = owned_order(BOB, 0);
+        let ask = owned_order(CHARLIE, 0);
+
+        // both sides at the same price, neither of them in a price level
+        assert_eq!(Exchange::batch_auction_orders(ep_hash), vec![bid, ask]);
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 100);
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, quote())), 50);
+
+        assert_ok!(Exchange::clear_batch_auction(ep_hash));
+
+        assert_eq!(order(bid).status, OrderStatus::Filled);
+        assert_eq!(order(ask).status, OrderStatus::Filled);
+        assert!(Exchange::batch_auction_orders(ep_hash).is_empty());
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 0);
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, quote())), 0);
+        assert_eq!(Assets::free_balance_of((BOB, quote())), SUPPLY / 3 + 50);
+        assert_eq!(Assets::free_balance_of((CHARLIE, base())), SUPPLY / 3 + 100);
+    });
+}
+
+#[test]
+fn batch_auction_leaves_an_uncrossed_book() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 3, 300));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 2, 50));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 4, 40));
+        let bid = owned_order(BOB, 0);
+        let ask = owned_order(CHARLIE, 0);
+        let far_ask = owned_order(CHARLIE, 1);
+
+        assert_ok!(Exchange::clear_batch_auction(ep_hash));
+
+        assert_eq!(order(ask).status, OrderStatus::Filled);
+        assert_eq!(order(bid).status, OrderStatus::PartialFilled);
+        assert_eq!(order(far_ask).status, OrderStatus::Pending);
+        assert!(Exchange::batch_auction_orders(ep_hash).is_empty());
+
+        // what is left of each side rests in a level of its own side
+        let bid_level = Exchange::linked_item(ep_hash, Some(3)).expect("bid level");
+        assert_eq!(bid_level.first_order, Some(bid));
+        assert_eq!(bid_level.order_count, 1);
+        assert_eq!(bid_level.sell_amount, order(bid).remained_sell_amount);
+
+        let ask_level = Exchange::linked_item(ep_hash, Some(4)).expect("ask level");
+        assert_eq!(ask_level.first_order, Some(far_ask));
+        assert_eq!(ask_level.order_count, 1);
+        assert_eq!(ask_level.sell_amount, 40);
+
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+        assert_eq!(
+            Assets::freezed_balance_of((BOB, base())),
+            order(bid).remained_sell_amount
+        );
+    });
+}
+
+#[test]
+fn waiting_batch_auction_order_can_be_canceled() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        let bid = owned_order(BOB, 0);
+
+        assert_noop!(
+            Exchange::cancel_order(Origin::signed(CHARLIE), bid),
+            Error::<Test>::CanOnlyCancelOwnOrder
+        );
+        assert_ok!(Exchange::cancel_order(Origin::signed(BOB), bid));
+
+        assert_eq!(order(bid).status, OrderStatus::Canceled);
+        assert!(Exchange::batch_auction_orders(ep_hash).is_empty());
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 0);
+        assert_eq!(Assets::free_balance_of((BOB, base())), SUPPLY / 3);
+    });
+}
+
+#[test]
+fn continuous_matching_takes_over_the_waiting_orders() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        let bid = owned_order(BOB, 0);
+
+        assert_ok!(Exchange::set_matching_mode(
+            Origin::ROOT,
+            ep_hash,
+            MatchingMode::Continuous
+        ));
+
+        assert!(Exchange::batch_auction_orders(ep_hash).is_empty());
+        assert!(Exchange::pending_batch_pairs().is_empty());
+        let level = Exchange::linked_item(ep_hash, Some(2)).expect("bid level");
+        assert_eq!(level.first_order, Some(bid));
+
+        // the next sell order matches it right away
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 2, 50));
+        assert_eq!(order(bid).status, OrderStatus::Filled);
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+    });
+}
+
+#[test]
+fn commitments_of_different_owners_do_not_collide() {
+    new_test_ext().execute_with(|| {
+        let salt = primitives::H256::repeat_byte(7);
+        let commitment =
+            Exchange::order_commitment_hash(&BOB, base(), quote(), OrderType::Buy, 2, 100, salt);
+
+        assert_ok!(Exchange::commit_order(Origin::signed(BOB), commitment, base(), 100));
+        assert_noop!(
+            Exchange::commit_order(Origin::signed(BOB), commitment, base(), 100),
+            Error::<Test>::CommitmentExisted
+        );
+        // copying the commitment of someone else does not block it
+        assert_ok!(Exchange::commit_order(Origin::signed(CHARLIE), commitment, base(), 100));
+        assert_eq!(
+            Exchange::commitments_by_expiry(11),
+            vec![(BOB, commitment), (CHARLIE, commitment)]
+        );
+
+        System::set_block_number(2);
+        assert_ok!(Exchange::reveal_order(
+            Origin::signed(BOB),
+            base(),
+            quote(),
+            OrderType::Buy,
+            2,
+            100,
+            salt
+        ));
+        assert!(Exchange::order_commitment(BOB, commitment).is_none());
+        assert_eq!(order(owned_order(BOB, 0)).sell_amount, 100);
+
+        assert!(Exchange::order_commitment(CHARLIE, commitment).is_some());
+        assert_noop!(
+            Exchange::reveal_order(
+                Origin::signed(CHARLIE),
+                base(),
+                quote(),
+                OrderType::Buy,
+                2,
+                100,
+                salt
+            ),
+            Error::<Test>::NoMatchingCommitment
+        );
+        assert_eq!(Exchange::commitments_by_expiry(11), vec![(CHARLIE, commitment)]);
+    });
+}
+
+#[test]
+fn unrevealed_commitment_penalty_is_burned() {
+    new_test_ext().execute_with(|| {
+        let supply = Assets::asset(base()).expect("genesis asset").total_supply;
+        let commitment = primitives::H256::repeat_byte(1);
+
+        assert_ok!(Exchange::commit_order(Origin::signed(CHARLIE), commitment, base(), 100));
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, base())), 100);
+
+        Exchange::expire_commitments(11);
+
+        assert!(Exchange::order_commitment(CHARLIE, commitment).is_none());
+        assert!(Exchange::commitments_by_expiry(11).is_empty());
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, base())), 0);
+        assert_eq!(Assets::free_balance_of((CHARLIE, base())), SUPPLY / 3 - 10);
+        assert_eq!(
+            Assets::asset(base()).expect("genesis asset").total_supply,
+            supply - 10
+        );
+        assert_eq!(
+            dex_events().last(),
+            Some(&RawEvent::CommitmentExpired(CHARLIE, commitment, 90, 10))
+        );
+    });
+}
+
+fn signed_order(
+    owner: u64,
+    otype: OrderType,
+    price: u128,
+    sell_amount: u128,
+) -> SignedOrder<Test> {
+    SignedOrder {
+        owner,
+        base: base(),
+        quote: quote(),
+        otype,
+        price,
+        sell_amount,
+        expires_at: 10,
+        salt: 0,
+    }
+}
+
+fn sign(order: &SignedOrder<Test>) -> TestSignature {
+    TestSignature(order.owner, Exchange::signed_order_payload(order))
+}
+
+#[test]
+fn signed_orders_are_signed_with_a_domain_separator() {
+    new_test_ext().execute_with(|| {
+        let maker = signed_order(BOB, OrderType::Sell, 2, 50);
+        let taker = signed_order(CHARLIE, OrderType::Buy, 2, 100);
+
+        let payload = Exchange::signed_order_payload(&maker);
+        assert_eq!(
+            payload,
+            (b"dna-signed-order".to_vec(), System::block_hash(0), maker.clone()).encode()
+        );
+
+        // a signature over the bare order is not accepted
+        let bare = TestSignature(BOB, maker.encode());
+        assert_noop!(
+            Exchange::settle_signed_orders(
+                Origin::signed(ALICE),
+                maker.clone(),
+                bare,
+                taker.clone(),
+                sign(&taker),
+                50
+            ),
+            Error::<Test>::InvalidOrderSignature
+        );
+
+        assert_ok!(Exchange::settle_signed_orders(
+            Origin::signed(ALICE),
+            maker.clone(),
+            sign(&maker),
+            taker.clone(),
+            sign(&taker),
+            50
+        ));
+        assert_eq!(Assets::free_balance_of((BOB, base())), SUPPLY / 3 + 100);
+        assert_eq!(Assets::free_balance_of((CHARLIE, quote())), SUPPLY / 3 + 50);
+        assert_eq!(
+            Exchange::signed_order_filled(Exchange::signed_order_hash(&maker)),
+            50
+        );
+    });
+}
+
+#[test]
+fn partly_filled_signed_orders_report_their_expiry() {
+    new_test_ext().execute_with(|| {
+        let maker = signed_order(BOB, OrderType::Sell, 2, 50);
+        let taker = signed_order(CHARLIE, OrderType::Buy, 2, 100);
+        let mut filled_maker = signed_order(BOB, OrderType::Sell, 2, 20);
+        filled_maker.salt = 1;
+        let (maker_hash, taker_hash) =
+            (Exchange::signed_order_hash(&maker), Exchange::signed_order_hash(&taker));
+        let filled_maker_hash = Exchange::signed_order_hash(&filled_maker);
+
+        assert_ok!(Exchange::settle_signed_orders(
+            Origin::signed(ALICE),
+            maker.clone(),
+            sign(&maker),
+            taker.clone(),
+            sign(&taker),
+            30
+        ));
+        assert_ok!(Exchange::settle_signed_orders(
+            Origin::signed(ALICE),
+            filled_maker.clone(),
+            sign(&filled_maker),
+            taker.clone(),
+            sign(&taker),
+            20
+        ));
+        assert_eq!(
+            Exchange::signed_orders_by_expiry(10),
+            vec![(BOB, maker_hash, 50), (CHARLIE, taker_hash, 50), (BOB, filled_maker_hash, 20)]
+        );
+
+        // the taker and the second maker are filled, a second taker is canceled after a fill
+        let mut canceled_taker = signed_order(CHARLIE, OrderType::Buy, 2, 100);
+        canceled_taker.salt = 1;
+        assert_ok!(Exchange::settle_signed_orders(
+            Origin::signed(ALICE),
+            maker.clone(),
+            sign(&maker),
+            canceled_taker.clone(),
+            sign(&canceled_taker),
+            10
+        ));
+        assert_ok!(Exchange::cancel_signed_order(Origin::signed(CHARLIE), canceled_taker.clone()));
+
+        Exchange::expire_signed_orders(10);
+
+        let expired: Vec<_> = dex_events()
+            .into_iter()
+            .filter(|e| match e {
+                RawEvent::SignedOrderExpired(..) => true,
+                _ => false,
+            })
+            .collect();
+        assert_eq!(expired, vec![RawEvent::SignedOrderExpired(BOB, maker_hash, 40, 10)]);
+        assert!(Exchange::signed_orders_by_expiry(10).is_empty());
+        assert_eq!(Exchange::signed_order_filled(maker_hash), 0);
+        assert_eq!(Exchange::signed_order_filled(taker_hash), 0);
+
+        System::set_block_number(11);
+        assert_noop!(
+            Exchange::settle_signed_orders(
+                Origin::signed(ALICE),
+                maker.clone(),
+                sign(&maker),
+                taker.clone(),
+                sign(&taker),
+                10
+            ),
+            Error::<Test>::SignedOrderExpired
+        );
+    });
+}
+
+#[test]
+fn fills_report_the_traded_amounts_of_both_orders() {
+    new_test_ext().execute_with(|| {
+        let (base, quote) = (base(), quote());
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base, quote, OrderType::Sell, 2, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 2, 8));
+        let (ask, bid) = (owned_order(ALICE, 0), owned_order(CHARLIE, 0));
+        let dex_hash = Exchange::order_owned_exchanges(ask, 0).expect("ask traded");
+
+        let events = dex_events();
+        assert!(events.contains(&RawEvent::OrderFilled(bid, dex_hash, 8, 4)));
+        assert!(events.contains(&RawEvent::OrderPartiallyFilled(ask, dex_hash, 8, 4, 6)));
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Buy, 2, 12));
+        let bid = owned_order(BOB, 0);
+        let dex_hash = Exchange::order_owned_exchanges(ask, 1).expect("ask traded");
+
+        let events = dex_events();
+        assert!(events.contains(&RawEvent::OrderFilled(bid, dex_hash, 12, 6)));
+        assert!(events.contains(&RawEvent::OrderFilled(ask, dex_hash, 12, 6)));
+        assert_eq!(order(ask).status, OrderStatus::Filled);
+    });
+}
+
+#[test]
+fn iceberg_slice_is_replenished_at_the_back_of_its_level() {
+    new_test_ext().execute_with(|| {
+        let (ep_hash, base, quote) = (ep_hash(), base(), quote());
+        assert_ok!(Exchange::create_iceberg_order(
+            Origin::signed(ALICE),
+            base,
+            quote,
+            OrderType::Sell,
+            2,
+            30,
+            10
+        ));
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, 2, 10));
+        let (iceberg, ask) = (owned_order(ALICE, 0), owned_order(BOB, 0));
+
+        // only the slice is shown, the whole order is frozen
+        let level = Exchange::linked_item(ep_hash, Some(2)).expect("ask level");
+        assert_eq!((level.sell_amount, level.buy_amount), (20, 40));
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 2), vec![iceberg, ask]);
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote)), 30);
+
+        // the slice is used up, its refill queues behind Bob's ask
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 2, 20));
+        assert_eq!(order(iceberg).remained_sell_amount, 20);
+        assert_eq!(order(iceberg).iceberg.expect("iceberg").visible_sell_amount, 10);
+        let level = Exchange::linked_item(ep_hash, Some(2)).expect("ask level");
+        assert_eq!((level.sell_amount, level.buy_amount), (20, 40));
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 2), vec![ask, iceberg]);
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote)), 20);
+
+        // so the next buy fills Bob first
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 2, 30));
+        assert_eq!(order(ask).status, OrderStatus::Filled);
+        assert_eq!(order(iceberg).remained_sell_amount, 15);
+        assert_eq!(order(iceberg).iceberg.expect("iceberg").visible_sell_amount, 5);
+        let level = Exchange::linked_item(ep_hash, Some(2)).expect("ask level");
+        assert_eq!((level.sell_amount, level.buy_amount), (5, 10));
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 2), vec![iceberg]);
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote)), 15);
+        assert_eq!(Assets::free_balance_of((ALICE, base)), SUPPLY - 2 * (SUPPLY / 3) + 30);
+    });
+}
+
+/// Bob's take-profit sell at 4 and stop-loss sell triggered at 2, returns the hashes of the
+/// group, the take-profit order and the stop order.
+fn take_profit_stop_loss() -> (primitives::H256, primitives::H256, primitives::H256) {
+    assert_ok!(Exchange::create_oco_orders(
+        Origin::signed(BOB),
+        base(),
+        quote(),
+        OrderType::Sell,
+        vec![(4, 50, None), (2, 50, Some(2))]
+    ));
+
+    let take_profit = owned_order(BOB, 0);
+    let group_hash = order(take_profit).group.expect("group member");
+    let group = Exchange::order_group(group_hash).expect("group exists");
+    assert_eq!(group.members.len(), 2);
+    let stop_loss = group.members[1];
+
+    (group_hash, take_profit, stop_loss)
+}
+
+#[test]
+fn order_group_stop_member_waits_for_its_trigger() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        let (group_hash, take_profit, stop_loss) = take_profit_stop_loss();
+
+        // the stop-loss is not in the book, so it does not sell below the market at once
+        let so = Exchange::stop_order(stop_loss).expect("stop order");
+        assert_eq!(so.trail, None);
+        assert_eq!(so.trigger_price, 2);
+        assert_eq!(so.group, Some(group_hash));
+        assert!(Exchange::linked_item(ep_hash, Some(2)).is_none());
+        assert!(Exchange::linked_item(ep_hash, Some(4)).is_some());
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 50);
+
+        // the take-profit fills and cancels the stop-loss
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 4, 200));
+
+        assert_eq!(order(take_profit).status, OrderStatus::Filled);
+        assert!(Exchange::stop_order(stop_loss).is_none());
+        assert!(Exchange::exchange_pair_stop_orders(ep_hash).is_empty());
+        assert!(Exchange::order_group(group_hash).is_none());
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 0);
+    });
+}
+
+#[test]
+fn triggered_order_group_stop_member_cancels_its_siblings_on_fill() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        let (group_hash, take_profit, stop_loss) = take_profit_stop_loss();
+
+        // a trade at 2 crosses the trigger
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 2, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 2, 20));
+        assert_eq!(Exchange::triggered_stop_orders(), vec![stop_loss]);
+
+        Exchange::execute_triggered_stop_orders();
+
+        assert!(Exchange::stop_order(stop_loss).is_none());
+        let placed = owned_order(BOB, 1);
+        assert_eq!(order(placed).price, 2);
+        assert_eq!(order(placed).group, Some(group_hash));
+        let group = Exchange::order_group(group_hash).expect("group exists");
+        assert_eq!(group.members, vec![take_profit, placed]);
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 50);
+
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 2, 100));
+
+        assert_eq!(order(placed).status, OrderStatus::Filled);
+        assert_eq!(order(take_profit).status, OrderStatus::Canceled);
+        assert!(Exchange::linked_item(ep_hash, Some(4)).is_none());
+        assert!(Exchange::order_group(group_hash).is_none());
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 0);
+    });
+}
+
+#[test]
+fn canceled_order_group_stop_member_leaves_the_group() {
+    new_test_ext().execute_with(|| {
+        let (group_hash, take_profit, stop_loss) = take_profit_stop_loss();
+
+        assert_ok!(Exchange::cancel_stop_order(Origin::signed(BOB), stop_loss));
+
+        assert!(Exchange::stop_order(stop_loss).is_none());
+        assert!(Exchange::order_group(group_hash).is_none());
+        assert_eq!(order(take_profit).group, None);
+        // the take-profit keeps the funds on its own
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 50);
+
+        assert_ok!(Exchange::cancel_order(Origin::signed(BOB), take_profit));
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 0);
+    });
+}
+
+/// Alice sells 10 quote to Charlie at `price`, which becomes the latest matched price.
+fn trade_at(price: u128) {
+    let (base, quote) = (base(), quote());
+    assert_ok!(Exchange::create_order(Origin::signed(ALICE), base, quote, OrderType::Sell, price, 10));
+    assert_ok!(Exchange::create_order(
+        Origin::signed(CHARLIE),
+        base,
+        quote,
+        OrderType::Buy,
+        price,
+        10 * price
+    ));
+}
+
+#[test]
+fn trailing_stop_order_follows_the_market_and_triggers() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+
+        assert_ok!(Exchange::create_trailing_stop_order(
+            Origin::signed(BOB),
+            base(),
+            quote(),
+            OrderType::Sell,
+            50,
+            TrailDistance::Absolute(2),
+            None
+        ));
+        let stop = Exchange::exchange_pair_stop_orders(ep_hash)[0];
+        assert_eq!(Exchange::stop_order_trigger_price(stop), Some(8));
+        assert_eq!(Exchange::owned_ep_stop_orders(BOB, ep_hash), vec![stop]);
+        assert_eq!(Balances::reserved_balance(&BOB), 10);
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 50);
+
+        trade_at(12);
+        assert_eq!(Exchange::stop_order_trigger_price(stop), Some(10));
+        trade_at(11);
+        assert_eq!(Exchange::stop_order_trigger_price(stop), Some(10));
+        assert!(Exchange::triggered_stop_orders().is_empty());
+
+        trade_at(10);
+        assert_eq!(Exchange::triggered_stop_orders(), vec![stop]);
+
+        Exchange::execute_triggered_stop_orders();
+
+        // placed at the trigger price since the book has no bid left
+        assert!(Exchange::stop_order(stop).is_none());
+        assert!(Exchange::owned_ep_stop_orders(BOB, ep_hash).is_empty());
+        let placed = order(owned_order(BOB, 0));
+        assert_eq!(placed.price, 10);
+        assert_eq!(placed.sell_amount, 50);
+        assert_eq!(Balances::reserved_balance(&BOB), 10);
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 50);
+    });
+}
+
+#[test]
+fn stop_orders_are_bounded() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+
+        let stop = |who| {
+            Exchange::create_trailing_stop_order(
+                Origin::signed(who),
+                base(),
+                quote(),
+                OrderType::Sell,
+                10,
+                TrailDistance::Absolute(1),
+                None,
+            )
+        };
+
+        for _ in 0..4 {
+            assert_ok!(stop(BOB));
+        }
+        assert_eq!(Balances::reserved_balance(&BOB), 40);
+
+        // the stop orders count as open orders of their owner
+        assert_ok!(Exchange::ensure_open_orders_limit(&BOB, ep_hash, 16));
+        assert_eq!(
+            Exchange::ensure_open_orders_limit(&BOB, ep_hash, 17),
+            Err(Error::<Test>::TooManyOpenOrders.into())
+        );
+
+        // the pair holds at most `StopOrdersCap` of them
+        assert_noop!(stop(CHARLIE), Error::<Test>::TooManyStopOrders);
+        assert_noop!(
+            Exchange::create_oco_orders(
+                Origin::signed(CHARLIE),
+                base(),
+                quote(),
+                OrderType::Sell,
+                vec![(12, 10, None), (8, 10, Some(8))]
+            ),
+            Error::<Test>::TooManyStopOrders
+        );
+
+        let canceled = Exchange::owned_ep_stop_orders(BOB, ep_hash)[0];
+        assert_ok!(Exchange::cancel_stop_order(Origin::signed(BOB), canceled));
+        assert_eq!(Balances::reserved_balance(&BOB), 30);
+        assert_eq!(Exchange::owned_ep_stop_orders(BOB, ep_hash).len(), 3);
+
+        assert_ok!(stop(CHARLIE));
+        assert_eq!(Balances::reserved_balance(&CHARLIE), 10);
+    });
+}
+
+#[test]
+fn stop_orders_are_bounds_checked_like_limit_orders() {
+    new_test_ext().execute_with(|| {
+        trade_at(10);
+
+        // the trail puts the trigger price at zero
+        assert_noop!(
+            Exchange::create_trailing_stop_order(
+                Origin::signed(BOB),
+                base(),
+                quote(),
+                OrderType::Sell,
+                10,
+                TrailDistance::Absolute(10),
+                None
+            ),
+            Error::<Test>::BoundsCheckFailed
+        );
+        // 10 base do not buy a whole number of quote at 3
+        assert_noop!(
+            Exchange::create_trailing_stop_order(
+                Origin::signed(BOB),
+                base(),
+                quote(),
+                OrderType::Buy,
+                10,
+                TrailDistance::Absolute(1),
+                Some(3)
+            ),
+            Error::<Test>::BoundsCheckFailed
+        );
+    });
+}
+
+#[test]
+fn open_orders_are_limited_and_their_deposits_refunded_on_cancel_and_fill() {
+    new_test_ext().execute_with(|| {
+        let (ep_hash, base, quote) = (ep_hash(), base(), quote());
+        let open_orders = |who| Exchange::owned_ep_opened_orders(who, ep_hash).unwrap_or_default();
+        for price in 100..120 {
+            assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, price, 10));
+        }
+        assert_eq!(open_orders(BOB).len(), 20);
+        assert_eq!(Balances::reserved_balance(&BOB), 20 * OrderDeposit::get());
+
+        assert_noop!(
+            Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, 120, 10),
+            Error::<Test>::TooManyOpenOrders
+        );
+
+        assert_ok!(Exchange::cancel_order(Origin::signed(BOB), owned_order(BOB, 0)));
+        assert_eq!(Balances::reserved_balance(&BOB), 19 * OrderDeposit::get());
+
+        // Charlie's order is filled as it is placed, so its deposit is refunded right away
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 101, 1_010));
+        assert_eq!(order(owned_order(BOB, 1)).status, OrderStatus::Filled);
+        assert_eq!(Balances::reserved_balance(&BOB), 18 * OrderDeposit::get());
+        assert_eq!(Balances::free_balance(&BOB), ENDOWMENT - 18 * OrderDeposit::get());
+        assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+        assert_eq!(Balances::free_balance(&CHARLIE), ENDOWMENT);
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, 120, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, 121, 10));
+        assert_eq!(open_orders(BOB).len(), 20);
+    });
+}
+
+#[test]
+fn order_history_is_complete_and_paginated() {
+    new_test_ext().execute_with(|| {
+        let (ep_hash, base, quote) = (ep_hash(), base(), quote());
+        let place = |price| {
+            assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, price, 10));
+        };
+        place(100);
+        place(101);
+        System::set_block_number(2);
+        place(102);
+        System::set_block_number(3);
+        place(103);
+        let orders: Vec<_> = (0..4).map(|index| owned_order(BOB, index)).collect();
+
+        assert_ok!(Exchange::cancel_order(Origin::signed(BOB), orders[1]));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 100, 1_000));
+        assert_eq!(Exchange::account_open_orders(BOB, ep_hash), vec![orders[3], orders[2]]);
+
+        let history = |status, from_block, to_block, start, limit| {
+            Exchange::account_order_history(BOB, ep_hash, status, from_block, to_block, start, limit)
+        };
+        assert_eq!(history(None, None, None, 0, 2), (vec![orders[0], orders[1]], Some(2)));
+        assert_eq!(history(None, None, None, 2, 2), (vec![orders[2], orders[3]], None));
+        assert_eq!(history(None, None, None, 4, 2), (vec![], None));
+
+        // following the pages one order at a time lists the whole history
+        let mut listed = vec![];
+        let mut start = Some(0);
+        while let Some(index) = start {
+            let (page, next) = history(None, None, None, index, 1);
+            listed.extend(page);
+            start = next;
+        }
+        assert_eq!(listed, orders);
+
+        assert_eq!(history(Some(OrderStatus::Filled), None, None, 0, 10), (vec![orders[0]], None));
+        assert_eq!(history(Some(OrderStatus::Canceled), None, None, 0, 10), (vec![orders[1]], None));
+        assert_eq!(
+            history(Some(OrderStatus::Pending), None, None, 0, 1),
+            (vec![orders[2]], Some(3))
+        );
+        assert_eq!(history(None, Some(2), Some(2), 0, 10), (vec![orders[2]], None));
+        assert_eq!(history(None, Some(3), None, 0, 10), (vec![orders[3]], None));
+        assert_eq!(history(None, None, Some(1), 1, 10), (vec![orders[1]], None));
+    });
+}
+
+#[test]
+fn orders_keyed_by_hash_alone_are_moved_to_their_exchange_pair() {
+    use sp_runtime::traits::OnRuntimeUpgrade;
+    use support::storage::migration::{get_storage_value, put_storage_value};
+
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 5, 10));
+        let hash = owned_order(ALICE, 0);
+        let placed = order(hash);
+
+        // the order as it was stored before `V3_0_0`
+        let key = hash.using_encoded(Blake2_256::hash);
+        let index_key = (ep_hash, 0u64).using_encoded(Blake2_256::hash);
+        <Orders<Test>>::remove(ep_hash, hash);
+        <OrderPairs<Test>>::remove(hash);
+        <ExchangePairOwnedOrders<Test>>::remove(ep_hash, 0);
+        put_storage_value(b"ExchangeStorage", b"Orders", &key, placed.clone());
+        put_storage_value(b"ExchangeStorage", b"ExchangePairOwnedOrders", &index_key, hash);
+        StorageVersion::put(Releases::V2_0_0);
+        assert!(Exchange::order(hash).is_none());
+
+        Exchange::on_runtime_upgrade();
+
+        assert_eq!(Exchange::storage_version(), Releases::LATEST);
+        assert_eq!(Exchange::exchange_pair_owned_order(ep_hash, 0), Some(hash));
+        assert_eq!(Exchange::order_pair(hash), Some(ep_hash));
+        assert_eq!(Exchange::pair_order(ep_hash, hash), Some(placed.clone()));
+        assert_eq!(Exchange::order(hash), Some(placed));
+        let legacy = get_storage_value::<LimitOrder<Test>>(b"ExchangeStorage", b"Orders", &key);
+        assert!(legacy.is_none());
+
+        // and can still be canceled
+        assert_ok!(Exchange::cancel_order(Origin::signed(ALICE), hash));
+        assert_eq!(order(hash).status, OrderStatus::Canceled);
+    });
+}
+
+/// Balance, free and frozen balance of every account in `BASE` and `QUOTE` after the same
+/// payments between frozen balances, netted by a `Settlement` or made one transfer at a time.
+fn balances_after_payments(netted: bool) -> Vec<(u128, u128, u128)> {
+    new_test_ext().execute_with(|| {
+        let (base, quote) = (base(), quote());
+        assert_ok!(Assets::freeze(ALICE, quote, 100));
+        assert_ok!(Assets::freeze(BOB, quote, 50));
+        assert_ok!(Assets::freeze(CHARLIE, base, 200));
+
+        // (payer, asset, payee or `None` to release the rest, amount)
+        let payments = vec![
+            (ALICE, quote, Some(CHARLIE), 40),
+            (CHARLIE, base, Some(ALICE), 80),
+            (ALICE, quote, Some(BOB), 30),
+            (BOB, quote, Some(ALICE), 20),
+            (CHARLIE, base, Some(BOB), 60),
+            (ALICE, quote, None, 30),
+            (CHARLIE, base, None, 60),
+        ];
+        if netted {
+            let mut settlement = Settlement::<Test>::default();
+            for (from, asset, to, amount) in payments {
+                let result = match to {
+                    Some(to) => settlement.pay_frozen(&from, asset, &to, amount),
+                    None => settlement.release(&from, asset, amount),
+                };
+                assert_ok!(result);
+            }
+            assert_ok!(settlement.apply());
+        } else {
+            for (from, asset, to, amount) in payments {
+                assert_ok!(Assets::unfreeze(from, asset, amount));
+                if let Some(to) = to {
+                    assert_ok!(Assets::transfer(from, asset, to, amount));
+                }
+            }
+        }
+
+        let mut balances = vec![];
+        for who in &[ALICE, BOB, CHARLIE] {
+            for asset in &[base, quote] {
+                let key = (*who, *asset);
+                balances.push((
+                    Assets::balance_of(key),
+                    Assets::free_balance_of(key),
+                    Assets::freezed_balance_of(key),
+                ));
+            }
+        }
+        balances
+    })
+}
+
+#[test]
+fn netted_settlement_leaves_the_balances_of_one_transfer_at_a_time() {
+    let netted = balances_after_payments(true);
+    assert_eq!(netted, balances_after_payments(false));
+
+    let (alice, others) = (SUPPLY - 2 * (SUPPLY / 3), SUPPLY / 3);
+    assert_eq!(
+        netted,
+        vec![
+            // Alice: base, quote
+            (alice + 80, alice + 80, 0),
+            (alice - 50, alice - 50, 0),
+            // Bob
+            (others + 60, others + 60, 0),
+            (others + 10, others - 20, 30),
+            // Charlie
+            (others - 140, others - 140, 0),
+            (others + 40, others + 40, 0),
+        ]
+    );
+}
+
+#[test]
+fn stop_order_failing_on_placement_is_rejected_with_its_funds_released() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+
+        assert_ok!(Exchange::create_trailing_stop_order(
+            Origin::signed(BOB),
+            base(),
+            quote(),
+            OrderType::Buy,
+            260,
+            TrailDistance::Absolute(3),
+            None
+        ));
+        let stop = Exchange::exchange_pair_stop_orders(ep_hash)[0];
+
+        trade_at(13);
+        assert_eq!(Exchange::triggered_stop_orders(), vec![stop]);
+
+        // 260 does not buy a whole amount at the best ask of 12
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 12, 10));
+        let alice_frozen = Assets::freezed_balance_of((ALICE, quote()));
+        Exchange::execute_triggered_stop_orders();
+
+        assert!(Exchange::stop_order(stop).is_none());
+        assert!(Exchange::owned_ep_stop_orders(BOB, ep_hash).is_empty());
+        assert_eq!(Exchange::owned_orders_index(BOB), 0);
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 0);
+        assert_eq!(Assets::free_balance_of((BOB, base())), SUPPLY / 3);
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote())), alice_frozen);
+        assert_eq!(Exchange::linked_item(ep_hash, Some(12)).expect("ask level").sell_amount, 10);
+        assert_eq!(
+            dex_events().last(),
+            Some(&RawEvent::OrderRejected(
+                BOB,
+                stop,
+                Error::<Test>::BoundsCheckFailed.into()
+            ))
+        );
+    });
+}
+
+/// Take `amount` off the frozen balance of an account behind the exchange's back, the next
+/// unfreeze or settlement of the whole balance fails.
+fn take_frozen(who: u64, asset: primitives::H256, amount: u128) {
+    assets::FreezedBalanceOf::<Test>::mutate((who, asset), |frozen| *frozen -= amount);
+}
+
+#[test]
+fn cancel_failing_midway_leaves_the_order_in_the_book() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        let bid = owned_order(BOB, 0);
+
+        // the order is taken off its level before its funds fail to unfreeze
+        take_frozen(BOB, base(), 1);
+        assert_noop!(
+            Exchange::cancel_order(Origin::signed(BOB), bid),
+            assets::Error::<Test>::BalanceNotEnough
+        );
+
+        assert_eq!(order(bid).status, OrderStatus::Pending);
+        let level = Exchange::linked_item(ep_hash, Some(2)).expect("bid level");
+        assert_eq!(level.first_order, Some(bid));
+        assert_eq!(level.sell_amount, 100);
+        assert_eq!(Exchange::owned_ep_opened_orders(BOB, ep_hash), Some(vec![bid]));
+        assert_eq!(Balances::reserved_balance(&BOB), 10);
+    });
+}
+
+#[test]
+fn reveal_failing_to_place_the_order_keeps_the_commitment() {
+    new_test_ext().execute_with(|| {
+        // no exchange pair trades against this quote asset
+        let quote = primitives::H256::repeat_byte(9);
+        let salt = primitives::H256::repeat_byte(7);
+        let commitment =
+            Exchange::order_commitment_hash(&BOB, base(), quote, OrderType::Buy, 2, 100, salt);
+        assert_ok!(Exchange::commit_order(Origin::signed(BOB), commitment, base(), 100));
+
+        // the commitment is removed and its collateral unfrozen before the order fails
+        System::set_block_number(2);
+        assert_noop!(
+            Exchange::reveal_order(Origin::signed(BOB), base(), quote, OrderType::Buy, 2, 100, salt),
+            Error::<Test>::NoMatchingExchangePair
+        );
+
+        assert!(Exchange::order_commitment(BOB, commitment).is_some());
+        assert_eq!(Exchange::commitments_by_expiry(11), vec![(BOB, commitment)]);
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 100);
+        assert_eq!(Exchange::owned_orders_index(BOB), 0);
+    });
+}
+
+#[test]
+fn signed_order_settlement_failing_midway_fills_neither_order() {
+    new_test_ext().execute_with(|| {
+        // Dave signs a buy order without holding any base asset
+        const DAVE: u64 = 4;
+        let maker = signed_order(BOB, OrderType::Sell, 2, 50);
+        let taker = signed_order(DAVE, OrderType::Buy, 2, 100);
+
+        // both orders are filled before Dave fails to pay
+        assert_noop!(
+            Exchange::settle_signed_orders(
+                Origin::signed(ALICE),
+                maker.clone(),
+                sign(&maker),
+                taker.clone(),
+                sign(&taker),
+                50
+            ),
+            assets::Error::<Test>::SenderHaveNoAsset
+        );
+
+        assert_eq!(Exchange::signed_order_filled(Exchange::signed_order_hash(&maker)), 0);
+        assert_eq!(Exchange::signed_order_filled(Exchange::signed_order_hash(&taker)), 0);
+        assert_eq!(Assets::free_balance_of((BOB, quote())), SUPPLY / 3);
+    });
+}
+
+#[test]
+fn batch_clearing_failing_midway_keeps_the_orders_waiting() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 2, 50));
+        let bid = owned_order(BOB, 0);
+        let ask = owned_order(CHARLIE, 0);
+
+        // the orders are filled before Charlie's side fails to settle
+        take_frozen(CHARLIE, quote(), 1);
+        assert_noop!(
+            Exchange::clear_batch_auction(ep_hash),
+            assets::Error::<Test>::BalanceNotEnough
+        );
+        // switching to continuous matching clears the waiting orders first
+        assert_noop!(
+            Exchange::set_matching_mode(Origin::ROOT, ep_hash, MatchingMode::Continuous),
+            assets::Error::<Test>::BalanceNotEnough
+        );
+
+        assert_eq!(order(bid).status, OrderStatus::Pending);
+        assert_eq!(order(ask).status, OrderStatus::Pending);
+        assert_eq!(Exchange::batch_auction_orders(ep_hash), vec![bid, ask]);
+        assert_eq!(Exchange::exchange_pair_owned_exchanges_index(ep_hash), 0);
+        assert_eq!(
+            Exchange::exchange_pair(ep_hash).expect("exchange pair").matching_mode,
+            MatchingMode::BatchAuction
+        );
+    });
+}
+
+fn set_price_band(ep_hash: primitives::H256, band_bps: u32) {
+    assert_ok!(Exchange::set_circuit_breaker(
+        Origin::ROOT,
+        Some(ep_hash),
+        Some(CircuitBreaker {
+            band_bps,
+            halt_move_bps: 0,
+            window: 0,
+            cooldown: 0,
+        })
+    ));
+}
+
+#[test]
+fn order_failing_mid_match_leaves_no_trace() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+        set_price_band(ep_hash, 1_000);
+
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 10, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 12, 10));
+        let alice_frozen = Assets::freezed_balance_of((ALICE, quote()));
+        let charlie_base = Assets::free_balance_of((CHARLIE, base()));
+
+        // the ask at 10 is filled before the one at 12 turns out to be outside the band
+        assert_noop!(
+            Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 12, 240),
+            Error::<Test>::PriceOutsideBand
+        );
+
+        assert_eq!(Assets::free_balance_of((CHARLIE, base())), charlie_base);
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, base())), 0);
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote())), alice_frozen);
+        assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+        assert_eq!(Exchange::linked_item(ep_hash, Some(10)).expect("ask level").sell_amount, 10);
+        assert_eq!(Exchange::linked_item(ep_hash, Some(12)).expect("ask level").sell_amount, 10);
+    });
+}
+
+#[test]
+fn stop_order_of_a_halted_pair_is_rejected_with_its_funds_released() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+
+        assert_ok!(Exchange::create_trailing_stop_order(
+            Origin::signed(BOB),
+            base(),
+            quote(),
+            OrderType::Sell,
+            50,
+            TrailDistance::Absolute(2),
+            None
+        ));
+        let stop = Exchange::exchange_pair_stop_orders(ep_hash)[0];
+
+        trade_at(8);
+        assert_eq!(Exchange::triggered_stop_orders(), vec![stop]);
+
+        // the pair halts before the stop order is placed
+        <HaltedPairs<Test>>::put(vec![(ep_hash, 100)]);
+        Exchange::execute_triggered_stop_orders();
+
+        assert!(Exchange::stop_order(stop).is_none());
+        assert!(Exchange::owned_ep_stop_orders(BOB, ep_hash).is_empty());
+        assert_eq!(Exchange::owned_orders_index(BOB), 0);
+        assert_eq!(Assets::freezed_balance_of((BOB, quote())), 0);
+        assert_eq!(Assets::free_balance_of((BOB, quote())), SUPPLY / 3);
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_eq!(
+            dex_events().last(),
+            Some(&RawEvent::OrderRejected(
+                BOB,
+                stop,
+                Error::<Test>::ExchangePairHalted.into()
+            ))
+        );
+    });
+}
+
+#[test]
+fn stop_order_failing_mid_match_is_rejected_and_the_book_is_unchanged() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+
+        assert_ok!(Exchange::create_trailing_stop_order(
+            Origin::signed(BOB),
+            base(),
+            quote(),
+            OrderType::Buy,
+            260,
+            TrailDistance::Absolute(1),
+            Some(13)
+        ));
+        let stop = Exchange::exchange_pair_stop_orders(ep_hash)[0];
+
+        trade_at(11);
+        assert_eq!(Exchange::triggered_stop_orders(), vec![stop]);
+
+        set_price_band(ep_hash, 1_000);
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 11, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 13, 10));
+        let alice_frozen = Assets::freezed_balance_of((ALICE, quote()));
+
+        // the ask at 11 is filled before the one at 13 turns out to be outside the band
+        Exchange::execute_triggered_stop_orders();
+
+        assert!(Exchange::stop_order(stop).is_none());
+        assert_eq!(Exchange::owned_orders_index(BOB), 0);
+        assert_eq!(Assets::freezed_balance_of((BOB, base())), 0);
+        assert_eq!(Assets::free_balance_of((BOB, base())), SUPPLY / 3);
+        assert_eq!(Assets::free_balance_of((BOB, quote())), SUPPLY / 3);
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+
+        assert_eq!(Assets::freezed_balance_of((ALICE, quote())), alice_frozen);
+        assert_eq!(Exchange::linked_item(ep_hash, Some(11)).expect("ask level").sell_amount, 10);
+        assert_eq!(Exchange::linked_item(ep_hash, Some(13)).expect("ask level").sell_amount, 10);
+        assert_eq!(
+            dex_events().last(),
+            Some(&RawEvent::OrderRejected(
+                BOB,
+                stop,
+                Error::<Test>::PriceOutsideBand.into()
+            ))
+        );
+    });
+
+#[test]
+fn matching_mode_stays_while_batch_auction_orders_wait_out_a_halt() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = batch_auction_pair();
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 2, 100));
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Sell, 2, 50));
+
+        // a halted pair is cleared once the halt is over
+        <HaltedPairs<Test>>::put(vec![(ep_hash, 100)]);
+        assert_noop!(
+            Exchange::set_matching_mode(Origin::ROOT, ep_hash, MatchingMode::Continuous),
+            Error::<Test>::BatchAuctionPending
+        );
+        assert_eq!(Exchange::batch_auction_orders(ep_hash).len(), 2);
+    });
+}
+
+
+fn set_halt(ep_hash: primitives::H256, halt_move_bps: u32, window: u64, cooldown: u64) {
+    assert_ok!(Exchange::set_circuit_breaker(
+        Origin::ROOT,
+        Some(ep_hash),
+        Some(CircuitBreaker {
+            band_bps: 0,
+            halt_move_bps,
+            window,
+            cooldown,
+        })
+    ));
+}
+
+#[test]
+fn trades_outside_the_price_band_are_rejected() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        set_price_band(ep_hash, 1_000);
+
+        // a pair without a trade has no band yet
+        trade_at(20);
+
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 23, 10));
+        assert_noop!(
+            Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 23, 230),
+            Error::<Test>::PriceOutsideBand
+        );
+
+        // the edge of the band is inside it
+        trade_at(22);
+        assert_eq!(
+            Exchange::exchange_pair(ep_hash).expect("exchange pair").latest_matched_price,
+            Some(22)
+        );
+
+        // the band moved with the latest matched price
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 23, 230));
+        assert!(Exchange::linked_item(ep_hash, Some(23)).is_none());
+    });
+}
+
+#[test]
+fn price_move_halts_the_pair_and_cancels_the_rest_of_the_taker() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        set_halt(ep_hash, 1_000, 10, 5);
+        trade_at(10);
+        assert_eq!(Exchange::price_window(ep_hash), Some((1, 10)));
+
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 12, 10));
+        assert_ok!(Exchange::create_order(Origin::signed(ALICE), base(), quote(), OrderType::Sell, 13, 10));
+        let ask = owned_order(ALICE, 2);
+        let charlie_base = Assets::free_balance_of((CHARLIE, base()));
+
+        // the trade at 12 moves the price by 20% and halts the pair
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base(), quote(), OrderType::Buy, 13, 260));
+        let bid = owned_order(CHARLIE, 1);
+
+        assert!(Exchange::is_pair_halted(ep_hash));
+        assert!(dex_events().contains(&RawEvent::ExchangePairHalted(ep_hash, 6, 10, 12)));
+
+        // the rest of the bid is canceled instead of joining the ask level at 13
+        assert_eq!(order(bid).status, OrderStatus::Canceled);
+        assert_eq!(order(bid).remained_sell_amount, 140);
+        assert_eq!(dex_events().last(), Some(&RawEvent::OrderCanceled(CHARLIE, bid)));
+        let level = Exchange::linked_item(ep_hash, Some(13)).expect("ask level");
+        assert_eq!(level.first_order, Some(ask));
+        assert_eq!(level.order_count, 1);
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, base())), 0);
+        assert_eq!(Assets::free_balance_of((CHARLIE, base())), charlie_base - 120);
+        assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+
+        assert_noop!(
+            Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 13, 130),
+            Error::<Test>::ExchangePairHalted
+        );
+    });
+}
+
+#[test]
+fn halted_pair_resumes_after_its_cooldown() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        set_halt(ep_hash, 1_000, 10, 5);
+        trade_at(10);
+        trade_at(12);
+        assert_eq!(Exchange::halted_pairs(), vec![(ep_hash, 6)]);
+
+        Exchange::resume_halted_pairs(4);
+        assert_eq!(Exchange::halted_pairs(), vec![(ep_hash, 6)]);
+        System::set_block_number(5);
+        assert_noop!(
+            Exchange::create_order(Origin::signed(BOB), base(), quote(), OrderType::Buy, 12, 120),
+            Error::<Test>::ExchangePairHalted
+        );
+
+        // lifted at the end of the block before the one the halt ends in
+        Exchange::resume_halted_pairs(5);
+        assert!(Exchange::halted_pairs().is_empty());
+        assert_eq!(dex_events().last(), Some(&RawEvent::ExchangePairResumed(ep_hash)));
+
+        // the next trade starts a new window
+        System::set_block_number(6);
+        trade_at(11);
+        assert_eq!(Exchange::price_window(ep_hash), Some((6, 11)));
+    });
+}
+
+#[test]
+fn governance_lifts_a_halt_before_its_cooldown_is_over() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        assert_noop!(
+            Exchange::resume_exchange_pair(Origin::ROOT, ep_hash),
+            Error::<Test>::ExchangePairNotHalted
+        );
+
+        set_halt(ep_hash, 1_000, 10, 5);
+        trade_at(10);
+        trade_at(12);
+        assert!(Exchange::is_pair_halted(ep_hash));
+
+        assert_ok!(Exchange::resume_exchange_pair(Origin::ROOT, ep_hash));
+
+        assert!(!Exchange::is_pair_halted(ep_hash));
+        assert_eq!(dex_events().last(), Some(&RawEvent::ExchangePairResumed(ep_hash)));
+        trade_at(12);
+    });
+}
+
+type Levels = OrderLinkedItemList<Test>;
+
+fn add_level(ep_hash: primitives::H256, otype: OrderType, price: u128) {
+    let hash = primitives::H256::from_low_u64_be(price as u64);
+    Levels::append(ep_hash, price, hash, 1, 1, otype);
+}
+
+fn remove_level(ep_hash: primitives::H256, price: u128) {
+    let hash = primitives::H256::from_low_u64_be(price as u64);
+    assert_ok!(Levels::remove_order(ep_hash, price, hash, 1, 1));
+}
+
+/// Prices of the book from the lowest bid to the highest ask, `None` between the sides.
+fn book_prices(ep_hash: primitives::H256) -> Vec<Option<u128>> {
+    let mut prices = Vec::new();
+    let mut price = Levels::read_bottom(ep_hash).next;
+    while price != Some(u128::max_value()) {
+        prices.push(price);
+        price = Levels::read(ep_hash, price).next;
+    }
+    prices
+}
+
+fn buckets(ep_hash: primitives::H256, otype: OrderType) -> Vec<Vec<u128>> {
+    let mut bounds = Exchange::price_level_index(ep_hash, (otype, None));
+    if bounds.is_empty() {
+        bounds.push(0);
+    }
+    bounds
+        .into_iter()
+        .map(|bound| Exchange::price_level_index(ep_hash, (otype, Some(bound))))
+        .collect()
+}
+
+/// The index of a side holds exactly `levels`, in buckets of at most the capacity that
+/// each start at or above their bound, and only the first bucket may be empty.
+fn assert_index(ep_hash: primitives::H256, otype: OrderType, levels: &[u128]) {
+    let bounds = Exchange::price_level_index(ep_hash, (otype, None));
+    let buckets = buckets(ep_hash, otype);
+
+    assert_eq!(buckets.concat(), levels);
+    assert_ne!(bounds.len(), 1);
+    for (position, bucket) in buckets.iter().enumerate() {
+        assert!(bucket.len() <= PRICE_BUCKET_CAPACITY);
+        if position > 0 {
+            assert!(!bucket.is_empty());
+            assert!(bucket[0] >= bounds[position]);
+        }
+    }
+}
+
+#[test]
+fn price_level_index_splits_a_full_bucket() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        for price in 1..=64 {
+            add_level(ep_hash, OrderType::Buy, price);
+        }
+        assert_eq!(buckets(ep_hash, OrderType::Buy), vec![(1..=64).collect::<Vec<_>>()]);
+
+        add_level(ep_hash, OrderType::Buy, 65);
+
+        assert_eq!(
+            buckets(ep_hash, OrderType::Buy),
+            vec![(1..=32).collect::<Vec<_>>(), (33..=65).collect()]
+        );
+        assert_eq!(Exchange::price_level_index(ep_hash, (OrderType::Buy, None)), vec![0, 33]);
+
+        let mut prices: Vec<_> = (1..=65).map(Some).collect();
+        prices.push(None);
+        assert_eq!(book_prices(ep_hash), prices);
+    });
+}
+
+#[test]
+fn price_level_index_merges_buckets_fitting_in_half_a_bucket() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        for price in 1..=65 {
+            add_level(ep_hash, OrderType::Buy, price);
+        }
+        for price in 1..=30 {
+            remove_level(ep_hash, price);
+        }
+        remove_level(ep_hash, 34);
+        remove_level(ep_hash, 35);
+
+        // 2 and 31 levels are left
+        assert_eq!(buckets(ep_hash, OrderType::Buy).len(), 2);
+
+        remove_level(ep_hash, 36);
+
+        let levels: Vec<u128> = [31, 32, 33].iter().cloned().chain(37..=65).collect();
+        assert_eq!(buckets(ep_hash, OrderType::Buy), vec![levels.clone()]);
+        assert!(!PriceLevelIndex::<Test>::contains_key(ep_hash, (OrderType::Buy, None)));
+        assert!(!PriceLevelIndex::<Test>::contains_key(ep_hash, (OrderType::Buy, Some(33))));
+
+        let mut prices: Vec<_> = levels.into_iter().map(Some).collect();
+        prices.push(None);
+        assert_eq!(book_prices(ep_hash), prices);
+    });
+}
+
+#[test]
+fn price_level_index_keeps_thousands_of_levels_in_order() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        // every price once, in an order scattered over the whole range
+        let bids: Vec<u128> = (0..1500).map(|i| 1 + i * 7919 % 1500).collect();
+        let asks: Vec<u128> = bids.iter().map(|price| price + 2000).collect();
+
+        for (&bid, &ask) in bids.iter().zip(asks.iter()) {
+            add_level(ep_hash, OrderType::Buy, bid);
+            add_level(ep_hash, OrderType::Sell, ask);
+        }
+
+        let assert_book = |bids: &[u128], asks: &[u128]| {
+            let (mut bids, mut asks) = (bids.to_vec(), asks.to_vec());
+            bids.sort();
+            asks.sort();
+            assert_index(ep_hash, OrderType::Buy, &bids);
+            assert_index(ep_hash, OrderType::Sell, &asks);
+
+            let mut prices: Vec<_> = bids.into_iter().map(Some).collect();
+            prices.push(None);
+            prices.extend(asks.into_iter().map(Some));
+            assert_eq!(book_prices(ep_hash), prices);
+        };
+        assert_book(&bids, &asks);
+        assert!(buckets(ep_hash, OrderType::Buy).len() >= 1500 / PRICE_BUCKET_CAPACITY);
+
+        // drain every other level, then the rest
+        for (&bid, &ask) in bids.iter().zip(asks.iter()).step_by(2) {
+            remove_level(ep_hash, bid);
+            remove_level(ep_hash, ask);
+        }
+        let left = |levels: &[u128]| levels.iter().skip(1).step_by(2).cloned().collect::<Vec<_>>();
+        assert_book(&left(&bids), &left(&asks));
+
+        for (&bid, &ask) in bids.iter().zip(asks.iter()).skip(1).step_by(2) {
+            remove_level(ep_hash, bid);
+            remove_level(ep_hash, ask);
+        }
+        assert_book(&[], &[]);
+        assert_eq!(buckets(ep_hash, OrderType::Buy), vec![Vec::<u128>::new()]);
+        assert_eq!(buckets(ep_hash, OrderType::Sell), vec![Vec::<u128>::new()]);
+    });
+}
+
+#[test]
+fn orders_of_a_price_level_are_filled_first_in_first_out() {
+    new_test_ext().execute_with(|| {
+        let (ep_hash, base, quote) = (ep_hash(), base(), quote());
+        for who in &[BOB, CHARLIE, ALICE] {
+            assert_ok!(Exchange::create_order(Origin::signed(*who), base, quote, OrderType::Sell, 5, 10));
+        }
+        let (bob, charlie) = (owned_order(BOB, 0), owned_order(CHARLIE, 0));
+        let alice = owned_order(ALICE, 0);
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 5), vec![bob, charlie, alice]);
+
+        // a canceled order leaves the queue from the middle
+        assert_ok!(Exchange::cancel_order(Origin::signed(CHARLIE), charlie));
+        let level = Exchange::linked_item(ep_hash, Some(5)).expect("ask level");
+        assert_eq!(
+            (level.first_order, level.last_order, level.order_count),
+            (Some(bob), Some(alice), 2)
+        );
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 5), vec![bob, alice]);
+
+        // a partly filled order keeps its place at the front
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 5, 25));
+        assert_eq!(order(bob).remained_sell_amount, 5);
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 5), vec![bob, alice]);
+
+        assert_ok!(Exchange::create_order(Origin::signed(CHARLIE), base, quote, OrderType::Buy, 5, 50));
+        assert_eq!(order(bob).status, OrderStatus::Filled);
+        assert_eq!(order(alice).remained_sell_amount, 5);
+
+        assert_ok!(Exchange::create_order(Origin::signed(BOB), base, quote, OrderType::Sell, 5, 10));
+        let later = owned_order(BOB, 1);
+        let level = Exchange::linked_item(ep_hash, Some(5)).expect("ask level");
+        assert_eq!(
+            (level.first_order, level.last_order, level.order_count),
+            (Some(alice), Some(later), 2)
+        );
+        assert_eq!(level.sell_amount, 15);
+        assert_eq!(<OrderLinkedItemList<Test>>::level_orders(ep_hash, 5), vec![alice, later]);
+    });
+}
+
+#[test]
+fn orders_sized_by_the_amount_received_round_their_cost_up() {
+    new_test_ext().execute_with(|| {
+        let (base, quote) = (base(), quote());
+
+        // 10 base at 3 base per quote cost 3.33 quote, 4 are frozen
+        assert_ok!(Exchange::create_sized_order(
+            Origin::signed(CHARLIE),
+            base,
+            quote,
+            OrderType::Sell,
+            3,
+            OrderSizing::Receive,
+            10
+        ));
+        let sell = order(owned_order(CHARLIE, 0));
+        assert_eq!((sell.sell_amount, sell.buy_amount, sell.sizing), (4, 10, OrderSizing::Receive));
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, quote)), 4);
+
+        // 10 quote at 2 cost exactly 20 base
+        assert_ok!(Exchange::create_sized_order(
+            Origin::signed(BOB),
+            base,
+            quote,
+            OrderType::Buy,
+            2,
+            OrderSizing::Receive,
+            10
+        ));
+        let buy = order(owned_order(BOB, 0));
+        assert_eq!((buy.sell_amount, buy.buy_amount), (20, 10));
+        assert_eq!(Assets::freezed_balance_of((BOB, base)), 20);
+
+        assert_ok!(Exchange::cancel_order(Origin::signed(CHARLIE), sell.hash));
+        assert_eq!(Assets::freezed_balance_of((CHARLIE, quote)), 0);
+        assert_eq!(Assets::free_balance_of((CHARLIE, quote)), SUPPLY / 3);
+    });
+}
+
+#[test]
+fn twap_accumulates_prices_over_blocks_without_trades() {
+    new_test_ext().execute_with(|| {
+        let ep_hash = ep_hash();
+        trade_at(10);
+        Exchange::record_price_observations(1);
+
+        // no trades in blocks 2 to 10
+        System::set_block_number(11);
+        trade_at(20);
+        Exchange::record_price_observations(11);
+
+        // only the last price of a block is carried into the next blocks
+        System::set_block_number(21);
+        trade_at(12);
+        trade_at(16);
+        Exchange::record_price_observations(21);
+        assert_eq!(
+            Exchange::price_accumulator(ep_hash),
+            Some(PriceAccumulator {
+                price_cumulative: U256::from(10 * 10 + 20 * 10),
+                latest_price: 16,
+                updated_at: 21,
+            })
+        );
+        assert_eq!(Exchange::price_observation_count(ep_hash), 3);
+
+        System::set_block_number(31);
+        assert_eq!(Exchange::time_weighted_average_price(ep_hash, 10), Some(16));
+        assert_eq!(
+            Exchange::time_weighted_average_price(ep_hash, 20),
+            Some((20 * 10 + 16 * 10) / 20)
+        );
+        assert_eq!(
+            Exchange::time_weighted_average_price(ep_hash, 30),
+            Some((10 * 10 + 20 * 10 + 16 * 10) / 30)
+        );
+        // windows are rounded up to the observation before them
+        assert_eq!(Exchange::time_weighted_average_price(ep_hash, 15), Some(18));
+        assert_eq!(Exchange::time_weighted_average_price(ep_hash, 31), None);
+        assert_eq!(Exchange::time_weighted_average_price(ep_hash, 0), None);
+    });
+}
+
+#[test]
+fn genesis_assets_are_unique_and_within_their_supply() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Assets::create_genesis_asset(ALICE, b"BASE".to_vec(), SUPPLY, &[]),
+            assets::Error::<Test>::DuplicateGenesisAsset
+        );
+        assert_noop!(
+            Assets::create_genesis_asset(ALICE, b"FEE".to_vec(), 100, &[(BOB, 60), (BOB, 60)]),
+            assets::Error::<Test>::BalanceNotEnough
+        );
+
+        // a holder listed twice holds both parts
+        assert_ok!(Assets::create_genesis_asset(ALICE, b"FEE".to_vec(), 100, &[(BOB, 30), (BOB, 20)]));
+        let fee = Assets::genesis_asset_hash(b"FEE");
+        assert_eq!(Assets::free_balance_of((BOB, fee)), 50);
+        assert_eq!(Assets::free_balance_of((ALICE, fee)), 50);
+    });
+}
+
+/// A new asset of Alice, `OTHER`, not paired with `BASE` yet.
+fn other_asset() -> primitives::H256 {
+    assert_ok!(Assets::create_genesis_asset(ALICE, b"OTHER".to_vec(), SUPPLY, &[]));
+    Assets::genesis_asset_hash(b"OTHER")
+}
+
+#[test]
+fn listing_application_is_unique_in_either_orientation() {
+    new_test_ext().execute_with(|| {
+        let other = other_asset();
+        assert_ok!(Exchange::set_listing_policy(Origin::ROOT, ListingPolicy::Governance));
+
+        assert_ok!(Exchange::create_exchange_pair(Origin::signed(BOB), base(), other));
+        let hash = Exchange::listing_application_hash(base(), other);
+        assert_eq!(Exchange::listing_application(hash).unwrap().applicant, BOB);
+
+        assert_noop!(
+            Exchange::create_exchange_pair(Origin::signed(CHARLIE), base(), other),
+            Error::<Test>::ListingApplicationExisted
+        );
+        assert_noop!(
+            Exchange::create_exchange_pair(Origin::signed(CHARLIE), other, base()),
+            Error::<Test>::ListingApplicationExisted
+        );
+    });
+}
+
+#[test]
+fn rejected_listing_bond_goes_to_the_treasury() {
+    new_test_ext().execute_with(|| {
+        let other = other_asset();
+        assert_ok!(Exchange::set_listing_policy(Origin::ROOT, ListingPolicy::Governance));
+        assert_ok!(Exchange::create_exchange_pair(Origin::signed(BOB), base(), other));
+        let hash = Exchange::listing_application_hash(base(), other);
+        assert_eq!(Balances::reserved_balance(&BOB), ListingBond::get());
+
+        assert_noop!(
+            Exchange::withdraw_listing(Origin::signed(CHARLIE), hash),
+            Error::<Test>::CanOnlyWithdrawOwnListing
+        );
+        assert_ok!(Exchange::reject_listing(Origin::ROOT, hash));
+
+        assert!(Exchange::listing_application(hash).is_none());
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_eq!(Balances::free_balance(&BOB), ENDOWMENT - ListingBond::get());
+        assert_eq!(Balances::free_balance(&TREASURY), ListingBond::get());
+        assert!(dex_events().contains(&RawEvent::ListingRejected(hash, ListingBond::get())));
+    });
+}
+
+#[test]
+fn slashed_listing_bond_goes_to_the_treasury() {
+    new_test_ext().execute_with(|| {
+        let other = other_asset();
+        assert_ok!(Exchange::set_listing_policy(Origin::ROOT, ListingPolicy::Bonded));
+        assert_ok!(Exchange::create_exchange_pair(Origin::signed(BOB), base(), other));
+        let ep_hash = Exchange::exchange_pair_hash_by_base_quote(base(), other).unwrap();
+
+        assert_ok!(Exchange::release_listing_bond(Origin::ROOT, ep_hash, true));
+
+        assert!(Exchange::listing_bond(ep_hash).is_none());
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_eq!(Balances::free_balance(&TREASURY), ListingBond::get());
+    });
+}
@@ -0,0 +1,15 @@
+use super::*;
+use support::storage::{with_transaction, TransactionOutcome};
+
+impl<T: Trait> Module<T> {
+    /// Run `f` in a storage transaction, all storage changes it made are reverted when it
+    /// returns an error. Transactions nest, so the order paths can call each other.
+    pub fn transactional<R>(
+        f: impl FnOnce() -> result::Result<R, DispatchError>,
+    ) -> result::Result<R, DispatchError> {
+        with_transaction(|| match f() {
+            Ok(r) => TransactionOutcome::Commit(Ok(r)),
+            Err(e) => TransactionOutcome::Rollback(Err(e)),
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Add the blocks the previous price was in effect for to the pair's accumulator and make
+    /// `price` the price in effect from the next block on.
+    pub fn accumulate_price(ep_hash: T::Hash, price: T::Price) -> DispatchResult {
+        let now = <system::Module<T>>::block_number();
+
+        let price_cumulative = match Self::price_accumulator(ep_hash) {
+            Some(accumulator) => Self::price_cumulative_at(&accumulator, now)?,
+            None => U256::zero(),
+        };
+
+        <PriceAccumulators<T>>::insert(
+            ep_hash,
+            PriceAccumulator {
+                price_cumulative,
+                latest_price: price,
+                updated_at: now,
+            },
+        );
+        <TradedPairs<T>>::mutate(|pairs| {
+            if !pairs.contains(&ep_hash) {
+                pairs.push(ep_hash);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Keep an observation of the accumulators of the pairs traded in block `n`, at most one
+    /// per pair every `TwapObservationPeriod` blocks. The last `TwapObservationCap`
+    /// observations of a pair are kept.
+    pub fn record_price_observations(n: T::BlockNumber) {
+        let cap = u64::from(T::TwapObservationCap::get());
+
+        for ep_hash in <TradedPairs<T>>::take() {
+            let accumulator = match Self::price_accumulator(ep_hash) {
+                Some(accumulator) => accumulator,
+                None => continue,
+            };
+            if cap == 0 {
+                continue;
+            }
+
+            let count = Self::price_observation_count(ep_hash);
+            let latest = count
+                .checked_sub(1)
+                .and_then(|index| Self::price_observation(ep_hash, index % cap));
+            if let Some(latest) = latest {
+                if n < latest.block + T::TwapObservationPeriod::get() {
+                    continue;
+                }
+            }
+
+            <PriceObservations<T>>::insert(
+                ep_hash,
+                count % cap,
+                PriceObservation {
+                    block: n,
+                    price_cumulative: accumulator.price_cumulative,
+                },
+            );
+            <PriceObservationCount<T>>::insert(ep_hash, count + 1);
+        }
+    }
+
+    /// Average of the pair's price over the blocks since the newest observation at least
+    /// `window` blocks old, weighted by the number of blocks each price was in effect for.
+    /// The window is rounded up to that observation, trades of the current block are not
+    /// included. `None` when the pair has no observation that old.
+    pub fn time_weighted_average_price(
+        ep_hash: T::Hash,
+        window: T::BlockNumber,
+    ) -> Option<T::Price> {
+        if window.is_zero() {
+            return None;
+        }
+
+        let now = <system::Module<T>>::block_number();
+        let accumulator = Self::price_accumulator(ep_hash)?;
+        let observation = Self::price_observation_before(ep_hash, now.checked_sub(&window)?)?;
+
+        let price_cumulative = Self::price_cumulative_at(&accumulator, now).ok()?;
+        let blocks = U256::from(Self::into_128(now - observation.block).ok()?);
+
+        let price = (price_cumulative - observation.price_cumulative) / blocks;
+        Self::from_128(price.try_into().ok()?).ok()
+    }
+
+    fn price_cumulative_at(
+        accumulator: &PriceAccumulator<T::Price, T::BlockNumber>,
+        n: T::BlockNumber,
+    ) -> result::Result<U256, DispatchError> {
+        let price = U256::from(Self::into_128(accumulator.latest_price)?);
+        let blocks = U256::from(Self::into_128(n.saturating_sub(accumulator.updated_at))?);
+
+        Ok(accumulator
+            .price_cumulative
+            .saturating_add(price.saturating_mul(blocks)))
+    }
+
+    /// The newest kept observation of a pair made in or before block `n`.
+    fn price_observation_before(
+        ep_hash: T::Hash,
+        n: T::BlockNumber,
+    ) -> Option<PriceObservation<T::BlockNumber>> {
+        let cap = u64::from(T::TwapObservationCap::get());
+        let count = Self::price_observation_count(ep_hash);
+        let first = count.saturating_sub(cap);
+
+        // observations are kept in the order they were made, so by block number
+        let mut low = first;
+        let mut high = count;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let made_before = Self::price_observation(ep_hash, mid % cap)
+                .map_or(false, |observation| observation.block <= n);
+
+            if made_before {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low == first {
+            return None;
+        }
+        Self::price_observation(ep_hash, (low - 1) % cap)
+    }
+}
+
+impl<T: Trait> PriceOracle<T::Hash, T::Price, T::BlockNumber> for Module<T> {
+    fn latest_price(ep_hash: T::Hash) -> Option<T::Price> {
+        Self::exchange_pair(ep_hash).and_then(|ep| ep.latest_matched_price)
+    }
+
+    fn twap(ep_hash: T::Hash, window: T::BlockNumber) -> Option<T::Price> {
+        Self::time_weighted_average_price(ep_hash, window)
+    }
+}
@@ -10,23 +10,43 @@ pub struct LinkedItem<K1, K2, K3> {
     pub price: Option<K2>,
     pub buy_amount: K3,
     pub sell_amount: K3,
-    pub orders: Vec<K1>, // TODO DP remove the item at 0 index will caused performance issue, should be optimized
+    /// oldest order of the level, the orders are queued in time priority
+    pub first_order: Option<K1>,
+    /// newest order of the level
+    pub last_order: Option<K1>,
+    pub order_count: u32,
 }
 
-pub struct LinkedList<T, S, K1, K2, K3>(rstd::marker::PhantomData<(T, S, K1, K2, K3)>);
+/// Position of an order in the queue of its price level.
+#[derive(Encode, Decode, Clone)]
+#[cfg_attr(feature = "std", derive(PartialEq, Eq, Debug))]
+pub struct LevelOrderNode<K1> {
+    pub prev: Option<K1>,
+    pub next: Option<K1>,
+}
+
+pub struct LinkedList<T, S, I, Q, K1, K2, K3>(rstd::marker::PhantomData<(T, S, I, Q, K1, K2, K3)>);
+
+/// Most price levels kept in one bucket of the price level index, a full bucket is split
+/// in two.
+pub const PRICE_BUCKET_CAPACITY: usize = 64;
 
 pub type OrderLinkedItem<T> =
     LinkedItem<<T as system::Trait>::Hash, <T as Trait>::Price, <T as balances::Trait>::Balance>;
 pub type OrderLinkedItemList<T> = LinkedList<
     T,
     LinkedItemList<T>,
+    PriceLevelIndex<T>,
+    LevelOrderQueue<T>,
     <T as system::Trait>::Hash,
     <T as Trait>::Price,
     <T as balances::Trait>::Balance,
 >;
 
-// Self: StorageMap, Key1: ExchangePairHash, Key2: Price, Value: OrderHash
-impl<T, S, K1, K2, K3> LinkedList<T, S, K1, K2, K3>
+// Self: StorageDoubleMap, Key1: ExchangePairHash, Key2: Price, Value: OrderHash
+// I: StorageDoubleMap, Key1: ExchangePairHash, Key2: (OrderType, BucketLowestPrice), Value: Prices
+// Q: StorageDoubleMap, Key1: (ExchangePairHash, Price), Key2: OrderHash, Value: LevelOrderNode
+impl<T, S, I, Q, K1, K2, K3> LinkedList<T, S, I, Q, K1, K2, K3>
 where
     T: exchange::Trait,
     K1: EncodeLike
@@ -39,7 +59,9 @@ where
         + AsRef<[u8]>,
     K2: Parameter + Default + Member + Bounded + AtLeast32Bit + Copy,
     K3: Parameter + Default + Member + Bounded + AtLeast32Bit + Copy,
-    S: StorageMap<(K1, Option<K2>), LinkedItem<K1, K2, K3>, Query = Option<LinkedItem<K1, K2, K3>>>,
+    S: StorageDoubleMap<K1, Option<K2>, LinkedItem<K1, K2, K3>, Query = Option<LinkedItem<K1, K2, K3>>>,
+    I: StorageDoubleMap<K1, (OrderType, Option<K2>), Vec<K2>, Query = Vec<K2>>,
+    Q: StorageDoubleMap<(K1, K2), K1, LevelOrderNode<K1>, Query = Option<LevelOrderNode<K1>>>,
 {
     pub fn read_head(key: K1) -> LinkedItem<K1, K2, K3> {
         Self::read(key, None)
@@ -54,12 +76,14 @@ where
     }
 
     pub fn read(key1: K1, key2: Option<K2>) -> LinkedItem<K1, K2, K3> {
-        S::get((key1, key2)).unwrap_or_else(|| {
+        S::get(key1, key2).unwrap_or_else(|| {
             let bottom = LinkedItem {
                 prev: Some(K2::max_value()),
                 next: None,
                 price: Some(K2::min_value()),
-                orders: Vec::<K1>::new(),
+                first_order: None,
+                last_order: None,
+                order_count: 0,
                 buy_amount: Default::default(),
                 sell_amount: Default::default(),
             };
@@ -68,7 +92,9 @@ where
                 prev: None,
                 next: Some(K2::min_value()),
                 price: Some(K2::max_value()),
-                orders: Vec::<K1>::new(),
+                first_order: None,
+                last_order: None,
+                order_count: 0,
                 buy_amount: Default::default(),
                 sell_amount: Default::default(),
             };
@@ -77,7 +103,9 @@ where
                 prev: Some(K2::min_value()),
                 next: Some(K2::max_value()),
                 price: None,
-                orders: Vec::<K1>::new(),
+                first_order: None,
+                last_order: None,
+                order_count: 0,
                 buy_amount: Default::default(),
                 sell_amount: Default::default(),
             };
@@ -90,7 +118,7 @@ where
     }
 
     pub fn write(key1: K1, key2: Option<K2>, item: LinkedItem<K1, K2, K3>) {
-        S::insert((key1, key2), item);
+        S::insert(key1, key2, item);
     }
 
     pub fn append(
@@ -101,44 +129,24 @@ where
         buy_amount: K3,
         otype: OrderType,
     ) {
-        let item = S::get((key1, Some(key2)));
+        let item = S::get(key1, Some(key2));
         match item {
             Some(mut item) => {
-                item.orders.push(value);
+                Self::push_order(key1, key2, &mut item, value);
                 item.buy_amount = item.buy_amount + buy_amount;
                 item.sell_amount = item.sell_amount + sell_amount;
                 Self::write(key1, Some(key2), item);
                 return;
             }
             None => {
-                let start_item;
-                let end_item;
-
-                match otype {
-                    OrderType::Buy => {
-                        start_item = Some(K2::min_value());
-                        end_item = None;
-                    }
-                    OrderType::Sell => {
-                        start_item = None;
-                        end_item = Some(K2::max_value());
-                    }
-                }
-
-                let mut item = Self::read(key1, start_item);
-
-                while item.next != end_item {
-                    match item.next {
-                        None => {}
-                        Some(price) => {
-                            if key2 < price {
-                                break;
-                            }
-                        }
-                    }
+                let start_item = match otype {
+                    OrderType::Buy => Some(K2::min_value()),
+                    OrderType::Sell => None,
+                };
 
-                    item = Self::read(key1, item.next);
-                }
+                // the level follows the next lower level of its side, or the side's start
+                let prev = Self::index_insert(key1, otype, key2).map_or(start_item, Some);
+                let item = Self::read(key1, prev);
 
                 // update new_prev
                 let new_prev = LinkedItem {
@@ -156,16 +164,17 @@ where
                 Self::write(key1, new_next.price, new_next.clone());
 
                 // update key2
-                let mut v = Vec::new();
-                v.push(value);
-                let item = LinkedItem {
+                let mut item = LinkedItem {
                     prev: new_prev.price,
                     next: new_next.price,
                     buy_amount,
                     sell_amount,
-                    orders: v,
+                    first_order: None,
+                    last_order: None,
+                    order_count: 0,
                     price: Some(key2),
                 };
+                Self::push_order(key1, key2, &mut item, value);
                 Self::write(key1, Some(key2), item);
             }
         };
@@ -181,35 +190,26 @@ where
 
     pub fn update_amount(key1: K1, key2: K2, sell_amount: K3, buy_amount: K3) {
         let mut item = Self::read(key1, Some(key2));
-        item.buy_amount = item.buy_amount - buy_amount;
-        item.sell_amount = item.sell_amount - sell_amount;
+        item.buy_amount = item.buy_amount.saturating_sub(buy_amount);
+        item.sell_amount = item.sell_amount.saturating_sub(sell_amount);
         Self::write(key1, Some(key2), item);
     }
 
-    pub fn remove_all(key1: K1, otype: OrderType) {
-        let end_item;
-
-        if otype == OrderType::Buy {
-            end_item = Some(K2::min_value());
-        } else {
-            end_item = Some(K2::max_value());
-        }
-
-        let mut head = Self::read_head(key1);
-
-        loop {
-            let key2 = Self::next_match_price(&head, otype);
-            if key2 == end_item {
-                break;
-            }
-
-            match Self::remove_orders_in_one_item(key1, key2.unwrap()) {
-                Err(_) => break,
-                _ => {}
-            };
+    // move an order to the back of its price level and add the given amounts to the level
+    pub fn requeue(key1: K1, key2: K2, value: K1, sell_amount: K3, buy_amount: K3) -> Result {
+        let mut item = S::get(key1, Some(key2)).ok_or("requeue the order but price level not found")?;
+        ensure!(
+            Q::contains_key((key1, key2), value),
+            "requeue the order but not in market order list"
+        );
+
+        Self::take_order(key1, key2, &mut item, value)?;
+        Self::push_order(key1, key2, &mut item, value);
+        item.buy_amount = item.buy_amount + buy_amount;
+        item.sell_amount = item.sell_amount + sell_amount;
+        Self::write(key1, Some(key2), item);
 
-            head = Self::read_head(key1);
-        }
+        Ok(())
     }
 
     pub fn remove_order(
@@ -219,19 +219,19 @@ where
         sell_amount: K3,
         buy_amount: K3,
     ) -> Result {
-        match S::get((key1, Some(key2))) {
+        match S::get(key1, Some(key2)) {
             Some(mut item) => {
                 ensure!(
-                    item.orders.contains(&order_hash),
+                    Q::contains_key((key1, key2), order_hash),
                     "cancel the order but not in market order list"
                 );
 
-                item.orders.retain(|&x| x != order_hash);
+                Self::take_order(key1, key2, &mut item, order_hash)?;
                 item.buy_amount = item.buy_amount - buy_amount;
                 item.sell_amount = item.sell_amount - sell_amount;
                 Self::write(key1, Some(key2), item.clone());
 
-                if item.orders.len() == 0 {
+                if item.order_count == 0 {
                     Self::remove_item(key1, key2);
                 }
             }
@@ -241,14 +241,16 @@ where
     }
 
     pub fn remove_item(key1: K1, key2: K2) {
-        if let Some(item) = S::take((key1, Some(key2))) {
-            S::mutate((key1.clone(), item.prev), |x| {
+        if let Some(item) = S::take(key1, Some(key2)) {
+            Self::index_remove(key1, key2);
+
+            S::mutate(key1, item.prev, |x| {
                 if let Some(x) = x {
                     x.next = item.next;
                 }
             });
 
-            S::mutate((key1.clone(), item.next), |x| {
+            S::mutate(key1, item.next, |x| {
                 if let Some(x) = x {
                     x.prev = item.prev;
                 }
@@ -256,29 +258,181 @@ where
         }
     }
 
-    // when the order is canceled, it should be remove from Sell / Buy orders
-    pub fn remove_orders_in_one_item(key1: K1, key2: K2) -> Result {
-        match S::get((key1, Some(key2))) {
-            Some(mut item) => {
-                while item.orders.len() > 0 {
-                    let order_hash = item.orders.get(0).ok_or("can not get order hash")?;
+    /// Order queued after `value` in its price level.
+    pub fn next_order(key1: K1, key2: K2, value: K1) -> Option<K1> {
+        Q::get((key1, key2), value).and_then(|node| node.next)
+    }
 
-                    let order = <exchange::Module<T>>::order(order_hash.borrow())
-                        .ok_or("can not get order")?;
-                    ensure!(order.is_finished(), "try to remove not finished order");
+    /// All orders of a price level, oldest first.
+    pub fn level_orders(key1: K1, key2: K2) -> Vec<K1> {
+        let mut orders = Vec::new();
+        let mut order = S::get(key1, Some(key2)).and_then(|item| item.first_order);
 
-                    item.orders.remove(0);
+        while let Some(value) = order {
+            orders.push(value);
+            order = Self::next_order(key1, key2, value);
+        }
 
-                    Self::write(key1, Some(key2), item.clone());
+        orders
+    }
+
+    /// Queue an order at the back of its price level, the caller writes the item.
+    pub fn push_order(key1: K1, key2: K2, item: &mut LinkedItem<K1, K2, K3>, value: K1) {
+        let node = LevelOrderNode {
+            prev: item.last_order,
+            next: None,
+        };
+
+        match item.last_order {
+            Some(last) => Q::mutate((key1, key2), last, |x| {
+                if let Some(x) = x {
+                    x.next = Some(value);
                 }
+            }),
+            None => item.first_order = Some(value),
+        }
 
-                if item.orders.len() == 0 {
-                    Self::remove_item(key1, key2);
+        Q::insert((key1, key2), value, node);
+        item.last_order = Some(value);
+        item.order_count += 1;
+    }
+
+    /// Unlink an order from anywhere in the queue of its price level, the caller writes the
+    /// item.
+    fn take_order(key1: K1, key2: K2, item: &mut LinkedItem<K1, K2, K3>, value: K1) -> Result {
+        let node = Q::take((key1, key2), value).ok_or("order not in market order list")?;
+
+        match node.prev {
+            Some(prev) => Q::mutate((key1, key2), prev, |x| {
+                if let Some(x) = x {
+                    x.next = node.next;
                 }
-            }
-            None => {}
+            }),
+            None => item.first_order = node.next,
+        }
+
+        match node.next {
+            Some(next) => Q::mutate((key1, key2), next, |x| {
+                if let Some(x) = x {
+                    x.prev = node.prev;
+                }
+            }),
+            None => item.last_order = node.prev,
         }
 
+        item.order_count = item.order_count.saturating_sub(1);
+
         Ok(())
     }
+
+    /// Bucket bounds of one side of the index, the lowest price each bucket can hold. The
+    /// first bucket starts at the minimum price, so a price belongs to the bucket of the
+    /// last bound not above it.
+    fn bucket_bounds(key1: K1, otype: OrderType) -> Vec<K2> {
+        let mut bounds = I::get(key1, (otype, None));
+        if bounds.is_empty() {
+            bounds.push(K2::min_value());
+        }
+        bounds
+    }
+
+    fn bucket_position(bounds: &[K2], key2: K2) -> usize {
+        match bounds.binary_search(&key2) {
+            Ok(position) => position,
+            Err(position) => position.saturating_sub(1),
+        }
+    }
+
+    /// Add a price level to the index and return the next lower level of the same side.
+    /// Only the first bucket can be empty, so that level is in the level's own bucket or
+    /// the last one of the bucket below.
+    fn index_insert(key1: K1, otype: OrderType, key2: K2) -> Option<K2> {
+        let mut bounds = Self::bucket_bounds(key1, otype);
+        let position = Self::bucket_position(&bounds, key2);
+        let bound = bounds[position];
+
+        let mut bucket = I::get(key1, (otype, Some(bound)));
+        let index = match bucket.binary_search(&key2) {
+            Ok(index) => index,
+            Err(index) => {
+                bucket.insert(index, key2);
+                index
+            }
+        };
+
+        let prev = if index > 0 {
+            Some(bucket[index - 1])
+        } else if position > 0 {
+            I::get(key1, (otype, Some(bounds[position - 1]))).last().cloned()
+        } else {
+            None
+        };
+
+        if bucket.len() > PRICE_BUCKET_CAPACITY {
+            let upper = bucket.split_off(bucket.len() / 2);
+            let upper_bound = upper[0];
+            I::insert(key1, (otype, Some(upper_bound)), upper);
+            bounds.insert(position + 1, upper_bound);
+            I::insert(key1, (otype, None), bounds);
+        }
+        I::insert(key1, (otype, Some(bound)), bucket);
+
+        prev
+    }
+
+    /// Remove a price level from the index of whichever side holds it, then merge its
+    /// bucket with a neighbour if they fit in half a bucket.
+    fn index_remove(key1: K1, key2: K2) {
+        for &otype in [OrderType::Buy, OrderType::Sell].iter() {
+            let bounds = Self::bucket_bounds(key1, otype);
+            let position = Self::bucket_position(&bounds, key2);
+            let bound = bounds[position];
+
+            let mut bucket = I::get(key1, (otype, Some(bound)));
+            let index = match bucket.binary_search(&key2) {
+                Ok(index) => index,
+                Err(_) => continue,
+            };
+            bucket.remove(index);
+
+            if !bucket.is_empty() {
+                I::insert(key1, (otype, Some(bound)), bucket);
+            } else {
+                I::remove(key1, (otype, Some(bound)));
+            }
+            Self::merge_buckets(key1, otype, bounds, position);
+
+            return;
+        }
+    }
+
+    /// Merge the bucket at `position` into the one below it, the first bucket with the one
+    /// above it, when both fit in half a bucket, so that buckets just split do not merge
+    /// back. An emptied bucket other than the first is always merged.
+    fn merge_buckets(key1: K1, otype: OrderType, mut bounds: Vec<K2>, position: usize) {
+        let upper = position.max(1);
+        if upper >= bounds.len() {
+            return;
+        }
+
+        let mut lower_bucket = I::get(key1, (otype, Some(bounds[upper - 1])));
+        let upper_bucket = I::get(key1, (otype, Some(bounds[upper])));
+        if !upper_bucket.is_empty()
+            && lower_bucket.len() + upper_bucket.len() > PRICE_BUCKET_CAPACITY / 2
+        {
+            return;
+        }
+
+        I::remove(key1, (otype, Some(bounds[upper])));
+        lower_bucket.extend(upper_bucket);
+        if !lower_bucket.is_empty() {
+            I::insert(key1, (otype, Some(bounds[upper - 1])), lower_bucket);
+        }
+        bounds.remove(upper);
+        if bounds.len() > 1 {
+            I::insert(key1, (otype, None), bounds);
+        } else {
+            I::remove(key1, (otype, None));
+        }
+    }
 }
@@ -0,0 +1,198 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Pay the Treasury share of a trading fee to the Treasury and the rest to the reward pot.
+    pub fn split_fee(
+        fee_account: &T::AccountId,
+        asset: T::Hash,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        <dex::Module<T>>::transactional(|| {
+            let to_treasury = Self::treasury_share() * amount;
+            let to_pot = amount - to_treasury;
+
+            if to_treasury != Zero::zero() {
+                <assets::Module<T>>::transfer(
+                    fee_account.clone(),
+                    asset,
+                    T::TreasuryAccount::get(),
+                    to_treasury,
+                )?;
+            }
+
+            if to_pot != Zero::zero() {
+                <assets::Module<T>>::transfer(
+                    fee_account.clone(),
+                    asset,
+                    Self::pot_account(),
+                    to_pot,
+                )?;
+
+                if !Self::pot_assets().contains(&asset) {
+                    <PotAssets<T>>::mutate(|assets| assets.push(asset));
+                }
+            }
+
+            Self::deposit_event(RawEvent::FeeSplit(asset, to_treasury, to_pot));
+
+            Ok(())
+        })
+    }
+
+    /// Start the payout of a new era once the payout of the previous one is finished, then
+    /// pay the next recipients.
+    pub fn advance_reward_payout() {
+        if Self::reward_payout().is_none() {
+            let era = T::Stakers::current_era();
+            if era != Self::last_era() {
+                LastEra::put(era);
+                Self::start_reward_payout(era);
+            }
+        }
+
+        Self::pay_out_rewards();
+    }
+
+    /// Start paying out the reward pot to the reward target of an era. What the pot holds is
+    /// taken now, `pay_out_rewards` pays the recipients over the following blocks, reading
+    /// them a page at a time. The stakes of the reward asset are copied into pages now, as
+    /// they may change before the payout is over.
+    pub fn start_reward_payout(era: u32) {
+        let pot = Self::pot_account();
+        let assets: Vec<_> = Self::pot_assets()
+            .into_iter()
+            .map(|asset| {
+                let amount = <assets::Module<T>>::free_balance_of((pot.clone(), asset));
+                (asset, amount, T::Balance::zero())
+            })
+            .filter(|(_, amount, _)| *amount != Zero::zero())
+            .collect();
+        if assets.is_empty() {
+            return;
+        }
+
+        let target = Self::reward_target();
+        let (total_stake, pages) = match target {
+            RewardTarget::Stakers => (T::Stakers::total_stake(era), T::Stakers::pages(era)),
+            RewardTarget::AssetHolders(_) => {
+                let stakes = Self::asset_stakes();
+                let total_stake = stakes
+                    .iter()
+                    .fold(T::Balance::zero(), |total, (_, stake)| total.saturating_add(*stake));
+                if total_stake == Zero::zero() {
+                    return;
+                }
+
+                let mut pages = 0;
+                for page in stakes.chunks(T::RewardPayoutsPerBlock::get().max(1) as usize) {
+                    <PayoutAssetStakes<T>>::insert(pages, page.to_vec());
+                    pages += 1;
+                }
+                (total_stake, pages)
+            }
+        };
+        if total_stake == Zero::zero() {
+            return;
+        }
+
+        <CurrentRewardPayout<T>>::put(RewardPayout {
+            era,
+            target,
+            total_stake,
+            assets,
+            pages,
+            next_page: 0,
+            next_recipient: 0,
+        });
+    }
+
+    /// Pay the next `RewardPayoutsPerBlock` recipients of the payout in progress their share
+    /// of each asset, pro rata to their stake, reading at most as many pages of recipients.
+    /// What rounding or a failed transfer leaves in the pot is paid out with the next era's
+    /// fees.
+    pub fn pay_out_rewards() {
+        let mut payout = match Self::reward_payout() {
+            Some(payout) => payout,
+            None => return,
+        };
+
+        let pot = Self::pot_account();
+        let limit = T::RewardPayoutsPerBlock::get().max(1);
+        let mut payments = 0;
+        let mut read = 0;
+
+        while payments < limit && read < limit && payout.next_page < payout.pages {
+            let recipients = match payout.target {
+                RewardTarget::Stakers => T::Stakers::stakers(payout.era, payout.next_page),
+                RewardTarget::AssetHolders(_) => Self::payout_asset_stakes(payout.next_page),
+            };
+            read += 1;
+
+            for (who, stake) in recipients
+                .iter()
+                .skip(payout.next_recipient as usize)
+                .take((limit - payments) as usize)
+            {
+                for (asset, amount, paid) in payout.assets.iter_mut() {
+                    match Self::pay_reward(&pot, *asset, *amount, who, *stake, payout.total_stake)
+                    {
+                        Ok(reward) => *paid = paid.saturating_add(reward),
+                        Err(e) => support::debug::warn!(
+                            "reward payout of {:?} to {:?} failed: {:?}",
+                            asset,
+                            who,
+                            e
+                        ),
+                    }
+                }
+                payments += 1;
+                payout.next_recipient += 1;
+            }
+
+            if payout.next_recipient as usize >= recipients.len() {
+                if let RewardTarget::AssetHolders(_) = payout.target {
+                    <PayoutAssetStakes<T>>::remove(payout.next_page);
+                }
+                payout.next_page += 1;
+                payout.next_recipient = 0;
+            }
+        }
+
+        if payout.next_page < payout.pages {
+            <CurrentRewardPayout<T>>::put(payout);
+            return;
+        }
+
+        <CurrentRewardPayout<T>>::kill();
+        for (asset, _, paid) in payout.assets {
+            Self::deposit_event(RawEvent::RewardsDistributed(payout.era, asset, paid));
+        }
+
+        <PotAssets<T>>::mutate(|assets| {
+            assets.retain(|asset| {
+                <assets::Module<T>>::free_balance_of((pot.clone(), *asset)) != Zero::zero()
+            })
+        });
+    }
+
+    fn pay_reward(
+        pot: &T::AccountId,
+        asset: T::Hash,
+        amount: T::Balance,
+        who: &T::AccountId,
+        stake: T::Balance,
+        total_stake: T::Balance,
+    ) -> result::Result<T::Balance, DispatchError> {
+        <dex::Module<T>>::transactional(|| {
+            // rounded down, so the rewards never add up to more than the pot
+            let reward = Self::from_u256(
+                Self::into_u256(amount)? * Self::into_u256(stake)? / Self::into_u256(total_stake)?,
+            )?;
+            if reward != Zero::zero() {
+                <assets::Module<T>>::transfer(pot.clone(), asset, who.clone(), reward)?;
+            }
+
+            Ok(reward)
+        })
+    }
+}
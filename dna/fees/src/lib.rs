@@ -0,0 +1,254 @@
+//! # Fees module
+//!
+//! Splits the trading fees the exchange collects between the Treasury and a reward pot. The
+//! pot is distributed each era, pro rata, to the stakers of the native currency or to the
+//! accounts staking an asset chosen by governance, `RewardPayoutsPerBlock` of them a block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The above line is needed to compile the Wasm binaries.
+
+use codec::{Decode, Encode};
+use core::convert::{TryFrom, TryInto};
+use dex::OnTradingFee;
+use primitives::U256;
+use rstd::{prelude::*, result};
+use sp_runtime::traits::{AccountIdConversion, CheckedAdd, CheckedSub, Saturating, Zero};
+use sp_runtime::{ModuleId, Permill, RuntimeDebug};
+use support::{
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
+    traits::{EnsureOrigin, Get},
+    StorageMap, StorageValue,
+};
+use system::ensure_signed;
+
+mod distribute;
+#[cfg(test)]
+mod mock;
+mod stake;
+#[cfg(test)]
+mod tests;
+
+/// Account of the reward pot
+const MODULE_ID: ModuleId = ModuleId(*b"dna/fees");
+
+pub trait Trait: dex::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Account the Treasury share of the fees is paid to
+    type TreasuryAccount: Get<Self::AccountId>;
+    /// Stakers of the native currency, rewarded when the pot goes to `RewardTarget::Stakers`
+    type Stakers: EraStakers<Self::AccountId, Self::Balance>;
+    /// Most accounts staking the reward asset at once
+    type MaxAssetStakers: Get<u32>;
+    /// Most recipients paid, and pages of recipients read, from the reward pot in one block
+    type RewardPayoutsPerBlock: Get<u32>;
+}
+
+/// Eras and stakers of the native currency, such as those of the staking module.
+pub trait EraStakers<AccountId, Balance> {
+    /// Index of the current era, the reward pot is distributed when it changes.
+    fn current_era() -> u32;
+
+    /// Total stake of the accounts staking in an era.
+    fn total_stake(era: u32) -> Balance;
+
+    /// Number of pages the accounts staking in an era are read in.
+    fn pages(era: u32) -> u32;
+
+    /// Accounts staking in an era, with their stake, on a page below `pages`.
+    fn stakers(era: u32, page: u32) -> Vec<(AccountId, Balance)>;
+}
+
+impl<AccountId, Balance: Zero> EraStakers<AccountId, Balance> for () {
+    fn current_era() -> u32 {
+        0
+    }
+
+    fn total_stake(_era: u32) -> Balance {
+        Zero::zero()
+    }
+
+    fn pages(_era: u32) -> u32 {
+        0
+    }
+
+    fn stakers(_era: u32, _page: u32) -> Vec<(AccountId, Balance)> {
+        Vec::new()
+    }
+}
+
+/// Who the reward pot is distributed to
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum RewardTarget<Hash> {
+    /// stakers of the native currency, pro rata to their stake
+    Stakers,
+    /// accounts staking the asset with `stake_reward_asset`, pro rata to their stake
+    AssetHolders(Hash),
+}
+
+impl<Hash> Default for RewardTarget<Hash> {
+    fn default() -> Self {
+        RewardTarget::Stakers
+    }
+}
+
+/// Payout of the reward pot of an era, `RewardPayoutsPerBlock` recipients a block
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RewardPayout<Hash, Balance> {
+    pub era: u32,
+    pub target: RewardTarget<Hash>,
+    /// stake of all recipients
+    pub total_stake: Balance,
+    /// (asset, amount of the pot paid out, amount paid so far)
+    pub assets: Vec<(Hash, Balance, Balance)>,
+    /// pages the recipients are read in
+    pub pages: u32,
+    pub next_page: u32,
+    /// index of the next recipient on `next_page`
+    pub next_recipient: u32,
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// Amount must be above zero
+        ZeroAmount,
+        /// Reward pot does not go to the holders of an asset
+        NoRewardAsset,
+        /// Reward asset has the maximum number of stakers
+        TooManyAssetStakers,
+        /// Unstaking more than the staked amount
+        StakeNotEnough,
+        /// Overflow
+        OverflowError,
+        /// Number conversion failed
+        NumberCastError,
+    }
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+		RewardTarget = RewardTarget<<T as system::Trait>::Hash>,
+	{
+		TreasuryShareSet(Permill),
+
+		RewardTargetSet(RewardTarget),
+
+		// (assetHash, paid to the Treasury, paid to the reward pot)
+		FeeSplit(Hash, Balance, Balance),
+
+		// (era, assetHash, distributed amount)
+		RewardsDistributed(u32, Hash, Balance),
+
+		// (accountId, assetHash, amount)
+		RewardAssetStaked(AccountId, Hash, Balance),
+
+		// (accountId, assetHash, amount)
+		RewardAssetUnstaked(AccountId, Hash, Balance),
+	}
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as FeesStorage {
+        /// Part of the trading fees paid to the Treasury, the rest goes to the reward pot
+        pub TreasuryShare get(fn treasury_share) config(): Permill;
+        /// Who the reward pot is distributed to
+        pub CurrentRewardTarget get(fn reward_target): RewardTarget<T::Hash>;
+        /// Assets the reward pot holds
+        pub PotAssets get(fn pot_assets): Vec<T::Hash>;
+        /// Era the reward pot was last distributed in
+        pub LastEra get(fn last_era): u32;
+        /// (AccountId, staked amount) of the accounts staking the reward asset
+        pub AssetStakes get(fn asset_stakes): Vec<(T::AccountId, T::Balance)>;
+        /// Payout of the reward pot in progress
+        pub CurrentRewardPayout get(fn reward_payout): Option<RewardPayout<T::Hash, T::Balance>>;
+        /// Page => (AccountId, stake) of the reward asset stakers the payout in progress has left
+        pub PayoutAssetStakes get(fn payout_asset_stakes): map hasher(blake2_128_concat) u32 => Vec<(T::AccountId, T::Balance)>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event() = default;
+
+        type Error = Error<T>;
+
+        const MaxAssetStakers: u32 = T::MaxAssetStakers::get();
+
+        const RewardPayoutsPerBlock: u32 = T::RewardPayoutsPerBlock::get();
+
+        fn on_finalize(_n: T::BlockNumber) {
+            Self::advance_reward_payout();
+        }
+
+        /// # Set the part of the trading fees paid to the Treasury
+        /// * `origin` - governance origin of the exchange
+        /// * `share` - part paid to the Treasury, the rest goes to the reward pot
+        pub fn set_treasury_share(origin, share: Permill) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            TreasuryShare::put(share);
+
+            Self::deposit_event(RawEvent::TreasuryShareSet(share));
+
+            Ok(())
+        }
+
+        /// # Set who the reward pot is distributed to
+        /// * `origin` - governance origin of the exchange
+        /// * `target` - stakers of the native currency or stakers of an asset, the stakes of
+        ///   the previous reward asset are released
+        pub fn set_reward_target(origin, target: RewardTarget<T::Hash>) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            Self::do_set_reward_target(target)
+        }
+
+        /// # Stake the reward asset to share in the reward pot
+        /// * `origin` - signer
+        /// * `amount` - amount of the reward asset frozen
+        pub fn stake_reward_asset(origin, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_stake_reward_asset(sender, amount)
+        }
+
+        /// # Unstake the reward asset
+        /// * `origin` - signer
+        /// * `amount` - amount of the reward asset unfrozen
+        pub fn unstake_reward_asset(origin, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_unstake_reward_asset(sender, amount)
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Account holding the reward pot.
+    pub fn pot_account() -> T::AccountId {
+        MODULE_ID.into_account()
+    }
+
+    fn into_u256(amount: T::Balance) -> result::Result<U256, DispatchError> {
+        let amount: u128 = amount.try_into().map_err(|_| Error::<T>::NumberCastError)?;
+        Ok(U256::from(amount))
+    }
+
+    fn from_u256(amount: U256) -> result::Result<T::Balance, DispatchError> {
+        let amount: u128 = amount.try_into().map_err(|_| Error::<T>::OverflowError)?;
+        T::Balance::try_from(amount).map_err(|_| Error::<T>::NumberCastError.into())
+    }
+}
+
+impl<T: Trait> OnTradingFee<T::AccountId, T::Hash, T::Balance> for Module<T> {
+    fn on_trading_fee(fee_account: &T::AccountId, asset: T::Hash, amount: T::Balance) {
+        if let Err(e) = Self::split_fee(fee_account, asset, amount) {
+            support::debug::warn!("trading fee split failed for {:?}: {:?}", asset, e);
+        }
+    }
+}
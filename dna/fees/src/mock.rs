@@ -0,0 +1,191 @@
+//! Test runtime of the fees module.
+
+use super::*;
+use primitives::H256;
+use sp_runtime::{
+    testing::{Header, TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+use std::cell::RefCell;
+use support::{impl_outer_origin, parameter_types, weights::Weight};
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = system {}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 1;
+}
+
+impl balances::Trait for Test {
+    type Balance = u128;
+    type DustRemoval = ();
+    type Event = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = system::Module<Test>;
+}
+
+impl assets::Trait for Test {
+    type Event = ();
+}
+
+parameter_types! {
+    pub const PriceFactor: u128 = 1;
+    pub const BlocksPerDay: u32 = 6 * 60 * 24;
+    pub const OpenedOrdersArrayCap: u8 = 20;
+    pub const CommitRevealWindow: u64 = 10;
+    pub const UnrevealedCommitmentPenalty: Perbill = Perbill::from_percent(10);
+    pub const OrderGroupCap: u8 = 4;
+    pub const OrderDeposit: u128 = 10;
+    pub const StopOrdersCap: u32 = 4;
+    pub const TwapObservationPeriod: u64 = 10;
+    pub const TwapObservationCap: u32 = 10;
+    pub const ListingBond: u128 = 1_000;
+    pub const TradingFee: Permill = Permill::zero();
+}
+
+impl dex::Trait for Test {
+    type Event = ();
+    type Price = u128;
+    type PriceFactor = PriceFactor;
+    type BlocksPerDay = BlocksPerDay;
+    type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
+    type GovernanceOrigin = system::EnsureRoot<u64>;
+    type CommitRevealWindow = CommitRevealWindow;
+    type UnrevealedCommitmentPenalty = UnrevealedCommitmentPenalty;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type OrderGroupCap = OrderGroupCap;
+    type OrderDeposit = OrderDeposit;
+    type StopOrdersCap = StopOrdersCap;
+    type TwapObservationPeriod = TwapObservationPeriod;
+    type TwapObservationCap = TwapObservationCap;
+    type ReferencePrice = ();
+    type ListingBond = ListingBond;
+    type ListingOrigin = system::EnsureRoot<u64>;
+    type ListingSlash = ();
+    type TradingFee = TradingFee;
+    type OnTradingFee = Fees;
+}
+
+parameter_types! {
+    pub const TreasuryAccount: u64 = 99;
+    pub const MaxAssetStakers: u32 = 10;
+    pub const RewardPayoutsPerBlock: u32 = 2;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type TreasuryAccount = TreasuryAccount;
+    type Stakers = TestStakers;
+    type MaxAssetStakers = MaxAssetStakers;
+    type RewardPayoutsPerBlock = RewardPayoutsPerBlock;
+}
+
+thread_local! {
+    static ERA: RefCell<u32> = RefCell::new(0);
+    static PAGES_READ: RefCell<u32> = RefCell::new(0);
+}
+
+/// Accounts 10 to 14 staking 100 each, 200 for the last one, on pages of three, none and two
+/// stakers.
+pub struct TestStakers;
+
+impl EraStakers<u64, u128> for TestStakers {
+    fn current_era() -> u32 {
+        ERA.with(|era| *era.borrow())
+    }
+
+    fn total_stake(_era: u32) -> u128 {
+        600
+    }
+
+    fn pages(_era: u32) -> u32 {
+        3
+    }
+
+    fn stakers(_era: u32, page: u32) -> Vec<(u64, u128)> {
+        PAGES_READ.with(|x| *x.borrow_mut() += 1);
+        match page {
+            0 => vec![(10, 100), (11, 100), (12, 100)],
+            2 => vec![(13, 100), (14, 200)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Pages of stakers read so far.
+pub fn pages_read() -> u32 {
+    PAGES_READ.with(|x| *x.borrow())
+}
+
+pub fn start_era(era: u32) {
+    ERA.with(|x| *x.borrow_mut() = era);
+}
+
+pub type Assets = assets::Module<Test>;
+pub type Fees = Module<Test>;
+
+pub const ALICE: u64 = 1;
+pub const TREASURY: u64 = 99;
+
+/// Hash of the genesis asset `FEE` fees are paid in.
+pub fn fee_asset() -> H256 {
+    Assets::genesis_asset_hash(b"FEE")
+}
+
+/// Alice owns `FEE`, half of the fees go to the Treasury.
+pub fn new_test_ext() -> runtime_io::TestExternalities {
+    start_era(0);
+    PAGES_READ.with(|x| *x.borrow_mut() = 0);
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+    assets::GenesisConfig::<Test> {
+        assets: vec![(ALICE, b"FEE".to_vec(), 1_000_000, vec![])],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    GenesisConfig {
+        treasury_share: Permill::from_percent(50),
+    }
+    .assimilate_storage::<Test>(&mut t)
+    .unwrap();
+
+    let mut ext = runtime_io::TestExternalities::new(t);
+    ext.execute_with(|| system::Module::<Test>::set_block_number(1));
+    ext
+}
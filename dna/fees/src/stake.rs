@@ -0,0 +1,87 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    pub fn do_set_reward_target(target: RewardTarget<T::Hash>) -> DispatchResult {
+        <dex::Module<T>>::transactional(|| {
+            // stakes of an asset no longer rewarded are released
+            if let RewardTarget::AssetHolders(asset) = Self::reward_target() {
+                if target != RewardTarget::AssetHolders(asset) {
+                    for (who, stake) in <AssetStakes<T>>::take() {
+                        <assets::Module<T>>::unfreeze(who.clone(), asset, stake)?;
+                        Self::deposit_event(RawEvent::RewardAssetUnstaked(who, asset, stake));
+                    }
+                }
+            }
+
+            <CurrentRewardTarget<T>>::put(target);
+
+            Self::deposit_event(RawEvent::RewardTargetSet(target));
+
+            Ok(())
+        })
+    }
+
+    pub fn do_stake_reward_asset(sender: T::AccountId, amount: T::Balance) -> DispatchResult {
+        ensure!(amount > Zero::zero(), Error::<T>::ZeroAmount);
+        let asset = Self::reward_asset()?;
+
+        <dex::Module<T>>::transactional(|| {
+            let mut stakes = Self::asset_stakes();
+
+            match stakes.iter_mut().find(|(who, _)| *who == sender) {
+                Some((_, stake)) => {
+                    *stake = stake
+                        .checked_add(&amount)
+                        .ok_or(Error::<T>::OverflowError)?
+                }
+                None => {
+                    ensure!(
+                        stakes.len() < T::MaxAssetStakers::get() as usize,
+                        Error::<T>::TooManyAssetStakers
+                    );
+                    stakes.push((sender.clone(), amount));
+                }
+            }
+
+            <assets::Module<T>>::freeze(sender.clone(), asset, amount)?;
+            <AssetStakes<T>>::put(stakes);
+
+            Self::deposit_event(RawEvent::RewardAssetStaked(sender, asset, amount));
+
+            Ok(())
+        })
+    }
+
+    pub fn do_unstake_reward_asset(sender: T::AccountId, amount: T::Balance) -> DispatchResult {
+        ensure!(amount > Zero::zero(), Error::<T>::ZeroAmount);
+        let asset = Self::reward_asset()?;
+
+        <dex::Module<T>>::transactional(|| {
+            let mut stakes = Self::asset_stakes();
+
+            let stake = stakes
+                .iter_mut()
+                .find(|(who, _)| *who == sender)
+                .map(|(_, stake)| stake)
+                .ok_or(Error::<T>::StakeNotEnough)?;
+            *stake = stake
+                .checked_sub(&amount)
+                .ok_or(Error::<T>::StakeNotEnough)?;
+            stakes.retain(|(_, stake)| *stake != Zero::zero());
+
+            <assets::Module<T>>::unfreeze(sender.clone(), asset, amount)?;
+            <AssetStakes<T>>::put(stakes);
+
+            Self::deposit_event(RawEvent::RewardAssetUnstaked(sender, asset, amount));
+
+            Ok(())
+        })
+    }
+
+    fn reward_asset() -> result::Result<T::Hash, DispatchError> {
+        match Self::reward_target() {
+            RewardTarget::AssetHolders(asset) => Ok(asset),
+            RewardTarget::Stakers => Err(Error::<T>::NoRewardAsset.into()),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Tests of the fees module.
+
+use crate::mock::*;
+use crate::RewardTarget;
+use support::assert_ok;
+
+fn balance(who: u64) -> u128 {
+    Assets::free_balance_of((who, fee_asset()))
+}
+
+/// Collect a trading fee of `amount`, half of it goes to the reward pot.
+fn collect_fee(amount: u128) {
+    assert_ok!(Assets::transfer(ALICE, fee_asset(), 2, amount));
+    assert_ok!(Fees::split_fee(&2, fee_asset(), amount));
+}
+
+#[test]
+fn reward_pot_is_paid_out_a_chunk_of_recipients_a_block() {
+    new_test_ext().execute_with(|| {
+        collect_fee(2_400);
+        assert_eq!(balance(TREASURY), 1_200);
+        assert_eq!(balance(Fees::pot_account()), 1_200);
+
+        start_era(1);
+        Fees::advance_reward_payout();
+
+        assert_eq!((balance(10), balance(11), balance(12)), (200, 200, 0));
+        let payout = Fees::reward_payout().unwrap();
+        assert_eq!((payout.next_page, payout.next_recipient), (0, 2));
+        assert_eq!(pages_read(), 1);
+
+        // the empty page counts towards the pages read in a block
+        Fees::advance_reward_payout();
+        assert_eq!((balance(12), balance(13)), (200, 0));
+        let payout = Fees::reward_payout().unwrap();
+        assert_eq!((payout.next_page, payout.next_recipient), (2, 0));
+        assert_eq!(pages_read(), 3);
+
+        Fees::advance_reward_payout();
+        assert_eq!((balance(13), balance(14)), (200, 400));
+        assert!(Fees::reward_payout().is_none());
+        assert_eq!(pages_read(), 4);
+        assert_eq!(balance(Fees::pot_account()), 0);
+        assert!(Fees::pot_assets().is_empty());
+
+        // nothing left to pay until the next era
+        Fees::advance_reward_payout();
+        assert_eq!(balance(10), 200);
+    });
+}
+
+#[test]
+fn next_era_waits_for_the_payout_in_progress() {
+    new_test_ext().execute_with(|| {
+        collect_fee(2_400);
+        start_era(1);
+        Fees::advance_reward_payout();
+
+        // fees of the next era arrive while the first payout goes on
+        start_era(2);
+        collect_fee(1_200);
+        Fees::advance_reward_payout();
+        Fees::advance_reward_payout();
+
+        assert_eq!(Fees::last_era(), 1);
+        assert_eq!(balance(14), 400);
+        assert_eq!(balance(Fees::pot_account()), 600);
+        assert_eq!(Fees::pot_assets(), vec![fee_asset()]);
+
+        Fees::advance_reward_payout();
+
+        assert_eq!(Fees::last_era(), 2);
+        assert_eq!((balance(10), balance(11)), (300, 300));
+    });
+}
+
+#[test]
+fn asset_stakes_are_paid_out_as_they_were_when_the_payout_started() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Fees::do_set_reward_target(RewardTarget::AssetHolders(fee_asset())));
+        for who in 20..23 {
+            assert_ok!(Assets::transfer(ALICE, fee_asset(), who, 100));
+            assert_ok!(Fees::do_stake_reward_asset(who, 100));
+        }
+
+        collect_fee(1_200);
+        start_era(1);
+        Fees::advance_reward_payout();
+
+        assert_eq!((balance(20), balance(21), balance(22)), (200, 200, 0));
+        assert!(Fees::payout_asset_stakes(0).is_empty());
+        assert_eq!(Fees::payout_asset_stakes(1), vec![(22, 100)]);
+
+        // unstaking does not change the payout in progress
+        assert_ok!(Fees::do_unstake_reward_asset(22, 100));
+        Fees::advance_reward_payout();
+
+        assert_eq!(balance(22), 300);
+        assert!(Fees::reward_payout().is_none());
+        assert!(Fees::payout_asset_stakes(1).is_empty());
+        assert_eq!(pages_read(), 0);
+    });
+}
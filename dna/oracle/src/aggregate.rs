@@ -0,0 +1,67 @@
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Replace the oracle's report of a feed and aggregate the fresh reports of the feed.
+    pub fn do_report_price(oracle: T::AccountId, feed: T::Hash, price: T::Price) -> DispatchResult {
+        ensure!(
+            Self::feed_endpoints().iter().any(|(hash, _)| *hash == feed),
+            Error::<T>::NoMatchingFeed
+        );
+        ensure!(!price.is_zero(), Error::<T>::ZeroPrice);
+
+        let mut reports = Self::reports(feed);
+        reports.retain(|report| report.oracle != oracle);
+        reports.push(PriceReport {
+            oracle: oracle.clone(),
+            price,
+            reported_at: <system::Module<T>>::block_number(),
+        });
+
+        Self::deposit_event(RawEvent::PriceReported(oracle, feed, price));
+        Self::aggregate(feed, reports);
+
+        Ok(())
+    }
+
+    /// Store the median of the fresh reports of current oracles as the price of a feed, as
+    /// fresh as its latest report. A feed left without such reports has no price.
+    pub fn aggregate(
+        feed: T::Hash,
+        mut reports: Vec<PriceReport<T::AccountId, T::Price, T::BlockNumber>>,
+    ) {
+        let now = <system::Module<T>>::block_number();
+        let oracles = Self::oracles();
+        reports.retain(|report| {
+            oracles.contains(&report.oracle) && now < report.reported_at + T::ReportLifetime::get()
+        });
+
+        let reported_at = match reports.iter().map(|report| report.reported_at).max() {
+            Some(reported_at) => reported_at,
+            None => {
+                <Reports<T>>::remove(feed);
+                <Prices<T>>::remove(feed);
+                return;
+            }
+        };
+
+        let median = Self::median(reports.iter().map(|report| report.price).collect());
+        <Reports<T>>::insert(feed, reports);
+        <Prices<T>>::insert(feed, (median, reported_at));
+
+        Self::deposit_event(RawEvent::PriceAggregated(feed, median));
+    }
+
+    /// Middle price of a non-empty list, the mean of the two middle prices for an even count.
+    pub fn median(mut prices: Vec<T::Price>) -> T::Price {
+        prices.sort();
+
+        let mid = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            return prices[mid];
+        }
+
+        let two = T::Price::from(2u128);
+        let (low, high) = (prices[mid - 1], prices[mid]);
+        low / two + high / two + (low % two + high % two) / two
+    }
+}
@@ -0,0 +1,65 @@
+use super::*;
+use runtime_io::offchain;
+use sp_runtime::offchain::{http, Duration};
+
+/// Time an endpoint has to answer, in milliseconds.
+const FETCH_TIMEOUT: u64 = 2_000;
+
+impl<T: Trait> Module<T> {
+    /// Fetch the price of every feed and submit them with each oracle key of the node.
+    pub fn fetch_and_report_prices() -> Result<(), &'static str> {
+        if !T::SubmitTransaction::can_sign() {
+            // not an oracle node
+            return Ok(());
+        }
+
+        let mut prices = Vec::new();
+        for (feed, url) in Self::feed_endpoints() {
+            match Self::fetch_price(&url) {
+                Ok(price) => prices.push((feed, price)),
+                Err(e) => debug::warn!("fetching the price of {:?} failed: {:?}", feed, e),
+            }
+        }
+        if prices.is_empty() {
+            return Ok(());
+        }
+
+        let results = T::SubmitTransaction::submit_signed(Call::report_prices(prices));
+        for (account, result) in results {
+            if result.is_err() {
+                debug::warn!("submitting the price report of {:?} failed", account);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// GET the endpoint and read its body as the price.
+    pub fn fetch_price(url: &[u8]) -> Result<T::Price, http::Error> {
+        let url = rstd::str::from_utf8(url).map_err(|_| http::Error::Unknown)?;
+        let deadline = offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT));
+
+        let pending = http::Request::get(url)
+            .deadline(deadline)
+            .send()
+            .map_err(|_| http::Error::IoError)?;
+        let response = pending
+            .try_wait(deadline)
+            .map_err(|_| http::Error::DeadlineReached)??;
+        if response.code != 200 {
+            return Err(http::Error::Unknown);
+        }
+
+        Self::parse_price(&response.body().collect::<Vec<u8>>()).ok_or(http::Error::Unknown)
+    }
+
+    /// A decimal integer, surrounding whitespace is ignored.
+    pub fn parse_price(body: &[u8]) -> Option<T::Price> {
+        let body = rstd::str::from_utf8(body).ok()?.trim();
+        if body.is_empty() || !body.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        body.parse::<u128>().ok().map(T::Price::from)
+    }
+}
@@ -0,0 +1,190 @@
+//! # Oracle module
+//!
+//! Reference prices reported by authorized oracle accounts. The offchain worker of a node
+//! holding an oracle key fetches the price of each feed from its HTTP endpoint and submits
+//! them in a signed transaction, the price of a feed is the median of the fresh reports.
+//!
+//! An endpoint answers a GET request with the price as a decimal integer, scaled like the
+//! prices of the exchange module. `scripts/oracle` has a local stand-in for the endpoints.
+//! A node reports with the keys of the `orcl` key type in its keystore.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The above line is needed to compile the Wasm binaries.
+
+use codec::{Decode, Encode};
+use primitives::crypto::KeyTypeId;
+use rstd::prelude::*;
+use sp_runtime::traits::{AtLeast32Bit, Member, Zero};
+use sp_runtime::RuntimeDebug;
+use support::{
+    debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+    traits::{EnsureOrigin, Get},
+    Parameter, StorageMap, StorageValue,
+};
+use system::{ensure_signed, offchain::SubmitSignedTransaction};
+
+mod aggregate;
+mod fetch;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+/// Key type of the oracle keys in a node's keystore.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::app_crypto::{app_crypto, sr25519};
+    app_crypto!(sr25519, KEY_TYPE);
+}
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Call: From<Call<Self>>;
+    type Price: Parameter + Default + Member + AtLeast32Bit + Copy + From<u128> + Into<u128>;
+    /// Signs and submits the price reports of the offchain worker
+    type SubmitTransaction: SubmitSignedTransaction<Self, <Self as Trait>::Call>;
+    /// Origin allowed to change the oracle accounts and feed endpoints
+    type GovernanceOrigin: EnsureOrigin<Self::Origin>;
+    /// Number of blocks between two fetches of the offchain worker
+    type FetchInterval: Get<Self::BlockNumber>;
+    /// Number of blocks a report counts towards the price of its feed
+    type ReportLifetime: Get<Self::BlockNumber>;
+}
+
+/// A price reported by an oracle account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceReport<AccountId, Price, BlockNumber> {
+    pub oracle: AccountId,
+    pub price: Price,
+    pub reported_at: BlockNumber,
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// Sender is not an oracle account
+        NotAnOracle,
+        /// No endpoint is set for the feed
+        NoMatchingFeed,
+        /// Price must be above zero
+        ZeroPrice,
+    }
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		Price = <T as Trait>::Price,
+	{
+		// (oracle accounts)
+		OraclesSet(Vec<AccountId>),
+
+		// (feedHash, endpoint url or None when removed)
+		FeedEndpointSet(Hash, Option<Vec<u8>>),
+
+		// (oracle, feedHash, price)
+		PriceReported(AccountId, Hash, Price),
+
+		// (feedHash, median of the fresh reports)
+		PriceAggregated(Hash, Price),
+	}
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as OracleStorage {
+        /// Accounts allowed to report prices
+        pub Oracles get(fn oracles): Vec<T::AccountId>;
+        /// (FeedHash, endpoint url) of the feeds the offchain worker fetches
+        pub FeedEndpoints get(fn feed_endpoints): Vec<(T::Hash, Vec<u8>)>;
+        /// FeedHash => latest report of each oracle
+        pub Reports get(fn reports): map hasher(blake2_256) T::Hash => Vec<PriceReport<T::AccountId, T::Price, T::BlockNumber>>;
+        /// FeedHash => (median of the fresh reports, BlockNumber of the latest of them)
+        pub Prices get(fn aggregated_price): map hasher(blake2_256) T::Hash => Option<(T::Price, T::BlockNumber)>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event() = default;
+
+        type Error = Error<T>;
+
+        /// # Report the prices of feeds
+        /// * `origin` - oracle account
+        /// * `prices` - (feed hash, price) of each reported feed
+        pub fn report_prices(origin, prices: Vec<(T::Hash, T::Price)>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::oracles().contains(&sender), Error::<T>::NotAnOracle);
+
+            for (feed, price) in prices {
+                Self::do_report_price(sender.clone(), feed, price)?;
+            }
+
+            Ok(())
+        }
+
+        /// # Set the accounts allowed to report prices
+        /// * `origin` - governance origin
+        /// * `oracles` - oracle accounts, replacing the current ones
+        pub fn set_oracles(origin, oracles: Vec<T::AccountId>) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            <Oracles<T>>::put(oracles.clone());
+            Self::deposit_event(RawEvent::OraclesSet(oracles));
+
+            // reports of removed oracles no longer count
+            for (feed, _) in Self::feed_endpoints() {
+                Self::aggregate(feed, Self::reports(feed));
+            }
+
+            Ok(())
+        }
+
+        /// # Set the HTTP endpoint the price of a feed is fetched from
+        /// * `origin` - governance origin
+        /// * `feed` - feed hash, the hash of an exchange pair for its reference price
+        /// * `url` - endpoint url, `None` stops fetching the feed
+        pub fn set_feed_endpoint(origin, feed: T::Hash, url: Option<Vec<u8>>) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            <FeedEndpoints<T>>::mutate(|feeds| {
+                feeds.retain(|(hash, _)| *hash != feed);
+                if let Some(url) = url.clone() {
+                    feeds.push((feed, url));
+                }
+            });
+            if url.is_none() {
+                <Reports<T>>::remove(feed);
+                <Prices<T>>::remove(feed);
+            }
+
+            Self::deposit_event(RawEvent::FeedEndpointSet(feed, url));
+
+            Ok(())
+        }
+
+        fn offchain_worker(n: T::BlockNumber) {
+            if (n % T::FetchInterval::get()).is_zero() {
+                if let Err(e) = Self::fetch_and_report_prices() {
+                    debug::warn!("oracle price report failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Price of a feed, `None` when no oracle reported it within `ReportLifetime` blocks.
+    pub fn price(feed: T::Hash) -> Option<T::Price> {
+        let (price, aggregated_at) = Self::aggregated_price(feed)?;
+        let now = <system::Module<T>>::block_number();
+
+        if now >= aggregated_at + T::ReportLifetime::get() {
+            return None;
+        }
+        Some(price)
+    }
+}
@@ -0,0 +1,117 @@
+//! Test runtime of the oracle module.
+
+use super::*;
+use primitives::{sr25519, H256};
+use sp_runtime::{
+    testing::{Header, TestXt},
+    traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup},
+    Perbill,
+};
+use support::{impl_outer_origin, parameter_types, weights::Weight};
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = system {}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = sr25519::Public;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+type Extrinsic = TestXt<Call<Test>, ()>;
+
+impl system::offchain::CreateTransaction<Test, Extrinsic> for Test {
+    type Public = sr25519::Public;
+    type Signature = sr25519::Signature;
+
+    fn create_transaction<F: system::offchain::Signer<Self::Public, Self::Signature>>(
+        call: Call<Test>,
+        _public: Self::Public,
+        _account: <Test as system::Trait>::AccountId,
+        nonce: u64,
+    ) -> Option<(Call<Test>, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+        Some((call, (nonce, ())))
+    }
+}
+
+parameter_types! {
+    pub const FetchInterval: u64 = 10;
+    pub const ReportLifetime: u64 = 10;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type Call = Call<Test>;
+    type Price = u128;
+    type SubmitTransaction =
+        system::offchain::TransactionSubmitter<crypto::Public, Test, Extrinsic>;
+    type GovernanceOrigin = system::EnsureRoot<sr25519::Public>;
+    type FetchInterval = FetchInterval;
+    type ReportLifetime = ReportLifetime;
+}
+
+pub type System = system::Module<Test>;
+pub type Oracle = Module<Test>;
+
+pub const URL: &[u8] = b"http://localhost:8000/price";
+
+pub fn account(seed: u8) -> sr25519::Public {
+    sr25519::Public::from_raw([seed; 32])
+}
+
+pub fn alice() -> sr25519::Public {
+    account(1)
+}
+
+pub fn bob() -> sr25519::Public {
+    account(2)
+}
+
+pub fn charlie() -> sr25519::Public {
+    account(3)
+}
+
+/// Hash of the one feed, fetched from `URL`.
+pub fn feed() -> H256 {
+    H256::repeat_byte(1)
+}
+
+/// Alice, Bob and Charlie are the oracles of the feed.
+pub fn new_test_ext() -> runtime_io::TestExternalities {
+    let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+    let mut ext = runtime_io::TestExternalities::new(t);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        Oracle::set_oracles(Origin::ROOT, vec![alice(), bob(), charlie()]).unwrap();
+        Oracle::set_feed_endpoint(Origin::ROOT, feed(), Some(URL.to_vec())).unwrap();
+    });
+    ext
+}
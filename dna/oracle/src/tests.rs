@@ -0,0 +1,167 @@
+//! Tests of the oracle module.
+
+use super::*;
+use crate::mock::*;
+use primitives::offchain::{testing, OffchainExt};
+use primitives::H256;
+use sp_runtime::offchain::http;
+use support::{assert_noop, assert_ok, dispatch::DispatchError};
+
+fn report(oracle: <Test as system::Trait>::AccountId, price: u128) {
+    assert_ok!(Oracle::report_prices(Origin::signed(oracle), vec![(feed(), price)]));
+}
+
+/// A test externality whose one HTTP request, a GET of `URL`, is answered with `body`.
+fn offchain_ext(body: &[u8]) -> runtime_io::TestExternalities {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    state.write().expect_request(
+        0,
+        testing::PendingRequest {
+            method: "GET".into(),
+            uri: rstd::str::from_utf8(URL).unwrap().into(),
+            response: Some(body.to_vec()),
+            sent: true,
+            ..Default::default()
+        },
+    );
+
+    let mut ext = new_test_ext();
+    ext.register_extension(OffchainExt::new(offchain));
+    ext
+}
+
+#[test]
+fn fetch_price_reads_the_body_of_the_endpoint() {
+    offchain_ext(b"15523\n").execute_with(|| {
+        assert_eq!(Oracle::fetch_price(URL), Ok(15523));
+    });
+}
+
+#[test]
+fn fetch_price_rejects_a_body_that_is_no_price() {
+    offchain_ext(br#"{"USD": 155.23}"#).execute_with(|| {
+        assert_eq!(Oracle::fetch_price(URL), Err(http::Error::Unknown));
+        // an url that is no utf8 is not requested
+        assert_eq!(Oracle::fetch_price(&[0xff, 0xfe]), Err(http::Error::Unknown));
+    });
+}
+
+#[test]
+fn parse_price_takes_a_decimal_integer() {
+    assert_eq!(Oracle::parse_price(b"42"), Some(42));
+    assert_eq!(Oracle::parse_price(b" 42\r\n"), Some(42));
+    assert_eq!(
+        Oracle::parse_price(b"340282366920938463463374607431768211455"),
+        Some(u128::max_value())
+    );
+
+    assert_eq!(Oracle::parse_price(b""), None);
+    assert_eq!(Oracle::parse_price(b"  "), None);
+    assert_eq!(Oracle::parse_price(b"1.5"), None);
+    assert_eq!(Oracle::parse_price(b"-1"), None);
+    assert_eq!(Oracle::parse_price(b"+1"), None);
+    assert_eq!(Oracle::parse_price(b"1 2"), None);
+    assert_eq!(Oracle::parse_price(b"340282366920938463463374607431768211456"), None);
+    assert_eq!(Oracle::parse_price(&[0xff]), None);
+}
+
+#[test]
+fn median_of_odd_and_even_counts() {
+    assert_eq!(Oracle::median(vec![7]), 7);
+    assert_eq!(Oracle::median(vec![3, 1, 2]), 2);
+    assert_eq!(Oracle::median(vec![4, 1, 3, 2]), 2);
+    assert_eq!(Oracle::median(vec![3, 5]), 4);
+    assert_eq!(Oracle::median(vec![u128::max_value(), u128::max_value()]), u128::max_value());
+    assert_eq!(
+        Oracle::median(vec![u128::max_value() - 1, u128::max_value()]),
+        u128::max_value() - 1
+    );
+}
+
+#[test]
+fn only_oracles_report_positive_prices_of_known_feeds() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Oracle::report_prices(Origin::signed(account(9)), vec![(feed(), 100)]),
+            Error::<Test>::NotAnOracle
+        );
+        assert_noop!(
+            Oracle::report_prices(Origin::signed(alice()), vec![(H256::repeat_byte(2), 100)]),
+            Error::<Test>::NoMatchingFeed
+        );
+        assert_noop!(
+            Oracle::report_prices(Origin::signed(alice()), vec![(feed(), 0)]),
+            Error::<Test>::ZeroPrice
+        );
+        assert_noop!(
+            Oracle::set_oracles(Origin::signed(alice()), vec![alice()]),
+            DispatchError::BadOrigin
+        );
+
+        report(alice(), 100);
+
+        assert_eq!(Oracle::reports(feed()).len(), 1);
+        assert_eq!(Oracle::price(feed()), Some(100));
+    });
+}
+
+#[test]
+fn price_is_the_median_of_the_latest_reports() {
+    new_test_ext().execute_with(|| {
+        report(alice(), 100);
+        report(bob(), 300);
+        report(charlie(), 200);
+        assert_eq!(Oracle::price(feed()), Some(200));
+
+        // a new report replaces the oracle's last one
+        report(alice(), 400);
+        assert_eq!(Oracle::reports(feed()).len(), 3);
+        assert_eq!(Oracle::price(feed()), Some(300));
+
+        report(charlie(), 100);
+        assert_eq!(Oracle::price(feed()), Some(300));
+    });
+}
+
+#[test]
+fn stale_reports_no_longer_count() {
+    new_test_ext().execute_with(|| {
+        report(alice(), 100);
+        System::set_block_number(5);
+        report(bob(), 300);
+
+        // Alice's report is as old as the lifetime
+        System::set_block_number(11);
+        report(charlie(), 200);
+
+        assert_eq!(Oracle::reports(feed()).len(), 2);
+        assert_eq!(Oracle::aggregated_price(feed()), Some((250, 11)));
+
+        System::set_block_number(20);
+        assert_eq!(Oracle::price(feed()), Some(250));
+        System::set_block_number(21);
+        assert_eq!(Oracle::price(feed()), None);
+    });
+}
+
+#[test]
+fn changing_the_oracles_aggregates_the_prices_again() {
+    new_test_ext().execute_with(|| {
+        report(alice(), 100);
+        report(bob(), 300);
+        report(charlie(), 200);
+
+        System::set_block_number(5);
+        assert_ok!(Oracle::set_oracles(Origin::ROOT, vec![alice(), bob()]));
+
+        // as fresh as the reports left, not the change of oracles
+        assert_eq!(Oracle::reports(feed()).len(), 2);
+        assert_eq!(Oracle::aggregated_price(feed()), Some((200, 1)));
+
+        assert_ok!(Oracle::set_oracles(Origin::ROOT, vec![charlie()]));
+
+        assert!(Oracle::reports(feed()).is_empty());
+        assert_eq!(Oracle::aggregated_price(feed()), None);
+        assert_eq!(Oracle::price(feed()), None);
+    });
+}
@@ -3,13 +3,15 @@ use babe_primitives::AuthorityId as BabeId;
 use grandpa::AuthorityId as GrandpaId;
 use hex_literal::hex;
 use im_online::sr25519::AuthorityId as ImOnlineId;
-use node_primitives::{AccountId, Balance, Signature};
+use dex::{CircuitBreaker, MatchingMode};
+use node_primitives::{AccountId, Balance, BlockNumber, Signature};
 use node_runtime::constants::currency::*;
 use node_runtime::Block;
 use node_runtime::{
-    AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, CouncilConfig, DemocracyConfig,
-    GenesisConfig, GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys,
-    StakerStatus, StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
+    AssetsConfig, AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, CouncilConfig,
+    DemocracyConfig, DexConfig, FeesConfig, GenesisConfig, GrandpaConfig, ImOnlineConfig,
+    IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig, SudoConfig,
+    SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
 };
 use primitives::crypto::UncheckedInto;
 use primitives::{sr25519, Pair, Public};
@@ -17,7 +19,7 @@ use sc_chain_spec::ChainSpecExtension;
 use serde::{Deserialize, Serialize};
 use sp_runtime::{
     traits::{IdentifyAccount, Verify},
-    Perbill,
+    Perbill, Permill,
 };
 
 use telemetry::TelemetryEndpoints;
@@ -112,6 +114,66 @@ fn session_keys(
     }
 }
 
+/// Demo tokens owned by `owner`, with a part of each supply held by every endowed account
+fn demo_assets(
+    owner: &AccountId,
+    endowed_accounts: &[AccountId],
+) -> Vec<(AccountId, Vec<u8>, Balance, Vec<(AccountId, Balance)>)> {
+    const SUPPLY: Balance = 1_000_000_000_000;
+    const HOLDING: Balance = 10_000_000_000;
+
+    let holders: Vec<(AccountId, Balance)> = endowed_accounts
+        .iter()
+        .filter(|account| *account != owner)
+        .map(|account| (account.clone(), HOLDING))
+        .collect();
+
+    vec![b"USDT".to_vec(), b"BTC".to_vec(), b"ETH".to_vec()]
+        .into_iter()
+        .map(|symbol| (owner.clone(), symbol, SUPPLY, holders.clone()))
+        .collect()
+}
+
+/// Demo markets of the demo tokens, created by their owner
+fn demo_exchange_pairs(
+    owner: &AccountId,
+) -> Vec<(
+    AccountId,
+    Vec<u8>,
+    Vec<u8>,
+    MatchingMode,
+    Option<CircuitBreaker<BlockNumber>>,
+)> {
+    vec![
+        (
+            owner.clone(),
+            b"USDT".to_vec(),
+            b"BTC".to_vec(),
+            MatchingMode::Continuous,
+            Some(CircuitBreaker {
+                band_bps: 1_000,
+                halt_move_bps: 2_000,
+                window: 100,
+                cooldown: 50,
+            }),
+        ),
+        (
+            owner.clone(),
+            b"USDT".to_vec(),
+            b"ETH".to_vec(),
+            MatchingMode::Continuous,
+            None,
+        ),
+        (
+            owner.clone(),
+            b"BTC".to_vec(),
+            b"ETH".to_vec(),
+            MatchingMode::BatchAuction,
+            None,
+        ),
+    ]
+}
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
     initial_authorities: Vec<(
@@ -123,6 +185,7 @@ pub fn testnet_genesis(
         AuthorityDiscoveryId,
     )>,
     endowed_accounts: Option<Vec<AccountId>>,
+    demo_markets: bool,
 ) -> GenesisConfig {
     let endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
         vec![
@@ -144,6 +207,16 @@ pub fn testnet_genesis(
     const ENDOWMENT: Balance = 100 * DNA;
     const STASH: Balance = 1_000 * DNA;
 
+    let (assets, exchange_pairs) = if demo_markets {
+        let owner = &endowed_accounts[0];
+        (
+            demo_assets(owner, &endowed_accounts),
+            demo_exchange_pairs(owner),
+        )
+    } else {
+        (vec![], vec![])
+    };
+
     GenesisConfig {
         system: Some(SystemConfig {
             code: WASM_BINARY.to_vec(),
@@ -202,6 +275,11 @@ pub fn testnet_genesis(
         }),
         im_online: Some(ImOnlineConfig { keys: vec![] }),
         authority_discovery: Some(AuthorityDiscoveryConfig { keys: vec![] }),
+        assets: Some(AssetsConfig { assets }),
+        dex: Some(DexConfig { exchange_pairs }),
+        fees: Some(FeesConfig {
+            treasury_share: Permill::from_percent(50),
+        }),
     }
 }
 
@@ -264,7 +342,7 @@ fn dna_config_genesis() -> GenesisConfig {
 
     //    info!( "Hello --------------------------------------------------");
 
-    testnet_genesis(initial_authorities, Some(endowed_accounts))
+    testnet_genesis(initial_authorities, Some(endowed_accounts), false)
 }
 
 // pub fn dna_testnet_config() -> ChainSpec {
@@ -295,7 +373,7 @@ pub fn dna_testnet_config() -> ChainSpec {
 }
 
 fn development_config_genesis() -> GenesisConfig {
-    testnet_genesis(vec![get_authority_keys_from_seed("Alice")], None)
+    testnet_genesis(vec![get_authority_keys_from_seed("Alice")], None, true)
 }
 
 /// Development config (single validator Alice)
@@ -319,6 +397,7 @@ fn local_testnet_genesis() -> GenesisConfig {
             get_authority_keys_from_seed("Bob"),
         ],
         Some(vec![get_account_id_from_seed::<sr25519::Public>("Alice")]),
+        true,
     )
 }
 
@@ -335,3 +414,53 @@ pub fn local_testnet_config() -> ChainSpec {
         Default::default(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use node_runtime::{Assets, Dex};
+    use sp_runtime::BuildStorage;
+
+    /// Build the genesis storage of a spec and check that the demo markets are in it when the
+    /// spec has them, and that the exchange storage starts at the latest layout.
+    fn assert_genesis(spec: ChainSpec, demo_markets: bool) {
+        let storage = spec.build_storage().expect("genesis storage builds");
+
+        sp_io::TestExternalities::new(storage).execute_with(|| {
+            assert_eq!(Dex::storage_version(), dex::Releases::LATEST);
+
+            let owner = get_account_id_from_seed::<sr25519::Public>("Alice");
+            for (_, base, quote, matching_mode, _) in demo_exchange_pairs(&owner) {
+                let base = Assets::genesis_asset_hash(&base);
+                let quote = Assets::genesis_asset_hash(&quote);
+                let ep_hash = Dex::exchange_pair_hash_by_base_quote(base, quote);
+                if !demo_markets {
+                    assert!(Assets::asset(base).is_none());
+                    assert!(ep_hash.is_none());
+                    continue;
+                }
+
+                assert_eq!(Assets::owner(base), Some(owner.clone()));
+                assert_eq!(Assets::owner(quote), Some(owner.clone()));
+                let ep = Dex::exchange_pair(ep_hash.expect("demo exchange pair")).unwrap();
+                assert_eq!((ep.base, ep.quote), (base, quote));
+                assert_eq!(ep.matching_mode, matching_mode);
+            }
+        });
+    }
+
+    #[test]
+    fn development_spec_builds_with_demo_markets() {
+        assert_genesis(development_config(), true);
+    }
+
+    #[test]
+    fn local_testnet_spec_builds_with_demo_markets() {
+        assert_genesis(local_testnet_config(), true);
+    }
+
+    #[test]
+    fn dna_testnet_spec_builds_without_demo_markets() {
+        assert_genesis(dna_testnet_config(), false);
+    }
+}
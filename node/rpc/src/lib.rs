@@ -31,7 +31,7 @@
 
 use std::{fmt, sync::Arc};
 
-use node_primitives::{AccountId, Balance, Block, BlockNumber, Index};
+use node_primitives::{AccountId, Balance, Block, BlockNumber, Hash, Index};
 use node_runtime::UncheckedExtrinsic;
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRPCHandler;
@@ -91,6 +91,8 @@ where
         UncheckedExtrinsic,
     >,
     C::Api: BabeApi<Block>,
+    C::Api: dex_rpc::ExchangeRuntimeApi<Block, AccountId, Hash, BlockNumber, u128, dex::OrderStatus>,
+    C::Api: amm_rpc::AmmRuntimeApi<Block, Hash, Balance>,
     <C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
     P: TransactionPool + 'static,
     M: jsonrpc_core::Metadata + Default,
@@ -99,6 +101,8 @@ where
     use substrate_frame_rpc_system::{FullSystem, SystemApi};
     // use pallet_contracts_rpc::{Contracts, ContractsApi};
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+    use dex_rpc::{Exchange, ExchangeApi};
+    use amm_rpc::{Amm, AmmApi};
 
     let mut io = jsonrpc_core::IoHandler::default();
     let FullDeps {
@@ -126,6 +130,8 @@ where
     io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(
         client.clone(),
     )));
+    io.extend_with(ExchangeApi::to_delegate(Exchange::new(client.clone())));
+    io.extend_with(AmmApi::to_delegate(Amm::new(client.clone())));
     io.extend_with(sc_consensus_babe_rpc::BabeApi::to_delegate(
         BabeRPCHandler::new(
             client,
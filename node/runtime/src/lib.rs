@@ -29,7 +29,7 @@ use sp_runtime::{create_runtime_str, generic, ApplyExtrinsicResult, Percent};
 use sp_std::prelude::*;
 use support::{
     construct_runtime, debug, parameter_types,
-    traits::{Currency, Imbalance, OnUnbalanced, Randomness, SplitTwoWays},
+    traits::{Currency, Get, Imbalance, OnUnbalanced, Randomness, SplitTwoWays},
     weights::Weight,
 };
 use system::offchain::TransactionSubmitter;
@@ -61,8 +61,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to equal spec_version. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 2,
-    impl_version: 2,
+    spec_version: 13,
+    impl_version: 13,
     apis: RUNTIME_API_VERSIONS,
 };
 
@@ -178,7 +178,21 @@ parameter_types! {
     pub const PriceFactor: u128 = 1;
     pub const BlocksPerDay: u32 = 6 * 60 * 24;
     pub const OpenedOrdersArrayCap: u8 = 20;
-    pub const ClosedOrdersArrayCap: u8 = 100;
+    pub const CommitRevealWindow: BlockNumber = 10;
+    pub const UnrevealedCommitmentPenalty: Perbill = Perbill::from_percent(1);
+    pub const OrderGroupCap: u8 = 4;
+    pub const OrderDeposit: Balance = 1 * CENTS;
+    pub const StopOrdersCap: u32 = 256;
+    pub const ListingBond: Balance = 100 * DOLLARS;
+    pub const TwapObservationPeriod: BlockNumber = 100;
+    pub const TwapObservationCap: u32 = 144;
+    pub const OracleFetchInterval: BlockNumber = 10;
+    pub const OracleReportLifetime: BlockNumber = 100;
+    pub const SwapFee: Permill = Permill::from_parts(3_000);
+    pub const MaxRouteHops: u32 = 3;
+    pub const TradingFee: Permill = Permill::from_parts(1_000);
+    pub const MaxAssetStakers: u32 = 1_000;
+    pub const RewardPayoutsPerBlock: u32 = 64;
 }
 
 pub type NegativeImbalance<T> =
@@ -385,7 +399,99 @@ impl dex::Trait for Runtime {
     type PriceFactor = PriceFactor;
     type BlocksPerDay = BlocksPerDay;
     type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
-    type ClosedOrdersArrayCap = ClosedOrdersArrayCap;
+    type GovernanceOrigin =
+        collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type CommitRevealWindow = CommitRevealWindow;
+    type UnrevealedCommitmentPenalty = UnrevealedCommitmentPenalty;
+    type OffchainSignature = Signature;
+    type OffchainPublic = <Signature as traits::Verify>::Signer;
+    type OrderGroupCap = OrderGroupCap;
+    type OrderDeposit = OrderDeposit;
+    type StopOrdersCap = StopOrdersCap;
+    type TwapObservationPeriod = TwapObservationPeriod;
+    type TwapObservationCap = TwapObservationCap;
+    type ReferencePrice = OracleReferencePrice;
+    type ListingBond = ListingBond;
+    type ListingOrigin =
+        collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type ListingSlash = Treasury;
+    type TradingFee = TradingFee;
+    type OnTradingFee = Fees;
+}
+
+/// Centers the price bands of an exchange pair on the oracle price of its feed.
+pub struct OracleReferencePrice;
+
+impl dex::ReferencePrice<Hash, u128> for OracleReferencePrice {
+    fn reference_price(ep_hash: Hash) -> Option<u128> {
+        Oracle::price(ep_hash)
+    }
+}
+
+impl oracle::Trait for Runtime {
+    type Event = Event;
+    type Call = Call;
+    type Price = u128;
+    type SubmitTransaction = TransactionSubmitter<oracle::crypto::Public, Runtime, UncheckedExtrinsic>;
+    type GovernanceOrigin =
+        collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type FetchInterval = OracleFetchInterval;
+    type ReportLifetime = OracleReportLifetime;
+}
+
+impl amm::Trait for Runtime {
+    type Event = Event;
+    type SwapFee = SwapFee;
+    type MaxRouteHops = MaxRouteHops;
+}
+
+impl fees::Trait for Runtime {
+    type Event = Event;
+    type TreasuryAccount = TreasuryAccount;
+    type Stakers = StakingEraStakers;
+    type MaxAssetStakers = MaxAssetStakers;
+    type RewardPayoutsPerBlock = RewardPayoutsPerBlock;
+}
+
+/// Pays the Treasury share of the trading fees to the Treasury.
+pub struct TreasuryAccount;
+
+impl Get<AccountId> for TreasuryAccount {
+    fn get() -> AccountId {
+        Treasury::account_id()
+    }
+}
+
+/// Rewards the validators and nominators of the active era with the fee reward pot, a page for
+/// each validator. As with staking rewards, nominators clipped out of a validator's exposure are
+/// not paid and their part stays in the pot.
+pub struct StakingEraStakers;
+
+impl fees::EraStakers<AccountId, Balance> for StakingEraStakers {
+    fn current_era() -> u32 {
+        Staking::active_era().map_or(0, |era| era.index)
+    }
+
+    fn total_stake(era: u32) -> Balance {
+        Staking::eras_total_stake(era)
+    }
+
+    fn pages(_era: u32) -> u32 {
+        Session::validators().len() as u32
+    }
+
+    fn stakers(era: u32, page: u32) -> Vec<(AccountId, Balance)> {
+        let validator = match Session::validators().get(page as usize) {
+            Some(validator) => validator.clone(),
+            None => return Vec::new(),
+        };
+
+        let exposure = Staking::eras_stakers_clipped(era, &validator);
+        let mut stakers = vec![(validator, exposure.own)];
+        stakers.extend(exposure.others.into_iter().map(|x| (x.who, x.value)));
+
+        stakers
+    }
 }
 
 impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtime {
@@ -564,8 +670,11 @@ construct_runtime!(
         TechnicalCommittee: collective::<Instance2>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
 
         // Custom modules
-        Assets: assets::{Module, Call, Storage,Event<T>},
-        Dex: dex::{Module,Call,Storage,Event<T>},
+        Assets: assets::{Module, Call, Storage, Event<T>, Config<T>},
+        Dex: dex::{Module, Call, Storage, Event<T>, Config<T>},
+        Oracle: oracle::{Module, Call, Storage, Event<T>},
+        Amm: amm::{Module, Call, Storage, Event<T>},
+        Fees: fees::{Module, Call, Storage, Event<T>, Config},
 
         // Utility module
         Utility: utility::{Module, Call, Event<T>},
@@ -751,6 +860,75 @@ impl_runtime_apis! {
         }
     }
 
+    impl dex_runtime_api::ExchangeApi<
+        Block,
+        AccountId,
+        Hash,
+        BlockNumber,
+        u128,
+        dex::OrderStatus,
+    > for Runtime {
+        fn stop_order_trigger_price(order_hash: Hash) -> Option<u128> {
+            Dex::stop_order_trigger_price(order_hash)
+        }
+
+        fn twap(ep_hash: Hash, window: BlockNumber) -> Option<u128> {
+            Dex::time_weighted_average_price(ep_hash, window)
+        }
+
+        fn open_orders(owner: AccountId, ep_hash: Hash) -> Vec<Hash> {
+            Dex::account_open_orders(owner, ep_hash)
+        }
+
+        fn order_history(
+            owner: AccountId,
+            ep_hash: Hash,
+            status: Option<dex::OrderStatus>,
+            from_block: Option<BlockNumber>,
+            to_block: Option<BlockNumber>,
+            start: u64,
+            limit: u32,
+        ) -> dex_runtime_api::OrderPage<Hash> {
+            let (orders, next) = Dex::account_order_history(
+                owner, ep_hash, status, from_block, to_block, start, limit,
+            );
+            dex_runtime_api::OrderPage { orders, next }
+        }
+    }
+
+    impl amm_runtime_api::AmmApi<Block, Hash, Balance> for Runtime {
+        fn best_route(
+            asset_in: Hash,
+            asset_out: Hash,
+            amount_in: Balance,
+        ) -> Option<amm_runtime_api::Route<Hash, Balance>> {
+            Amm::best_route(asset_in, asset_out, amount_in)
+                .map(|(path, amount_out)| amm_runtime_api::Route { path, amount_out })
+        }
+    }
+
+    impl frame_benchmarking::Benchmark<Block> for Runtime {
+        fn dispatch_benchmark(
+            module: Vec<u8>,
+            extrinsic: Vec<u8>,
+            steps: u32,
+            repeat: u32,
+        ) -> Option<Vec<frame_benchmarking::BenchmarkResults>> {
+            #[cfg(feature = "runtime-benchmarks")]
+            use frame_benchmarking::Benchmarking;
+
+            match module.as_slice() {
+                #[cfg(feature = "runtime-benchmarks")]
+                b"dna-exchange" | b"dex" => Dex::run_benchmark(extrinsic, steps, repeat).ok(),
+                _ => {
+                    // only read by the benchmarks of the `runtime-benchmarks` feature
+                    let _ = (extrinsic, steps, repeat);
+                    None
+                }
+            }
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             SessionKeys::generate(seed)
@@ -15,7 +15,11 @@ module.exports = {
       hash: "H256",
       base: "H256",
       quote: "H256",
-      latest_matched_price: "Option<Price>"
+      latest_matched_price: "Option<Price>",
+      matching_mode: "MatchingMode"
+    },
+    MatchingMode: {
+      _enum: ["Continuous", "BatchAuction"]
     },
     Price: "u128",
     LimitOrder: {
@@ -29,7 +33,129 @@ module.exports = {
       remained_sell_amount: "Balance",
       remained_buy_amount: "Balance",
       otype: "OrderType",
-      status: "OrderStatus"
+      status: "OrderStatus",
+      iceberg: "Option<Iceberg>",
+      group: "Option<H256>",
+      sizing: "OrderSizing"
+    },
+    OrderSizing: {
+      _enum: ["Spend", "Receive"]
+    },
+    OrderGroup: {
+      hash: "H256",
+      owner: "AccountId",
+      ep_hash: "H256",
+      asset: "H256",
+      frozen_amount: "Balance",
+      members: "Vec<H256>"
+    },
+    Iceberg: {
+      display_amount: "Balance",
+      visible_sell_amount: "Balance"
+    },
+    OrderCommitment: {
+      owner: "AccountId",
+      asset: "H256",
+      collateral: "Balance",
+      committed_at: "BlockNumber",
+      expires_at: "BlockNumber"
+    },
+    SignedOrder: {
+      owner: "AccountId",
+      base: "H256",
+      quote: "H256",
+      otype: "OrderType",
+      price: "Price",
+      sell_amount: "Balance",
+      expires_at: "BlockNumber",
+      salt: "u64"
+    },
+    TrailDistance: {
+      _enum: {
+        Absolute: "Price",
+        Percent: "Permill"
+      }
+    },
+    StopOrder: {
+      hash: "H256",
+      base: "H256",
+      quote: "H256",
+      owner: "AccountId",
+      otype: "OrderType",
+      sell_amount: "Balance",
+      trail: "Option<TrailDistance>",
+      limit_price: "Option<Price>",
+      reference_price: "Price",
+      trigger_price: "Price",
+      group: "Option<H256>"
+    },
+    CircuitBreaker: {
+      band_bps: "u32",
+      halt_move_bps: "u32",
+      window: "BlockNumber",
+      cooldown: "BlockNumber"
+    },
+    PriceAccumulator: {
+      price_cumulative: "U256",
+      latest_price: "Price",
+      updated_at: "BlockNumber"
+    },
+    PriceObservation: {
+      block: "BlockNumber",
+      price_cumulative: "U256"
+    },
+    PriceReport: {
+      oracle: "AccountId",
+      price: "Price",
+      reported_at: "BlockNumber"
+    },
+    Pool: {
+      hash: "H256",
+      account: "AccountId",
+      base: "H256",
+      quote: "H256",
+      base_reserve: "Balance",
+      quote_reserve: "Balance",
+      share_asset: "H256"
+    },
+    PoolOf: "Pool",
+    Route: {
+      path: "Vec<H256>",
+      amount_out: "Balance"
+    },
+    ListingPolicy: {
+      _enum: ["AssetOwner", "Bonded", "Governance"]
+    },
+    ListingApplication: {
+      hash: "H256",
+      applicant: "AccountId",
+      base: "H256",
+      quote: "H256",
+      matching_mode: "MatchingMode",
+      bond: "Balance",
+      applied_at: "BlockNumber"
+    },
+    RewardTarget: {
+      _enum: {
+        Stakers: null,
+        AssetHolders: "H256"
+      }
+    },
+    RewardPayout: {
+      era: "u32",
+      target: "RewardTarget",
+      total_stake: "Balance",
+      assets: "Vec<(H256, Balance, Balance)>",
+      pages: "u32",
+      next_page: "u32",
+      next_recipient: "u32"
+    },
+    OrderPage: {
+      orders: "Vec<H256>",
+      next: "Option<u64>"
+    },
+    Releases: {
+      _enum: ["V1_0_0", "V1_1_0", "V1_2_0", "V1_3_0", "V2_0_0", "V3_0_0", "V4_0_0", "V5_0_0", "V6_0_0"]
     },
     Dex: {
       hash: "H256",
@@ -48,7 +174,15 @@ module.exports = {
       prev: "Option<Price>",
       next: "Option<Price>",
       price: "Option<Price>",
-      orders: "Vec<H256>"
+      buy_amount: "Balance",
+      sell_amount: "Balance",
+      first_order: "Option<H256>",
+      last_order: "Option<H256>",
+      order_count: "u32"
+    },
+    LevelOrderNode: {
+      prev: "Option<H256>",
+      next: "Option<H256>"
     }
   }
 };
@@ -0,0 +1,44 @@
+// Local stand-in for the price endpoints of the oracle module. Serves the price of each
+// feed at `/<name>` from a JSON file of `{ "<name>": <price> }`, reread on every request
+// so prices can be changed while a node is running.
+const http = require("http");
+const fs = require("fs");
+const program = require("commander");
+const pkg = require("./package.json");
+
+program
+  .version(pkg.version, "-v, --version")
+  .option("--port <number>", "port to listen on", 8000)
+  .option("--prices <string>", "json file of the feed prices", "./prices.json")
+  .parse(process.argv);
+
+function readPrices(file) {
+  return JSON.parse(fs.readFileSync(file));
+}
+
+const server = http.createServer((req, res) => {
+  let prices;
+  try {
+    prices = readPrices(program.prices);
+  } catch (e) {
+    res.writeHead(500);
+    res.end();
+    console.error(`unable to read ${program.prices}: ${e.message}`);
+    return;
+  }
+
+  const name = req.url.replace(/^\//, "");
+  if (req.method !== "GET" || !(name in prices)) {
+    res.writeHead(404);
+    res.end();
+    return;
+  }
+
+  res.writeHead(200, { "Content-Type": "text/plain" });
+  res.end(String(prices[name]));
+  console.log(`${name}: ${prices[name]}`);
+});
+
+server.listen(program.port, "127.0.0.1", () => {
+  console.log(`serving ${program.prices} on http://127.0.0.1:${program.port}`);
+});